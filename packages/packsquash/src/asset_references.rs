@@ -0,0 +1,567 @@
+//! Contains the reference analysis that finds the textures and sounds of a pack that no other
+//! pack file refers to, which are likely unused, and the references of pack files to assets
+//! that do not exist, which are likely mistakes.
+
+use std::{io, slice};
+
+use ahash::HashSet;
+use json_comments::StripComments;
+use serde_json::Value;
+use tokio::io::AsyncReadExt;
+
+use crate::RelativePath;
+use crate::config::ProcessedSquashOptions;
+use crate::pack_file::strip_utf8_bom;
+use crate::pack_metadata::{PackLayerDirectoryName, PackLayerPathPrefixes};
+use crate::vfs::{IteratorTraversalOptions, VirtualFileSystem};
+
+#[cfg(test)]
+mod tests;
+
+/// The namespace of the resource locations that do not specify one. The game itself may refer
+/// to any asset in this namespace, so they are always considered referenced. Likewise, the game
+/// provides assets in this namespace, so references to them are never considered dangling.
+const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// The texture directories that the texture atlases built into the game include every texture
+/// of, no matter their namespace.
+const BUILT_IN_ATLAS_TEXTURE_DIRECTORIES: [&str; 8] = [
+	"block/",
+	"item/",
+	"particle/",
+	"painting/",
+	"mob_effect/",
+	"gui/sprites/",
+	"map/decorations/",
+	"trims/"
+];
+
+/// The extensions of the audio files that may be referred to as sounds.
+const SOUND_EXTENSIONS: [&str; 6] = ["ogg", "oga", "mp3", "flac", "wav", "m4a"];
+
+/// The results of analyzing the references between the files of a pack.
+pub(crate) struct AssetReferencesAnalysis {
+	/// The relative paths of the textures and sounds that no model, texture atlas, font or sound
+	/// events file of the pack refers to, and are not matched by the allowlist glob pattern,
+	/// sorted in ascending order.
+	pub(crate) unreferenced_assets: Vec<RelativePath<'static>>,
+	/// The references to assets that do not exist in the pack, sorted in ascending order by
+	/// referring file and referred path.
+	pub(crate) dangling_references: Vec<DanglingReference>
+}
+
+/// A reference of a pack file to an asset that does not exist in the pack.
+#[derive(PartialEq, Eq)]
+pub(crate) struct DanglingReference {
+	/// The relative path of the pack file that contains the reference.
+	pub(crate) referring_file: RelativePath<'static>,
+	/// The path, relative to its pack layer, where the referred asset was expected to be.
+	pub(crate) referred_path: String
+}
+
+/// Analyzes the references that the models, blockstates, texture atlases, fonts and sound events
+/// files of the pack contain, resolving them within and across every one of the specified pack
+/// layers.
+///
+/// Referring files that can't be parsed are considered to refer to nothing, as the game would
+/// not be able to read them either.
+pub(crate) async fn analyze_asset_references<'layers>(
+	vfs: &impl VirtualFileSystem,
+	options_holder: &ProcessedSquashOptions,
+	pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName>
+) -> Result<AssetReferencesAnalysis, io::Error> {
+	let layer_path_prefixes = PackLayerPathPrefixes::new(pack_layer_directory_names);
+
+	let pack_files = vfs
+		.file_iterator(
+			&options_holder.options.pack_directory,
+			IteratorTraversalOptions {
+				ignore_system_and_hidden_files: options_holder
+					.options
+					.global_options
+					.ignore_system_and_hidden_files
+			}
+		)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut referring_files = vec![];
+	let mut file_buf = vec![];
+	for pack_file in &pack_files {
+		let Some(referring_file) = ReferringFile::of(
+			layer_path_prefixes.layer_relative_path(pack_file.relative_path.as_str())
+		) else {
+			continue;
+		};
+
+		file_buf.clear();
+		vfs.open(&pack_file.file_path)?
+			.file_read
+			.read_to_end(&mut file_buf)
+			.await?;
+
+		if let Ok(json) = serde_json::from_reader(StripComments::new(strip_utf8_bom(&file_buf))) {
+			referring_files.push((
+				&pack_file.relative_path,
+				referring_file.references_in(&json)
+			));
+		}
+	}
+
+	let mut references = AssetReferences::default();
+	for reference in referring_files
+		.iter()
+		.flat_map(|(_, references)| references)
+	{
+		references.add(reference);
+	}
+
+	let mut unreferenced_assets = pack_files
+		.iter()
+		.map(|pack_file| &pack_file.relative_path)
+		.filter(|relative_path| {
+			Asset::of(layer_path_prefixes.layer_relative_path(relative_path.as_str()))
+				.is_some_and(|asset| !references.refer_to(&asset))
+				&& !options_holder
+					.unreferenced_assets_allowlist
+					.as_ref()
+					.is_some_and(|allowlist| allowlist.is_match(relative_path))
+		})
+		.cloned()
+		.collect::<Vec<_>>();
+	unreferenced_assets.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+
+	// Sounds are referred to without their extension, and may be referred to through any
+	// pack layer
+	let mut existing_paths = HashSet::default();
+	for pack_file in &pack_files {
+		let layer_relative_path =
+			layer_path_prefixes.layer_relative_path(pack_file.relative_path.as_str());
+		existing_paths.insert(layer_relative_path);
+
+		if let Some(asset) = Asset::of(layer_relative_path)
+			&& asset.texture_path.is_none()
+		{
+			existing_paths.insert(asset.reference_path);
+		}
+	}
+	for reference in referring_files
+		.iter()
+		.flat_map(|(_, references)| references)
+	{
+		if let Reference::GeneratedTexture(path) = reference {
+			existing_paths.insert(path.as_str());
+		}
+	}
+
+	let existing_paths = &existing_paths;
+	let mut dangling_references = referring_files
+		.iter()
+		.flat_map(|(referring_file, references)| {
+			references
+				.iter()
+				.filter_map(|reference| reference.required_path())
+				.filter(move |(path, _)| {
+					namespace_of(path) != DEFAULT_NAMESPACE
+						&& !existing_paths.contains(path)
+						// Files with the comments extension are added to the pack without it
+						&& !existing_paths.contains(format!("{path}c").as_str())
+				})
+				.map(move |(path, extension)| DanglingReference {
+					referring_file: (*referring_file).clone(),
+					referred_path: format!("{path}{extension}")
+				})
+		})
+		.collect::<Vec<_>>();
+	dangling_references.sort_unstable_by(|a, b| {
+		(a.referring_file.as_str(), &a.referred_path)
+			.cmp(&(b.referring_file.as_str(), &b.referred_path))
+	});
+	dangling_references.dedup();
+
+	Ok(AssetReferencesAnalysis {
+		unreferenced_assets,
+		dangling_references
+	})
+}
+
+/// A kind of JSON pack file that may refer to other assets.
+#[derive(Clone, Copy)]
+enum ReferringFile {
+	/// A block or item model, whose parent model and textures are referred to by resource
+	/// location.
+	Model,
+	/// A blockstate definition, whose variants refer to models.
+	BlockState,
+	/// A texture atlas definition, whose sources refer to textures or texture directories.
+	TextureAtlas,
+	/// A font definition, whose bitmap providers refer to textures.
+	Font,
+	/// A sound events file, `sounds.json`, which refers to sounds.
+	SoundEvents
+}
+
+impl ReferringFile {
+	/// Returns the kind of referring file at the specified path, relative to its pack layer,
+	/// if it is a referring file.
+	fn of(layer_relative_path: &str) -> Option<Self> {
+		let mut path_components = layer_relative_path.split('/');
+		let (Some("assets"), Some(_namespace), Some(asset_directory)) = (
+			path_components.next(),
+			path_components.next(),
+			path_components.next()
+		) else {
+			return None;
+		};
+		let is_json =
+			layer_relative_path.ends_with(".json") || layer_relative_path.ends_with(".jsonc");
+
+		match asset_directory {
+			"sounds.json" | "sounds.jsonc" => Some(Self::SoundEvents),
+			"models" if is_json => Some(Self::Model),
+			"blockstates" if is_json => Some(Self::BlockState),
+			"atlases" if is_json => Some(Self::TextureAtlas),
+			"font" if is_json => Some(Self::Font),
+			_ => None
+		}
+	}
+
+	/// Returns the references contained in the parsed JSON data of this referring file.
+	fn references_in(self, json: &Value) -> Vec<Reference> {
+		let mut references = vec![];
+
+		match self {
+			Self::Model => {
+				if let Some(parent) = json["parent"].as_str() {
+					references.push(Reference::Model(resource_path(parent, "models", ".json")));
+				}
+
+				for texture in json["textures"]
+					.as_object()
+					.into_iter()
+					.flat_map(|textures| textures.values())
+					.filter_map(Value::as_str)
+				{
+					// Texture variables refer to other textures of the model
+					if !texture.starts_with('#') {
+						references.push(Reference::texture(texture, ".png"));
+					}
+				}
+			}
+			Self::BlockState => {
+				// Each variant or multipart case may be a single model or a list of models to
+				// randomly choose from
+				for model in json["variants"]
+					.as_object()
+					.into_iter()
+					.flat_map(|variants| variants.values())
+					.chain(
+						json["multipart"]
+							.as_array()
+							.into_iter()
+							.flatten()
+							.map(|case| &case["apply"])
+					)
+					.flat_map(|models| {
+						models
+							.as_array()
+							.map_or(slice::from_ref(models), Vec::as_slice)
+					})
+					.filter_map(|model| model["model"].as_str())
+				{
+					references.push(Reference::Model(resource_path(model, "models", ".json")));
+				}
+			}
+			Self::TextureAtlas => {
+				for source in json["sources"].as_array().into_iter().flatten() {
+					match type_of(source) {
+						"single" => {
+							if let Some(texture) = source["resource"].as_str() {
+								references.push(Reference::texture(texture, ".png"));
+							}
+						}
+						"unstitch" => {
+							if let Some(texture) = source["resource"].as_str() {
+								references.push(Reference::texture(texture, ".png"));
+							}
+
+							for sprite in source["regions"]
+								.as_array()
+								.into_iter()
+								.flatten()
+								.filter_map(|region| region["sprite"].as_str())
+							{
+								references.push(Reference::GeneratedTexture(resource_path(
+									sprite, "textures", ".png"
+								)));
+							}
+						}
+						"directory" => {
+							if let Some(directory) = source["source"].as_str() {
+								references.push(Reference::TextureDirectory(format!(
+									"{}/",
+									directory.trim_end_matches('/')
+								)));
+							}
+						}
+						"paletted_permutations" => {
+							let permutations = source["permutations"].as_object();
+							let separator = source["separator"].as_str().unwrap_or("_");
+
+							for texture in source["textures"]
+								.as_array()
+								.into_iter()
+								.flatten()
+								.filter_map(Value::as_str)
+							{
+								references.push(Reference::texture(texture, ".png"));
+
+								// Every permutation of a texture is a new texture
+								for permutation in permutations
+									.into_iter()
+									.flat_map(|permutations| permutations.keys())
+								{
+									references.push(Reference::GeneratedTexture(resource_path(
+										&format!("{texture}{separator}{permutation}"),
+										"textures",
+										".png"
+									)));
+								}
+							}
+
+							for palette in [&source["palette_key"]]
+								.into_iter()
+								.chain(
+									permutations
+										.into_iter()
+										.flat_map(|permutations| permutations.values())
+								)
+								.filter_map(Value::as_str)
+							{
+								references.push(Reference::texture(palette, ".png"));
+							}
+						}
+						_ => {}
+					}
+				}
+			}
+			Self::Font => {
+				for provider in json["providers"].as_array().into_iter().flatten() {
+					match type_of(provider) {
+						// Bitmap font textures are referred to with their extension
+						"bitmap" => {
+							if let Some(texture) = provider["file"].as_str() {
+								references.push(Reference::texture(texture, ""));
+							}
+						}
+						// Legacy Unicode fonts refer to a texture for each of the 256 pages of
+						// the Basic Multilingual Plane, but pages without glyphs may be missing
+						"legacy_unicode" => {
+							if let Some(texture_template) = provider["template"].as_str() {
+								for page in 0..=u8::MAX {
+									references.push(Reference::Texture {
+										path: resource_path(
+											&texture_template.replace("%s", &format!("{page:02x}")),
+											"textures",
+											""
+										),
+										optional: true
+									});
+								}
+							}
+						}
+						_ => {}
+					}
+				}
+			}
+			Self::SoundEvents => {
+				for sound in json
+					.as_object()
+					.into_iter()
+					.flat_map(|sound_events| sound_events.values())
+					.flat_map(|sound_event| sound_event["sounds"].as_array())
+					.flatten()
+				{
+					match sound {
+						Value::String(sound) => {
+							references.push(Reference::Sound(resource_path(sound, "sounds", "")));
+						}
+						// Sounds of the event type refer to other sound events, not sounds
+						Value::Object(_) if matches!(sound["type"].as_str(), None | Some("file")) => {
+							if let Some(sound) = sound["name"].as_str() {
+								references.push(Reference::Sound(resource_path(sound, "sounds", "")));
+							}
+						}
+						_ => {}
+					}
+				}
+			}
+		}
+
+		references
+	}
+}
+
+/// Something a referring file refers to or defines.
+enum Reference {
+	/// A texture, by its path relative to its pack layer. Optional textures may be missing
+	/// without consequences.
+	Texture { path: String, optional: bool },
+	/// Every texture within a directory, by its path relative to the textures directory of any
+	/// namespace, with a trailing slash.
+	TextureDirectory(String),
+	/// A texture that a texture atlas generates from other textures, and thus may not exist as
+	/// a file, by its path relative to its pack layer.
+	GeneratedTexture(String),
+	/// A sound, by its path relative to its pack layer, without extension.
+	Sound(String),
+	/// A model, by its path relative to its pack layer.
+	Model(String)
+}
+
+impl Reference {
+	/// Returns a reference to the required texture with the specified resource location, which
+	/// is relative to the textures directory of its namespace, and must have the specified
+	/// extension appended to point to a texture file.
+	fn texture(resource_location: &str, extension: &str) -> Self {
+		Self::Texture {
+			path: resource_path(resource_location, "textures", extension),
+			optional: false
+		}
+	}
+
+	/// Returns the path, relative to its pack layer, of the file that must exist for this
+	/// reference not to be dangling, if any, along with the extension that file is expected to
+	/// have when the path lacks it.
+	fn required_path(&self) -> Option<(&str, &'static str)> {
+		match self {
+			Self::Texture {
+				path,
+				optional: false
+			}
+			| Self::Model(path) => Some((path.as_str(), "")),
+			// The game only reads Ogg Vorbis sounds, which are expected to have this extension
+			Self::Sound(path) => Some((path.as_str(), ".ogg")),
+			Self::Texture { optional: true, .. }
+			| Self::TextureDirectory(_)
+			| Self::GeneratedTexture(_) => None
+		}
+	}
+}
+
+/// The textures and sounds referred to by the referring files of a pack.
+#[derive(Default)]
+struct AssetReferences<'references> {
+	/// The paths, relative to their pack layer, of the referred textures, and the referred sounds
+	/// without their extension.
+	paths: HashSet<&'references str>,
+	/// The texture directories whose every texture is referred to, relative to the textures
+	/// directory of any namespace, with a trailing slash.
+	texture_directories: Vec<&'references str>
+}
+
+impl<'references> AssetReferences<'references> {
+	/// Adds a reference of some referring file to these references.
+	fn add(&mut self, reference: &'references Reference) {
+		match reference {
+			Reference::Texture { path, .. } | Reference::Sound(path) => {
+				self.paths.insert(path.as_str());
+			}
+			Reference::TextureDirectory(texture_directory) => {
+				self.texture_directories.push(texture_directory.as_str());
+			}
+			Reference::GeneratedTexture(_) | Reference::Model(_) => {}
+		}
+	}
+
+	/// Checks whether these references refer to the specified asset.
+	fn refer_to(&self, asset: &Asset<'_>) -> bool {
+		asset.namespace == DEFAULT_NAMESPACE
+			|| self.paths.contains(asset.reference_path)
+			|| asset.texture_path.is_some_and(|texture_path| {
+				BUILT_IN_ATLAS_TEXTURE_DIRECTORIES
+					.iter()
+					.chain(&self.texture_directories)
+					.any(|texture_directory| texture_path.starts_with(texture_directory))
+			})
+	}
+}
+
+/// A texture or sound that may be left unreferenced by other pack files.
+struct Asset<'path> {
+	/// The namespace of the asset.
+	namespace: &'path str,
+	/// The path the asset is referred to by, relative to its pack layer. Sounds are referred to
+	/// without their extension, and texture metadata files are referred to by their texture.
+	reference_path: &'path str,
+	/// For textures, the path of the texture relative to the textures directory of its
+	/// namespace.
+	texture_path: Option<&'path str>
+}
+
+impl<'path> Asset<'path> {
+	/// Returns the asset at the specified path, relative to its pack layer, if it is a texture,
+	/// texture metadata or sound file.
+	fn of(layer_relative_path: &'path str) -> Option<Self> {
+		let (namespace, asset_path) = layer_relative_path
+			.strip_prefix("assets/")?
+			.split_once('/')?;
+
+		let (stripped_suffix_length, texture_path) =
+			if let Some(texture_path) = asset_path.strip_prefix("textures/") {
+				// Texture metadata files are only used when their textures are
+				let texture_file_path = texture_path
+					.strip_suffix(".mcmeta")
+					.or_else(|| texture_path.strip_suffix(".mcmetac"))
+					.unwrap_or(texture_path);
+				if !texture_file_path.ends_with(".png") {
+					return None;
+				}
+
+				(
+					texture_path.len() - texture_file_path.len(),
+					Some(texture_file_path)
+				)
+			} else if let Some(sound_path) = asset_path.strip_prefix("sounds/") {
+				let (_, extension) = sound_path.rsplit_once('.')?;
+				if !SOUND_EXTENSIONS.contains(&extension) {
+					return None;
+				}
+
+				(extension.len() + 1, None)
+			} else {
+				return None;
+			};
+
+		Some(Self {
+			namespace,
+			reference_path: &layer_relative_path
+				[..layer_relative_path.len() - stripped_suffix_length],
+			texture_path
+		})
+	}
+}
+
+/// Returns the path, relative to its pack layer, of the asset with the specified resource
+/// location, which is relative to the specified asset directory of its namespace, and must have
+/// the specified extension appended to point to a file. Resource locations that do not specify
+/// a namespace default to the `minecraft` namespace.
+fn resource_path(resource_location: &str, asset_directory: &str, extension: &str) -> String {
+	let (namespace, path) = resource_location
+		.split_once(':')
+		.unwrap_or((DEFAULT_NAMESPACE, resource_location));
+
+	format!("assets/{namespace}/{asset_directory}/{path}{extension}")
+}
+
+/// Returns the namespace of the asset at the specified path, relative to its pack layer.
+fn namespace_of(layer_relative_path: &str) -> &str {
+	layer_relative_path.split('/').nth(1).unwrap_or_default()
+}
+
+/// Returns the type of the specified texture atlas source or font provider, without the
+/// optional `minecraft` namespace.
+fn type_of(json: &Value) -> &str {
+	json["type"].as_str().map_or("", |json_type| {
+		json_type.strip_prefix("minecraft:").unwrap_or(json_type)
+	})
+}
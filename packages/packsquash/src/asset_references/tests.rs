@@ -0,0 +1,284 @@
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+
+use crate::config::{GlobalOptions, ProcessedSquashOptions, SquashOptions};
+use crate::pack_metadata::PackLayerDirectoryName;
+use crate::vfs::memory_fs::MemoryFilesystem;
+
+use super::{AssetReferencesAnalysis, analyze_asset_references};
+
+/// Convenience façade for [`analyze_asset_references`] over an in-memory virtual file system
+/// that contains the given files.
+async fn analyze<'a>(
+	files: impl IntoIterator<Item = (&'a str, &'static str)>,
+	pack_layer_directory_names: &[PackLayerDirectoryName],
+	allowlist: Option<&str>
+) -> AssetReferencesAnalysis {
+	let mut vfs = MemoryFilesystem::new();
+	for (path, data) in files {
+		vfs.add_file(path, data, None)
+			.expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let options_holder = ProcessedSquashOptions::try_from(SquashOptions {
+		pack_directory: PathBuf::new(),
+		global_options: GlobalOptions {
+			unreferenced_assets_allowlist: allowlist.map(str::to_string),
+			..Default::default()
+		},
+		file_options: IndexMap::new()
+	})
+	.expect("The test options should be valid");
+
+	analyze_asset_references(&vfs, &options_holder, pack_layer_directory_names.iter())
+		.await
+		.expect("I/O operations are assumed not to fail during tests")
+}
+
+/// Returns the relative paths of the unreferenced assets among the given files.
+async fn unreferenced_assets<'a>(
+	files: impl IntoIterator<Item = (&'a str, &'static str)>,
+	pack_layer_directory_names: &[PackLayerDirectoryName],
+	allowlist: Option<&str>
+) -> Vec<String> {
+	analyze(files, pack_layer_directory_names, allowlist)
+		.await
+		.unreferenced_assets
+		.into_iter()
+		.map(|relative_path| relative_path.as_str().to_string())
+		.collect()
+}
+
+/// Returns the dangling references among the given files, as pairs of referring file relative
+/// path and referred path.
+async fn dangling_references<'a>(
+	files: impl IntoIterator<Item = (&'a str, &'static str)>,
+	pack_layer_directory_names: &[PackLayerDirectoryName]
+) -> Vec<(String, String)> {
+	analyze(files, pack_layer_directory_names, None)
+		.await
+		.dangling_references
+		.into_iter()
+		.map(|dangling_reference| {
+			(
+				dangling_reference.referring_file.as_str().to_string(),
+				dangling_reference.referred_path
+			)
+		})
+		.collect()
+}
+
+#[tokio::test]
+async fn unreferenced_textures_and_sounds_are_found() {
+	assert_eq!(
+		unreferenced_assets(
+			[
+				(
+					"assets/pack/models/item/wand.json",
+					r##"{ "textures": { "layer0": "pack:item_parts/wand", "particle": "#layer0" } }"##
+				),
+				(
+					"assets/pack/atlases/blocks.json",
+					r#"{ "sources": [{ "type": "minecraft:single", "resource": "pack:misc/glint" }] }"#
+				),
+				(
+					"assets/pack/font/runes.json",
+					r#"{ "providers": [{ "type": "bitmap", "file": "pack:font/runes.png" }] }"#
+				),
+				(
+					"assets/pack/sounds.json",
+					r#"{ "zap": { "sounds": ["pack:zap", { "name": "zap_event", "type": "event" }] } }"#
+				),
+				("assets/pack/textures/item_parts/wand.png", ""),
+				("assets/pack/textures/item_parts/wand.png.mcmeta", ""),
+				("assets/pack/textures/item_parts/unused.png", ""),
+				("assets/pack/textures/item_parts/unused.png.mcmeta", ""),
+				("assets/pack/textures/misc/glint.png", ""),
+				("assets/pack/textures/font/runes.png", ""),
+				("assets/pack/textures/block/stone.png", ""),
+				("assets/pack/sounds/zap.ogg", ""),
+				("assets/pack/sounds/zap_event.ogg", ""),
+				("assets/pack/sounds/unused.wav", ""),
+				("assets/minecraft/textures/entity/unused.png", ""),
+				("assets/minecraft/sounds/unused.ogg", "")
+			],
+			&[arcstr::literal!("")],
+			None
+		)
+		.await,
+		[
+			"assets/pack/sounds/unused.wav",
+			"assets/pack/sounds/zap_event.ogg",
+			"assets/pack/textures/item_parts/unused.png",
+			"assets/pack/textures/item_parts/unused.png.mcmeta"
+		]
+	);
+}
+
+#[tokio::test]
+async fn references_are_resolved_across_pack_layers() {
+	assert_eq!(
+		unreferenced_assets(
+			[
+				(
+					"overlay/assets/pack/atlases/blocks.json",
+					r#"{ "sources": [{ "type": "directory", "source": "custom", "prefix": "custom/" }] }"#
+				),
+				(
+					"assets/pack/models/block/lamp.jsonc",
+					r#"{
+						// The lamp texture is only in the overlay
+						"textures": { "all": "pack:lamp" }
+					}"#
+				),
+				("assets/pack/textures/custom/a.png", ""),
+				("overlay/assets/pack/textures/lamp.png", ""),
+				("overlay/assets/pack/textures/unused.png", "")
+			],
+			&[arcstr::literal!(""), arcstr::literal!("overlay")],
+			None
+		)
+		.await,
+		["overlay/assets/pack/textures/unused.png"]
+	);
+}
+
+#[tokio::test]
+async fn allowlisted_assets_are_not_unreferenced() {
+	assert_eq!(
+		unreferenced_assets(
+			[
+				("assets/pack/textures/code/a.png", ""),
+				("assets/pack/textures/unused.png", ""),
+				("assets/pack/sounds/code/b.ogg", "")
+			],
+			&[arcstr::literal!("")],
+			Some("assets/pack/{textures,sounds}/code/**")
+		)
+		.await,
+		["assets/pack/textures/unused.png"]
+	);
+}
+
+#[tokio::test]
+async fn malformed_referring_files_refer_to_nothing() {
+	assert_eq!(
+		unreferenced_assets(
+			[
+				(
+					"assets/pack/models/item/broken.json",
+					r#"{ "textures": { "all": "pack:broken" "#
+				),
+				("assets/pack/textures/broken.png", "")
+			],
+			&[arcstr::literal!("")],
+			None
+		)
+		.await,
+		["assets/pack/textures/broken.png"]
+	);
+}
+
+#[tokio::test]
+async fn dangling_references_are_found() {
+	assert_eq!(
+		dangling_references(
+			[
+				(
+					"assets/pack/blockstates/lamp.json",
+					r#"{
+						"variants": {
+							"lit=false": { "model": "pack:block/lamp" },
+							"lit=true": [{ "model": "pack:block/lamp_lit" }, { "model": "block/stone" }]
+						}
+					}"#
+				),
+				(
+					"assets/pack/blockstates/pipe.json",
+					r#"{ "multipart": [{ "apply": { "model": "pack:block/pipe" } }] }"#
+				),
+				(
+					"assets/pack/models/block/lamp.json",
+					r#"{ "parent": "pack:block/base", "textures": { "all": "pack:block/lamp" } }"#
+				),
+				("assets/pack/models/block/base.jsonc", "{}"),
+				(
+					"assets/pack/models/block/pipe.json",
+					r##"{ "parent": "block/cube_all", "textures": { "all": "pack:pipe", "particle": "#all" } }"##
+				),
+				(
+					"assets/pack/atlases/blocks.json",
+					r#"{ "sources": [{ "type": "single", "resource": "pack:glint" }] }"#
+				),
+				(
+					"assets/pack/sounds.json",
+					r#"{ "hum": { "sounds": ["pack:hum", { "name": "pack:buzz" }, "ambient/cave/cave1"] } }"#
+				),
+				("assets/pack/textures/block/lamp.png", ""),
+				("assets/pack/sounds/hum.wav", "")
+			],
+			&[arcstr::literal!("")]
+		)
+		.await,
+		[
+			(
+				"assets/pack/atlases/blocks.json".to_string(),
+				"assets/pack/textures/glint.png".to_string()
+			),
+			(
+				"assets/pack/blockstates/lamp.json".to_string(),
+				"assets/pack/models/block/lamp_lit.json".to_string()
+			),
+			(
+				"assets/pack/blockstates/pipe.json".to_string(),
+				"assets/pack/models/block/pipe.json".to_string()
+			),
+			(
+				"assets/pack/models/block/pipe.json".to_string(),
+				"assets/pack/textures/pipe.png".to_string()
+			),
+			(
+				"assets/pack/sounds.json".to_string(),
+				"assets/pack/sounds/buzz.ogg".to_string()
+			)
+		]
+	);
+}
+
+#[tokio::test]
+async fn generated_textures_and_other_layers_resolve_references() {
+	assert_eq!(
+		dangling_references(
+			[
+				(
+					"assets/pack/atlases/armor_trims.json",
+					r#"{
+						"sources": [{
+							"type": "paletted_permutations",
+							"textures": ["pack:trims/bolt"],
+							"palette_key": "pack:trims/palettes/key",
+							"permutations": { "gold": "pack:trims/palettes/gold" }
+						}]
+					}"#
+				),
+				(
+					"overlay/assets/pack/models/item/bolt.json",
+					r#"{ "textures": { "layer0": "pack:trims/bolt_gold", "layer1": "pack:item/bolt" } }"#
+				),
+				(
+					"assets/pack/font/legacy.json",
+					r#"{ "providers": [{ "type": "legacy_unicode", "template": "pack:font/page_%s.png" }] }"#
+				),
+				("assets/pack/textures/trims/bolt.png", ""),
+				("assets/pack/textures/trims/palettes/key.png", ""),
+				("overlay/assets/pack/textures/trims/palettes/gold.png", ""),
+				("overlay/assets/pack/textures/item/bolt.png", "")
+			],
+			&[arcstr::literal!(""), arcstr::literal!("overlay")]
+		)
+		.await,
+		Vec::<(String, String)>::new()
+	);
+}
@@ -2,22 +2,26 @@
 
 use std::num::{NonZeroU8, NonZeroU16, NonZeroU32};
 use std::thread::available_parallelism;
-use std::{num::NonZeroUsize, path::PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{env, io, num::NonZeroUsize, path::PathBuf};
 
 use enumset::{EnumSet, EnumSetType};
-use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use sysinfo::{MemoryRefreshKind, RefreshKind, System};
 
 use crate::squash_zip::{SquashZipSettings, ZipArchiveCommentString};
 
+#[cfg(test)]
+mod tests;
+
 /// Contains all the options that configure a `PackSquasher` operation.
 ///
 /// This is the root level configuration struct for PackSquash, so it is a
 /// good starting point to read the API documentation, after the `PackSquasher`
 /// struct.
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct SquashOptions {
 	/// The directory where the pack that will be processed resides.
 	pub pack_directory: PathBuf,
@@ -36,7 +40,8 @@ pub struct SquashOptions {
 #[derive(Clone)]
 pub struct ProcessedSquashOptions {
 	pub(super) options: SquashOptions,
-	pub(super) file_options_globs: GlobSet
+	pub(super) file_options_globs: GlobSet,
+	pub(super) unreferenced_assets_allowlist: Option<GlobMatcher>
 }
 
 impl TryFrom<SquashOptions> for ProcessedSquashOptions {
@@ -49,19 +54,67 @@ impl TryFrom<SquashOptions> for ProcessedSquashOptions {
 			globset_builder.add(compile_pack_file_glob_pattern(glob_pattern)?);
 		}
 
+		let unreferenced_assets_allowlist = squash_options
+			.global_options
+			.unreferenced_assets_allowlist
+			.as_deref()
+			.map(compile_pack_file_glob_pattern)
+			.transpose()?
+			.map(|glob| glob.compile_matcher());
+
 		Ok(ProcessedSquashOptions {
 			options: squash_options,
-			file_options_globs: globset_builder.build()?
+			file_options_globs: globset_builder.build()?,
+			unreferenced_assets_allowlist
 		})
 	}
 }
 
+/// The options that a `PackSquasher` operation would actually use, after automatic detections
+/// adjusted the [`SquashOptions`] it was configured with.
+///
+/// These options can be serialized to show them to users, but they are not meant to be read
+/// back as an options file.
+#[derive(Clone, Serialize)]
+pub struct ResolvedSquashOptions {
+	#[serde(flatten)]
+	pub(super) options: SquashOptions,
+	pub(super) asset_types: Vec<&'static str>,
+	pub(super) default_file_options: IndexMap<&'static str, FileOptions>
+}
+
+impl ResolvedSquashOptions {
+	/// Gets the configured options, with the automatically detected Minecraft quirks to work
+	/// around added to them.
+	pub const fn options(&self) -> &SquashOptions {
+		&self.options
+	}
+
+	/// Gets the names of the asset types pack files would be matched against, in snake case,
+	/// including the automatically detected ones.
+	pub fn asset_types(&self) -> &[&'static str] {
+		&self.asset_types
+	}
+
+	/// Gets the options used for pack files that do not match any configured file options
+	/// appropriate for them, by the name of the type of files they are meant for.
+	pub const fn default_file_options(&self) -> &IndexMap<&'static str, FileOptions> {
+		&self.default_file_options
+	}
+
+	/// Serializes these options to a TOML document, which includes the default value of
+	/// every option that was not configured.
+	pub fn to_toml_string(&self) -> Result<String, io::Error> {
+		toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+	}
+}
+
 /// Global options that affect how the entire pack is processed.
 ///
 /// The default values for  these options are meant to be the most reasonable that achieve good
 /// compression for  a wide range of use cases without using protection, compression or
 /// compressibility-improving techniques that may pose interoperability problems.
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct GlobalOptions {
@@ -264,7 +317,113 @@ pub struct GlobalOptions {
 	/// important user-facing notices and file tracking metadata.
 	///
 	/// **Default value**: empty string (no comment)
-	pub zip_comment: ZipArchiveCommentString
+	pub zip_comment: ZipArchiveCommentString,
+	/// When this option is set to `true`, PackSquash will generate byte-identical ZIP files when
+	/// run several times on the same pack files with the same options, which allows verifying
+	/// that a distributed ZIP file was generated from some pack, and avoids spurious changes
+	/// when storing generated ZIP files in version control systems or content-addressed caches.
+	///
+	/// To achieve this, the files in the ZIP file are sorted by their path, and their
+	/// modification times are set to the time specified by the `SOURCE_DATE_EPOCH` environment
+	/// variable, as defined by the [Reproducible Builds](https://reproducible-builds.org/specs/source-date-epoch/)
+	/// project, or to 1980-01-01 00:00:00 UTC, the earliest time that can be stored in a ZIP file,
+	/// if that variable is not set to a valid value. Because the time metadata needed to reuse
+	/// generated ZIP files in future runs can't be reproduced, it will not be stored, as if
+	/// `never_store_squash_times` was set to `true`.
+	///
+	/// ZIP file obfuscation is not deterministic, so this option can't be enabled when
+	/// `zip_spec_conformance_level` is set to [ZipSpecConformanceLevel::Disregard]. Doing so
+	/// is an error.
+	///
+	/// **Default value**: `false`
+	pub reproducible_output: bool,
+	/// The path where a manifest file that describes the output ZIP file will be written to, if
+	/// any. This manifest contains the size, SHA-1 and SHA-256 digests of the output ZIP file, and
+	/// the range of pack format versions supported by the pack, if it is known. These values are
+	/// handy to distribute the pack: for example, the SHA-1 digest is needed to fill in the
+	/// `resource-pack-sha1` property of Minecraft servers, which clients use to check the
+	/// integrity of the pack they download.
+	///
+	/// The manifest is written in TOML format if the path has a `toml` extension, and in JSON
+	/// format otherwise.
+	///
+	/// **Default value**: none (no manifest file is written)
+	pub output_manifest_file_path: Option<PathBuf>,
+	/// The path to a directory where PackSquash will store the results of optimizing and
+	/// compressing pack files, if any, so that they can be reused in future runs.
+	///
+	/// Unlike the reuse of the previously generated ZIP file, this cache does not depend on file
+	/// modification times, Squash Times, system IDs or the ZIP specification conformance level:
+	/// results are looked up by a hash of the pack file contents, its asset type, the options used
+	/// to optimize it and the PackSquash version. Therefore, this directory can be shared among
+	/// different machines, such as ephemeral CI runners, and packs, as long as the same
+	/// PackSquash version is used.
+	///
+	/// PackSquash never deletes entries from this directory. Deleting the directory, or any of
+	/// its files, is always safe when PackSquash is not running.
+	///
+	/// **Default value**: none (no optimization cache is used)
+	pub optimization_cache_directory: Option<PathBuf>,
+	/// The criterion used to decide whether a file of the previously generated ZIP file can be
+	/// reused, instead of processing its pack file again. Reusing previous ZIP files requires
+	/// their time metadata to be stored, as explained in the `never_store_squash_times` option
+	/// documentation, no matter what criterion is used.
+	///
+	/// **Default value**: [PreviousZipReuseMode::ModificationTime]
+	pub previous_zip_reuse_mode: PreviousZipReuseMode,
+	/// What to do with the textures and sounds of the pack that no model, texture atlas, font
+	/// or sound events file of the pack refers to, which are likely unused. Finding them requires
+	/// reading and parsing those files before processing the pack.
+	///
+	/// Assets in the `minecraft` namespace, and textures within directories that the texture
+	/// atlases built into the game include, such as `block` and `item`, are always considered
+	/// referenced, because the game may refer to them on its own. Assets that are only referred
+	/// to by code, such as mods, should be matched by `unreferenced_assets_allowlist`.
+	///
+	/// **Default value**: [UnreferencedAssetsMode::Ignore]
+	pub unreferenced_assets_mode: UnreferencedAssetsMode,
+	/// A glob pattern that matches the relative paths of the textures and sounds that should
+	/// always be considered referenced by `unreferenced_assets_mode`, no matter whether other
+	/// pack files refer to them. The syntax of this pattern is the same as file options glob
+	/// patterns.
+	///
+	/// **Default value**: none (every asset must be referenced by other pack files)
+	pub unreferenced_assets_allowlist: Option<String>,
+	/// What to do with the references of models, blockstates, texture atlases, fonts and sound
+	/// events files to models, textures and sounds that do not exist in the pack, which usually
+	/// are typos that only show up in-game as missing textures or silent sounds. Finding them
+	/// requires reading and parsing those files before processing the pack.
+	///
+	/// References to assets in the `minecraft` namespace are never considered dangling, because
+	/// the game provides them.
+	///
+	/// **Default value**: [DanglingReferencesMode::Ignore]
+	pub dangling_references_mode: DanglingReferencesMode,
+	/// What to do with pack files whose relative paths may prevent Minecraft from using them.
+	/// The namespaces and paths of files under the `assets` and `data` directories may only
+	/// contain lowercase ASCII letters, digits, `_`, `.` and `-` characters, and a path that only
+	/// differs from another in letter case or Unicode normalization, such as `Stone.png` and
+	/// `stone.png`, breaks the pack when extracted on most Windows and macOS computers.
+	///
+	/// **Default value**: [PathValidationMode::Warn]
+	pub path_validation_mode: PathValidationMode,
+	/// If `true`, the keys that the `en_us` language file of a namespace defines, but the
+	/// language files of other locales in that namespace do not, will be reported as warnings.
+	/// Minecraft shows the `en_us` strings for such keys, so they usually are translations that
+	/// were forgotten. Finding them requires reading and parsing every language file before
+	/// processing the pack.
+	///
+	/// **Default value**: `false`
+	pub check_missing_language_keys: bool,
+	/// The codes of the locales whose translations will be included in the generated ZIP file,
+	/// such as `es_es`. If set, the language files of any other locale will be left out, and so
+	/// will their declarations in the `language` section of the pack metadata file, so that the
+	/// game does not list languages without translations. The `en_us` locale is always included,
+	/// because Minecraft falls back to its strings when the selected locale does not define some
+	/// key. Locale codes are compared case-insensitively.
+	///
+	/// **Default value**: none (the translations of every locale are included)
+	pub included_locales: Option<Vec<String>>
 }
 
 impl Default for GlobalOptions {
@@ -297,7 +456,17 @@ impl Default for GlobalOptions {
 			spooling_buffers_size: (available_memory / 2097152 / (hardware_threads.get() as u64 + 1))
 				.try_into()
 				.unwrap_or(usize::MAX),
-			zip_comment: ZipArchiveCommentString::default()
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output: false,
+			output_manifest_file_path: None,
+			optimization_cache_directory: None,
+			previous_zip_reuse_mode: PreviousZipReuseMode::default(),
+			unreferenced_assets_mode: UnreferencedAssetsMode::default(),
+			unreferenced_assets_allowlist: None,
+			dangling_references_mode: DanglingReferencesMode::default(),
+			path_validation_mode: PathValidationMode::default(),
+			check_missing_language_keys: false,
+			included_locales: None
 		}
 	}
 }
@@ -309,6 +478,7 @@ impl GlobalOptions {
 		SquashZipSettings {
 			zopfli_iterations: self.zip_compression_iterations,
 			store_squash_time: !self.never_store_squash_times
+				&& !self.reproducible_output
 				&& !matches!(
 					self.zip_spec_conformance_level,
 					ZipSpecConformanceLevel::Pedantic
@@ -328,13 +498,97 @@ impl GlobalOptions {
 				.work_around_minecraft_quirks
 				.contains(MinecraftQuirk::Java8ZipParsing),
 			spool_buffer_size: self.spooling_buffers_size.saturating_mul(1024 * 1024),
-			zip_comment: self.zip_comment.clone()
+			zip_comment: self.zip_comment.clone(),
+			reproducible_output_time: self.reproducible_output.then(reproducible_output_time)
 		}
 	}
 }
 
+/// Returns the time that will be used for every time-like field of reproducible output ZIP files,
+/// which is taken from the `SOURCE_DATE_EPOCH` environment variable. If that variable is not set
+/// to a valid number of seconds since the Unix epoch, the Unix epoch is returned, which SquashZip
+/// clamps to the earliest time that can be stored in a ZIP file.
+fn reproducible_output_time() -> SystemTime {
+	env::var("SOURCE_DATE_EPOCH")
+		.ok()
+		.and_then(|source_date_epoch| source_date_epoch.trim().parse().ok())
+		.map_or(SystemTime::UNIX_EPOCH, |seconds_since_epoch| {
+			SystemTime::UNIX_EPOCH + Duration::from_secs(seconds_since_epoch)
+		})
+}
+
+/// A criterion to decide whether a file of a previously generated ZIP file can be reused.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PreviousZipReuseMode {
+	/// Files are reused if their pack files were not modified after they were added to the
+	/// previous ZIP file, according to their modification time. This is cheap, but files whose
+	/// modification time changes without changes to their contents, which is usual after a
+	/// `git checkout` or restoring a CI cache, will be processed again.
+	#[default]
+	ModificationTime,
+	/// Files are reused if the hash of their pack file contents and a fingerprint of the options
+	/// that affected their processing did not change since they were added to the previous ZIP
+	/// file, no matter what their modification times are. This requires reading every pack file
+	/// to hash it, and storing these hashes in the generated ZIP file, which increases its size
+	/// by 28 bytes per file.
+	///
+	/// ZIP files generated with other reuse modes do not contain these hashes, so their files
+	/// will be processed again the first time this mode is used.
+	ContentHash
+}
+
+/// What a squash operation does with textures and sounds that no other pack file refers to.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UnreferencedAssetsMode {
+	/// Unreferenced assets are not looked for, and are processed like any other pack file.
+	#[default]
+	Ignore,
+	/// Unreferenced assets are reported with a warning, but processed like any other pack
+	/// file.
+	Report,
+	/// Unreferenced assets are skipped, so they are left out of the generated ZIP file.
+	Prune
+}
+
+/// What a squash operation does with references to assets that do not exist in the pack.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum DanglingReferencesMode {
+	/// Dangling references are not looked for.
+	#[default]
+	Ignore,
+	/// Dangling references are reported with a warning, but the files that contain them are
+	/// processed like any other pack file.
+	Warn,
+	/// Dangling references are reported as optimization errors of the files that contain them,
+	/// which makes the squash operation fail.
+	Error
+}
+
+/// What a squash operation does with pack files whose relative paths may prevent Minecraft from
+/// using them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PathValidationMode {
+	/// Pack file paths are not validated.
+	Ignore,
+	/// Problems with pack file paths are reported with a warning, but the files are processed
+	/// like any other pack file.
+	#[default]
+	Warn,
+	/// Problems with pack file paths are reported as optimization errors of the files, which
+	/// makes the squash operation fail.
+	Error
+}
+
 /// A ZIP specification intent conformance level that a squash operation can adhere to.
-#[derive(Default, Clone, Copy, Deserialize)]
+#[derive(Default, Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum ZipSpecConformanceLevel {
@@ -402,8 +656,8 @@ pub enum ZipSpecConformanceLevel {
 }
 
 /// A helper struct that contains an integer guaranteed to be in the `[0, 100]` interval.
-#[derive(Deserialize, Clone, Copy)]
-#[serde(try_from = "u8")]
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(try_from = "u8", into = "u8")]
 #[repr(transparent)]
 pub struct PercentageInteger(u8);
 
@@ -496,7 +750,8 @@ pub enum MinecraftQuirk {
 }
 
 impl MinecraftQuirk {
-	pub(super) const fn as_str(&self) -> &'static str {
+	/// Returns the name of this quirk, as it is written in options files.
+	pub const fn as_str(&self) -> &'static str {
 		match self {
 			Self::GrayscaleImagesGammaMiscorrection => "grayscale_images_gamma_miscorrection",
 			Self::RestrictiveBannerLayerTextureFormatCheck => {
@@ -541,7 +796,7 @@ pub enum MinecraftMod {
 /// Options that customize how some file, of a certain file type, is processed.
 // When adding new variants to this enum, please update the lib.rs file too, so
 // the default options are used for new file types too
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(
 	untagged,
 	expecting = "some options did not match the expected global or file-specific options.\n\
@@ -565,6 +820,9 @@ pub enum FileOptions {
 	#[cfg(feature = "optifine")]
 	#[doc(cfg(feature = "optifine"))]
 	PropertiesFileOptions(PropertiesFileOptions),
+	/// Options that influence how language files are converted to a more
+	/// distribution-friendly representation.
+	LanguageFileOptions(LanguageFileOptions),
 	/// Options that influence how legacy language files are converted to a more
 	/// distribution-friendly representation.
 	LegacyLanguageFileOptions(LegacyLanguageFileOptions),
@@ -610,10 +868,73 @@ impl FileOptions {
 
 		self
 	}
+
+	/// Returns a stable, lowercase name for the type of files these options are meant for,
+	/// such as `json` or `png`.
+	pub(crate) const fn file_type_name(&self) -> &'static str {
+		match self {
+			FileOptions::AudioFileOptions(_) => "audio",
+			FileOptions::JsonFileOptions(_) => "json",
+			FileOptions::PngFileOptions(_) => "png",
+			FileOptions::ShaderFileOptions(_) => "shader",
+			#[cfg(feature = "optifine")]
+			FileOptions::PropertiesFileOptions(_) => "properties",
+			FileOptions::LanguageFileOptions(_) => "language",
+			FileOptions::LegacyLanguageFileOptions(_) => "legacy_language",
+			FileOptions::CommandFunctionFileOptions(_) => "command_function",
+			FileOptions::CompressedCompoundNbtTagFileOptions(_) => "compressed_compound_nbt_tag",
+			FileOptions::CustomFileOptions(_) => "custom"
+		}
+	}
+
+	/// Returns a byte string that identifies these options and the type of files they are
+	/// meant for, suitable for identifying how a pack file is processed across PackSquash
+	/// runs. Unlike the debug representation, it does not depend on how the option types
+	/// are declared, but on their serialized names and values only.
+	pub(crate) fn fingerprint(&self) -> Vec<u8> {
+		// Object keys are sorted so that reordering option fields does not change anything.
+		// The crate-private settings derived from other options are not serialized, so they
+		// are appended explicitly
+		let mut serialized_options =
+			serde_json::to_value(self).expect("File options are always representable as JSON");
+		serialized_options.sort_all_objects();
+
+		let mut fingerprint = format!("{}:{serialized_options}", self.file_type_name()).into_bytes();
+		match self {
+			Self::AudioFileOptions(audio_file_options) => fingerprint.push(
+				audio_file_options
+					.minecraft_version_supports_ogg_obfuscation
+					.into()
+			),
+			Self::PngFileOptions(png_file_options) => fingerprint.extend([
+				png_file_options
+					.working_around_grayscale_reduction_quirk
+					.into(),
+				png_file_options
+					.working_around_color_type_change_quirk
+					.into(),
+				png_file_options
+					.working_around_transparent_pixel_colors_change_quirk
+					.into(),
+				png_file_options
+					.minecraft_version_supports_png_obfuscation
+					.into()
+			]),
+			_ => ()
+		}
+
+		fingerprint
+	}
+
+	/// Serializes these options to a TOML document, in the same format they are read from
+	/// options files.
+	pub fn to_toml_string(&self) -> Result<String, io::Error> {
+		toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+	}
 }
 
 /// Parameters that influence how an audio file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct AudioFileOptions {
@@ -735,9 +1056,19 @@ pub enum ChannelMixingOption {
 	Skip
 }
 
+impl Serialize for ChannelMixingOption {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self {
+			Self::ToChannels(channel_count) => channel_count.serialize(serializer),
+			// Not mixing channels is represented by the absence of a channel count
+			Self::Skip => serializer.serialize_none()
+		}
+	}
+}
+
 /// Represents a bitrate control mode that can be used by the PackSquash Vorbis encoder,
 /// a modified version of the reference encoder with the aoTuV and Lancer patches applied.
-#[derive(Default, Deserialize, Clone, Copy)]
+#[derive(Default, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum AudioBitrateControlMode {
 	/// *Constant Quality Factor*: the encoder will interpret the target metric as a quality
@@ -822,8 +1153,8 @@ pub enum AudioBitrateControlMode {
 
 /// A helper struct that contains an integer that must be a valid number of
 /// audio channels accepted by Minecraft.
-#[derive(Deserialize, Clone, Copy)]
-#[serde(try_from = "NonZeroU8")]
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(try_from = "NonZeroU8", into = "NonZeroU8")]
 #[repr(transparent)]
 pub struct ChannelCount(NonZeroU8);
 
@@ -846,7 +1177,7 @@ impl From<ChannelCount> for NonZeroU8 {
 }
 
 /// Parameters that influence how a JSON file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct JsonFileOptions {
@@ -902,7 +1233,7 @@ impl Default for JsonFileOptions {
 ///
 /// Note that, in any case, any PNG chunks (e.g. metadata) that are not used by Minecraft
 /// to display the image will not be copied over from the original file.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct PngFileOptions {
@@ -1023,7 +1354,7 @@ impl Default for PngFileOptions {
 }
 
 /// Possible targets the colors of a PNG file will be quantized to.
-#[derive(Default, Deserialize, Copy, Clone)]
+#[derive(Default, Deserialize, Serialize, Copy, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum ColorQuantizationTarget {
 	/// No quantization will be done. The image will be losslessly compressed.
@@ -1076,8 +1407,8 @@ impl ColorQuantizationTarget {
 
 /// A helper struct that contains an 32-bit floating point number guaranteed to be
 /// in the `[0, 1]` interval.
-#[derive(Deserialize, Clone, Copy)]
-#[serde(try_from = "f32")]
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(try_from = "f32", into = "f32")]
 #[repr(transparent)]
 pub struct UnitIntervalFloat(f32);
 
@@ -1099,7 +1430,7 @@ impl From<UnitIntervalFloat> for f32 {
 }
 
 /// Parameters that influence how a shader file is optimized.
-#[derive(Deserialize, Clone, Copy, Default)]
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct ShaderFileOptions {
@@ -1141,7 +1472,7 @@ pub struct ShaderFileOptions {
 /// shaders will highlight that situation. These limitations might be removed in the future,
 /// rendering PackSquash capable of transforming more shaders according to the selected
 /// strategy.
-#[derive(Deserialize, Copy, Clone, Default)]
+#[derive(Deserialize, Serialize, Copy, Clone, Default)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum ShaderSourceTransformationStrategy {
@@ -1160,8 +1491,29 @@ pub enum ShaderSourceTransformationStrategy {
 	KeepAsIs
 }
 
+/// Parameters that influence how a language file is optimized.
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct LanguageFileOptions {
+	/// If `true`, the language file will be minified: insignificant whitespace characters and
+	/// comments will be removed. If `false`, the file will be prettified instead. Either way,
+	/// the file will be validated for duplicate keys and format strings that Minecraft would
+	/// not accept.
+	///
+	/// **Default value**: `true` (minify)
+	#[serde(rename = "minify_language")]
+	pub minify: bool
+}
+
+impl Default for LanguageFileOptions {
+	fn default() -> Self {
+		Self { minify: true }
+	}
+}
+
 /// Parameters that influence how a legacy language file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct LegacyLanguageFileOptions {
@@ -1198,7 +1550,7 @@ impl Default for LegacyLanguageFileOptions {
 }
 
 /// Parameters that influence how a command function file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct CommandFunctionFileOptions {
@@ -1219,7 +1571,7 @@ impl Default for CommandFunctionFileOptions {
 }
 
 /// Parameters that influence how a compressed compound NBT tag file is optimized.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 pub struct CompressedCompoundNbtTagFileOptions {
@@ -1251,7 +1603,7 @@ impl Default for CompressedCompoundNbtTagFileOptions {
 ///
 /// These files are only supported if PackSquash was compiled with OptiFine mod support. Otherwise,
 /// these parameters are read and parsed but ignored afterward.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(default, deny_unknown_fields)]
 #[non_exhaustive]
 #[cfg(feature = "optifine")]
@@ -1276,7 +1628,7 @@ impl Default for PropertiesFileOptions {
 /// Parameters that define a custom pack file, which PackSquash doesn't expect
 /// and skips by default, but that the pack author desires to put in the
 /// generated ZIP file.
-#[derive(Deserialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy)]
 #[serde(deny_unknown_fields)]
 #[non_exhaustive]
 pub struct CustomFileOptions {
@@ -0,0 +1,98 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+static EXAMPLE_OPTIONS_FILE: &str = r#"
+pack_directory = "pack"
+percentage_of_zip_structures_tuned_for_obfuscation_discretion = 42
+zip_comment = "Example comment"
+
+["**/*.ogg"]
+channels = 1
+target_pitch = 1.5
+
+["**/*.png"]
+color_quantization_target = "eight_bit_depth"
+color_quantization_dithering_level = 0.5
+"#;
+
+fn example_options() -> SquashOptions {
+	toml::from_str(EXAMPLE_OPTIONS_FILE).expect("The example options file is assumed to be valid")
+}
+
+fn to_toml_string(squash_options: &SquashOptions) -> String {
+	toml::to_string(squash_options).expect("Options should be serializable to TOML")
+}
+
+#[test]
+fn serialized_options_can_be_read_back() {
+	let squash_options = example_options();
+	let serialized_options = to_toml_string(&squash_options);
+
+	let deserialized_options = toml::from_str::<SquashOptions>(&serialized_options)
+		.expect("Serialized options should be valid options files");
+
+	assert_eq!(to_toml_string(&deserialized_options), serialized_options);
+
+	assert_eq!(
+		u8::from(
+			deserialized_options
+				.global_options
+				.percentage_of_zip_structures_tuned_for_obfuscation_discretion
+		),
+		42
+	);
+	assert_eq!(
+		&*deserialized_options.global_options.zip_comment,
+		"Example comment"
+	);
+
+	let Some(FileOptions::AudioFileOptions(audio_file_options)) =
+		deserialized_options.file_options.get("**/*.ogg")
+	else {
+		panic!("The audio file options should be read back as such");
+	};
+	assert!(matches!(
+		audio_file_options.channels,
+		ChannelMixingOption::ToChannels(channel_count) if NonZeroU8::from(channel_count).get() == 1
+	));
+	assert_eq!(audio_file_options.target_pitch, 1.5);
+
+	let Some(FileOptions::PngFileOptions(png_file_options)) =
+		deserialized_options.file_options.get("**/*.png")
+	else {
+		panic!("The PNG file options should be read back as such");
+	};
+	assert!(matches!(
+		png_file_options.color_quantization_target,
+		ColorQuantizationTarget::EightBitDepth
+	));
+	assert_eq!(
+		f32::from(png_file_options.color_quantization_dithering_level),
+		0.5
+	);
+}
+
+#[test]
+fn skipped_channel_mixing_is_serialized_as_a_missing_key() {
+	let mut audio_file_options = AudioFileOptions::default();
+	audio_file_options.channels = ChannelMixingOption::Skip;
+
+	let serialized_options = FileOptions::AudioFileOptions(audio_file_options)
+		.to_toml_string()
+		.expect("Options should be serializable to TOML");
+
+	assert!(
+		!serialized_options.contains("channels"),
+		"Skipped channel mixing should not be serialized: {serialized_options}"
+	);
+	assert!(
+		matches!(
+			toml::from_str::<AudioFileOptions>(&serialized_options)
+				.expect("Serialized options should be valid options")
+				.channels,
+			ChannelMixingOption::Skip
+		),
+		"A missing channel count should be read back as skipped channel mixing"
+	);
+}
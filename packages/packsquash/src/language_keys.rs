@@ -0,0 +1,119 @@
+//! Contains the comparison of the keys defined by the language files of a pack, which finds
+//! the language strings that are missing from some locale, and will be shown untranslated.
+
+use std::io;
+
+use ahash::{HashMap, HashSet};
+use json_comments::StripComments;
+use serde_json::{Map, Value};
+use tokio::io::AsyncReadExt;
+
+use crate::RelativePath;
+use crate::config::ProcessedSquashOptions;
+use crate::pack_file::strip_utf8_bom;
+use crate::pack_metadata::{PackLayerDirectoryName, PackLayerPathPrefixes};
+use crate::vfs::{IteratorTraversalOptions, VirtualFileSystem};
+
+#[cfg(test)]
+mod tests;
+
+/// The locale whose language files define the keys every other locale is expected to define.
+/// Minecraft falls back to the strings of this locale when some key is not defined.
+const REFERENCE_LOCALE: &str = "en_us";
+
+/// The keys of the reference locale that some locale of a namespace does not define.
+pub(crate) struct MissingLanguageKeys {
+	/// The relative path of a language file of the locale that lacks the keys.
+	pub(crate) path: RelativePath<'static>,
+	/// The keys that no language file of the locale defines, sorted in ascending order.
+	pub(crate) missing_keys: Vec<String>
+}
+
+/// Finds the keys that the `en_us` language file of each namespace defines, but the language
+/// files of other locales in the same namespace do not. The keys of a locale are those defined
+/// by its language files in any of the specified pack layers. The results are sorted in ascending
+/// order by path.
+///
+/// Language files that can't be parsed are ignored, as they will be reported as errors when
+/// processed.
+pub(crate) async fn find_missing_language_keys<'layers>(
+	vfs: &impl VirtualFileSystem,
+	options_holder: &ProcessedSquashOptions,
+	pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName>
+) -> Result<Vec<MissingLanguageKeys>, io::Error> {
+	let layer_path_prefixes = PackLayerPathPrefixes::new(pack_layer_directory_names);
+
+	// Maps (namespace, locale) pairs to the path of a language file for them, and their keys
+	let mut locale_keys = HashMap::<_, (RelativePath<'static>, HashSet<String>)>::default();
+	let mut file_buf = vec![];
+	for pack_file in vfs.file_iterator(
+		&options_holder.options.pack_directory,
+		IteratorTraversalOptions {
+			ignore_system_and_hidden_files: options_holder
+				.options
+				.global_options
+				.ignore_system_and_hidden_files
+		}
+	) {
+		let pack_file = pack_file?;
+		let Some((namespace, locale)) = language_file_locale(
+			layer_path_prefixes.layer_relative_path(pack_file.relative_path.as_str())
+		) else {
+			continue;
+		};
+
+		file_buf.clear();
+		vfs.open(&pack_file.file_path)?
+			.file_read
+			.read_to_end(&mut file_buf)
+			.await?;
+
+		let Ok(strings) = serde_json::from_reader::<_, Map<String, Value>>(StripComments::new(
+			strip_utf8_bom(&file_buf)
+		)) else {
+			continue;
+		};
+
+		let (namespace, locale) = (namespace.to_string(), locale.to_string());
+		locale_keys
+			.entry((namespace, locale))
+			.or_insert_with(|| (pack_file.relative_path, HashSet::default()))
+			.1
+			.extend(strings.into_iter().map(|(key, _)| key));
+	}
+
+	let mut missing_language_keys = locale_keys
+		.iter()
+		.filter(|((_, locale), _)| locale != REFERENCE_LOCALE)
+		.filter_map(|((namespace, _), (path, keys))| {
+			let (_, reference_keys) =
+				locale_keys.get(&(namespace.clone(), REFERENCE_LOCALE.to_string()))?;
+
+			let mut missing_keys = reference_keys.difference(keys).cloned().collect::<Vec<_>>();
+			missing_keys.sort_unstable();
+
+			(!missing_keys.is_empty()).then(|| MissingLanguageKeys {
+				path: path.clone(),
+				missing_keys
+			})
+		})
+		.collect::<Vec<_>>();
+	missing_language_keys.sort_unstable_by(|a, b| a.path.as_str().cmp(b.path.as_str()));
+
+	Ok(missing_language_keys)
+}
+
+/// Returns the namespace and locale of the language file at the specified path, relative to
+/// its pack layer, if it is a language file that Minecraft reads.
+fn language_file_locale(layer_relative_path: &str) -> Option<(&str, &str)> {
+	let (namespace, file_name) = layer_relative_path
+		.strip_prefix("assets/")?
+		.split_once("/lang/")?;
+
+	let locale = file_name
+		.strip_suffix(".json")
+		.or_else(|| file_name.strip_suffix(".jsonc"))?;
+
+	(!namespace.contains('/') && !locale.is_empty() && !locale.contains('/'))
+		.then_some((namespace, locale))
+}
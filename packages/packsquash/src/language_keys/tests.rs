@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+
+use crate::config::{ProcessedSquashOptions, SquashOptions};
+use crate::pack_metadata::PackLayerDirectoryName;
+use crate::vfs::memory_fs::MemoryFilesystem;
+
+use super::find_missing_language_keys;
+
+/// Convenience façade for [`find_missing_language_keys`] over an in-memory virtual file system
+/// that contains the given files, returning pairs of language file relative path and missing
+/// keys.
+async fn missing_language_keys<'a>(
+	files: impl IntoIterator<Item = (&'a str, &'static str)>,
+	pack_layer_directory_names: &[PackLayerDirectoryName]
+) -> Vec<(String, Vec<String>)> {
+	let mut vfs = MemoryFilesystem::new();
+	for (path, data) in files {
+		vfs.add_file(path, data, None)
+			.expect("I/O operations are assumed not to fail during tests");
+	}
+
+	let options_holder = ProcessedSquashOptions::try_from(SquashOptions {
+		pack_directory: PathBuf::new(),
+		global_options: Default::default(),
+		file_options: IndexMap::new()
+	})
+	.expect("The test options should be valid");
+
+	find_missing_language_keys(&vfs, &options_holder, pack_layer_directory_names.iter())
+		.await
+		.expect("I/O operations are assumed not to fail during tests")
+		.into_iter()
+		.map(|missing_language_keys| {
+			(
+				missing_language_keys.path.as_str().to_string(),
+				missing_language_keys.missing_keys
+			)
+		})
+		.collect()
+}
+
+#[tokio::test]
+async fn missing_keys_are_found() {
+	assert_eq!(
+		missing_language_keys(
+			[
+				(
+					"assets/my_pack/lang/en_us.json",
+					r#"{ "block.my_pack.crystal": "Crystal", "item.my_pack.shard": "Shard" }"#
+				),
+				(
+					"assets/my_pack/lang/es_es.json",
+					r#"{ "block.my_pack.crystal": "Cristal" }"#
+				),
+				(
+					"assets/my_pack/lang/de_de.jsonc",
+					r#"{
+						// Complete translation
+						"block.my_pack.crystal": "Kristall",
+						"item.my_pack.shard": "Scherbe"
+					}"#
+				),
+				(
+					"assets/other_pack/lang/fr_fr.json",
+					r#"{ "gui.other_pack.title": "Titre" }"#
+				)
+			],
+			&[arcstr::literal!("")]
+		)
+		.await,
+		[(
+			"assets/my_pack/lang/es_es.json".to_string(),
+			vec!["item.my_pack.shard".to_string()]
+		)]
+	);
+}
+
+#[tokio::test]
+async fn keys_are_merged_across_pack_layers() {
+	assert_eq!(
+		missing_language_keys(
+			[
+				(
+					"assets/my_pack/lang/en_us.json",
+					r#"{ "block.my_pack.crystal": "Crystal", "item.my_pack.shard": "Shard" }"#
+				),
+				(
+					"assets/my_pack/lang/es_es.json",
+					r#"{ "block.my_pack.crystal": "Cristal" }"#
+				),
+				(
+					"overlay/assets/my_pack/lang/es_es.json",
+					r#"{ "item.my_pack.shard": "Fragmento" }"#
+				)
+			],
+			&[arcstr::literal!(""), arcstr::literal!("overlay")]
+		)
+		.await,
+		Vec::<(String, Vec<String>)>::new()
+	);
+}
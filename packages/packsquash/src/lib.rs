@@ -11,63 +11,165 @@ use ahash::{HashMap, HashSet};
 use itertools::Itertools;
 use std::borrow::Cow;
 use std::convert::Infallible;
-use std::io::ErrorKind;
-use std::panic;
+use std::io::{Cursor, ErrorKind};
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, LazyLock};
-use std::{io, time::SystemTime};
+use std::{
+	io,
+	time::{Duration, Instant, SystemTime}
+};
 
 use enumset::EnumSet;
 use futures::StreamExt;
 use futures::future;
 use thiserror::Error;
-use tokio::io::AsyncSeek;
 use tokio::io::BufReader;
+use tokio::io::{AsyncSeek, AsyncWrite};
 use tokio::sync::Semaphore;
 use tokio::sync::mpsc::Sender;
-use tokio::{fs::File, io::AsyncRead, runtime::Builder};
-
-use config::ProcessedSquashOptions;
-use pack_metadata::{PackMetadata, PackMetadataError};
-use squash_zip::{SquashZip, SquashZipError};
+use tokio::{
+	fs::File,
+	io::AsyncRead,
+	runtime::{Builder, Handle},
+	task::{JoinError, JoinSet}
+};
+use tokio_util::either::Either;
+use tokio_util::sync::CancellationToken;
 
+use asset_references::analyze_asset_references;
+use config::{
+	DanglingReferencesMode, PathValidationMode, PreviousZipReuseMode, ProcessedSquashOptions,
+	ResolvedSquashOptions, UnreferencedAssetsMode, ZipSpecConformanceLevel
+};
+use language_keys::find_missing_language_keys;
+use locale_filter::LocaleFilter;
+use optimization_cache::OptimizationCache;
+use output_manifest::OutputManifest;
+use pack_metadata::{PackLayerDirectoryName, PackMetadata, PackMetadataError};
+use path_validation::PackFilePathValidator;
+use source_digest::compute_source_digest;
+use squash_zip::{SourceDigest, SquashZip, SquashZipError, inspect_zip, verify_zip};
+
+use crate::config::MinecraftQuirk;
 #[cfg(feature = "optifine")]
 use crate::config::PropertiesFileOptions;
+use crate::config::compile_pack_file_glob_pattern;
 use crate::config::{
 	AudioFileOptions, CommandFunctionFileOptions, CompressedCompoundNbtTagFileOptions, FileOptions,
-	JsonFileOptions, LegacyLanguageFileOptions, PngFileOptions, ShaderFileOptions, SquashOptions
+	JsonFileOptions, LanguageFileOptions, LegacyLanguageFileOptions, PngFileOptions,
+	ShaderFileOptions, SquashOptions
 };
-use crate::pack_file::PackFileProcessData;
+#[cfg(feature = "optifine")]
+pub use crate::pack_file::PropertiesFileOptimizationErrorKind;
 use crate::pack_file::asset_type::{
-	PackFileAssetTypeMatcher, PackFileAssetTypeMatches, tweak_asset_types_mask_from_global_options
+	PackFileAssetType, PackFileAssetTypeMatcher, PackFileAssetTypeMatches,
+	tweak_asset_types_mask_from_global_options
+};
+pub use crate::pack_file::{
+	AudioFileOptimizationErrorKind, CommandFunctionFileOptimizationErrorKind,
+	CompressedCompoundNbtTagFileOptimizationErrorKind, CustomOptimizationError,
+	CustomOptimizedByteChunksStream, CustomPackFileProcessor, JsonFileOptimizationErrorKind,
+	LanguageFileOptimizationErrorKind, LegacyLanguageFileOptimizationErrorKind,
+	OptimizationErrorKind, PngFileOptimizationErrorKind, ShaderFileOptimizationErrorKind
 };
+use crate::pack_file::{CustomPackFileProcessors, PackFileProcessData};
+pub use crate::path_validation::PackFilePathProblem;
 use crate::squash_zip::PreviousZipParseError;
 pub use crate::squash_zip::relative_path::RelativePath;
+pub use crate::squash_zip::{
+	OutputZipDigests, ZipEntryInspection, ZipInspectionReport, ZipVerificationProblem,
+	ZipVerificationReport
+};
 use crate::vfs::{IteratorTraversalOptions, VfsPackFileIterEntry, VirtualFileSystem};
 
 pub mod config;
 pub mod vfs;
 
+mod asset_references;
 mod buffered_async_spooled_temp_file;
+mod language_keys;
+mod locale_filter;
+mod optimization_cache;
+mod output_manifest;
 mod pack_file;
 mod pack_metadata;
+mod path_validation;
+mod source_digest;
 mod squash_zip;
 mod zopfli_iterations_time_model;
 
+#[cfg(test)]
+mod tests;
+
 /// A struct that represents a resource or data pack optimization operation with configuration
 /// parameters known beforehand, which generates an output ZIP file.
 ///
 /// This is a good starting  point for reading the API documentation. Once constructed, this
 /// struct can be used to run one or several optimization operations  with the same configuration
 /// on any pack, in an efficient manner.
-pub struct PackSquasher;
+pub struct PackSquasher {
+	cancellation_token: CancellationToken,
+	runtime_handle: Option<Handle>,
+	custom_pack_file_processors: CustomPackFileProcessors
+}
 
 impl PackSquasher {
 	/// Creates a new [`PackSquasher`] struct that will squash packs.
 	#[allow(clippy::new_without_default)] // It does not make much sense to have a default value
 	pub fn new() -> Self {
-		Self
+		Self {
+			cancellation_token: CancellationToken::new(),
+			runtime_handle: None,
+			custom_pack_file_processors: CustomPackFileProcessors::default()
+		}
+	}
+
+	/// Makes this [`PackSquasher`] optimize the pack files whose relative paths match the
+	/// specified glob pattern with the specified custom processor, which allows handling file
+	/// formats that PackSquash does not know about. The glob pattern syntax is the same as for
+	/// file options.
+	///
+	/// Custom processors take precedence over the built-in asset types, and are used when the
+	/// file options that apply to a pack file are either absent or meant for custom files. If
+	/// several custom processors match a pack file, the one registered first is used.
+	///
+	/// # Errors
+	/// [`PackSquasherError::InvalidFileGlobPattern`] is returned if the glob pattern is invalid.
+	pub fn with_custom_pack_file_processor(
+		mut self,
+		glob_pattern: &str,
+		processor: impl CustomPackFileProcessor
+	) -> Result<Self, PackSquasherError> {
+		self.custom_pack_file_processors.push(
+			compile_pack_file_glob_pattern(glob_pattern)?,
+			Arc::new(processor)
+		);
+		Ok(self)
+	}
+
+	/// Makes this [`PackSquasher`] process pack files on the Tokio runtime of the specified
+	/// handle, instead of the runtime the asynchronous squash operation methods are called
+	/// from, or a new runtime created by the synchronous ones. The runtime must be a
+	/// multi-threaded runtime.
+	///
+	/// The synchronous squash operation methods block on the runtime of the handle, so they
+	/// must not be called from an asynchronous context when a handle is set.
+	pub fn with_runtime_handle(mut self, runtime_handle: Handle) -> Self {
+		self.runtime_handle = Some(runtime_handle);
+		self
+	}
+
+	/// Makes the squash operations run by this [`PackSquasher`] stop as soon as possible when
+	/// the specified token is cancelled, which may be done from any thread.
+	///
+	/// When a squash operation is cancelled, no more pack files are processed, the processing
+	/// of pack files that is in progress is abandoned at the next opportunity, and any temporary
+	/// files are deleted. The output ZIP file is not written, so any previously generated output
+	/// ZIP file is left untouched, and [`PackSquasherError::Cancelled`] is returned.
+	pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+		self.cancellation_token = cancellation_token;
+		self
 	}
 
 	/// Executes the squash operation configured by the specified options, reading pack files from
@@ -83,13 +185,9 @@ impl PackSquasher {
 	/// happen and that information be desired.
 	///
 	/// # Panics
-	/// Reasonable client code can assume that this method does not panic. However, it should
-	/// be noted that this method may temporarily set a panic hook to handle any panics that
-	/// occur internally and then forward the panic information to the current panic hook,
-	/// in order to fulfill its contract under any circumstances.
-	///
-	/// Therefore, to guarantee that this method produces the expected results, the panic hook
-	/// should not be modified in any way while this method is executing.
+	/// Reasonable client code can assume that this method does not panic. Panics that occur
+	/// while processing pack files are reported to the current panic hook, and then make
+	/// this method return [`PackSquasherError::PackFileError`].
 	pub fn run<O: TryInto<ProcessedSquashOptions>>(
 		&self,
 		vfs: impl VirtualFileSystem + 'static,
@@ -99,8 +197,160 @@ impl PackSquasher {
 	where
 		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
 	{
-		let mut options_holder = squash_options.try_into()?;
+		self.run_with_output_sink(
+			vfs,
+			squash_options,
+			None::<tokio::io::Sink>,
+			pack_file_status_sender
+		)
+	}
 
+	/// Executes the squash operation configured by the specified options like
+	/// [`run`](Self::run) does, but writes the output ZIP file to the provided byte sink
+	/// instead of the output file path configured in the options. This is useful to write
+	/// the output ZIP file to an in-memory buffer, the standard output, or a network stream,
+	/// without going through the filesystem.
+	///
+	/// Because the output file path is ignored, no previous ZIP file is read to speed up the
+	/// squash operation. The sink is flushed after the whole output ZIP file is written to
+	/// it, but it is not shut down. No data is written to the sink if an error occurs
+	/// while processing pack files.
+	///
+	/// # Panics
+	/// This method has the same panic behavior as [`run`](Self::run).
+	pub fn run_with_output<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		squash_options: O,
+		output_writer: impl AsyncWrite + Unpin,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>
+	) -> Result<(), PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		self.run_with_output_sink(
+			vfs,
+			squash_options,
+			Some(output_writer),
+			pack_file_status_sender
+		)
+	}
+
+	/// Executes the squash operation configured by the specified options like
+	/// [`run`](Self::run) does, but asynchronously, on the Tokio runtime this method is
+	/// called from, or the one set with [`with_runtime_handle`](Self::with_runtime_handle).
+	/// This is useful to integrate squash operations in applications that already have
+	/// a Tokio runtime, without creating another one that would compete with it for CPU
+	/// cores.
+	///
+	/// The number of pack files processed concurrently is still bounded by the number of
+	/// threads configured in the options, regardless of the number of worker threads of the
+	/// runtime.
+	///
+	/// # Panics
+	/// This method panics if the runtime is not a multi-threaded runtime, or if it is called
+	/// outside a Tokio runtime and no runtime handle was set. Otherwise, it has the same panic
+	/// behavior as [`run`](Self::run).
+	pub async fn run_async<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		squash_options: O,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>
+	) -> Result<(), PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		self.squash(
+			vfs,
+			squash_options.try_into()?,
+			None::<tokio::io::Sink>,
+			pack_file_status_sender,
+			&self.runtime_handle.clone().unwrap_or_else(Handle::current)
+		)
+		.await
+	}
+
+	/// Executes the squash operation configured by the specified options like
+	/// [`run_with_output`](Self::run_with_output) does, but asynchronously, in the same way
+	/// as [`run_async`](Self::run_async).
+	///
+	/// # Panics
+	/// This method has the same panic behavior as [`run_async`](Self::run_async).
+	pub async fn run_with_output_async<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		squash_options: O,
+		output_writer: impl AsyncWrite + Unpin,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>
+	) -> Result<(), PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		self.squash(
+			vfs,
+			squash_options.try_into()?,
+			Some(output_writer),
+			pack_file_status_sender,
+			&self.runtime_handle.clone().unwrap_or_else(Handle::current)
+		)
+		.await
+	}
+
+	/// Shared implementation of [`run`](Self::run) and
+	/// [`run_with_output`](Self::run_with_output), which blocks on the runtime set with
+	/// [`with_runtime_handle`](Self::with_runtime_handle), or on a new runtime if none was set.
+	fn run_with_output_sink<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		squash_options: O,
+		output_writer: Option<impl AsyncWrite + Unpin>,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>
+	) -> Result<(), PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let options_holder = squash_options.try_into()?;
+
+		if let Some(runtime_handle) = &self.runtime_handle {
+			return runtime_handle.block_on(self.squash(
+				vfs,
+				options_holder,
+				output_writer,
+				pack_file_status_sender,
+				runtime_handle
+			));
+		}
+
+		let runtime = Builder::new_multi_thread()
+			.worker_threads(options_holder.options.global_options.threads.get())
+			// The actual number of blocking threads will be worker threads + 1 + this (1)
+			.max_blocking_threads(1)
+			.thread_name("packsquash-worker")
+			// 2 MiB -> 4 MiB. Avoids stack overflow during Zopfli sometimes, even on Linux
+			.thread_stack_size(4 * 1024 * 1024)
+			.build()
+			.unwrap();
+
+		runtime.block_on(self.squash(
+			vfs,
+			options_holder,
+			output_writer,
+			pack_file_status_sender,
+			runtime.handle()
+		))
+	}
+
+	/// Shared implementation of every squash operation method. Pack files are processed in
+	/// tasks spawned on the runtime of the specified handle. The output ZIP file is written
+	/// to the specified writer, if any, or to the configured output file path otherwise.
+	async fn squash(
+		&self,
+		vfs: impl VirtualFileSystem + 'static,
+		mut options_holder: ProcessedSquashOptions,
+		mut output_writer: Option<impl AsyncWrite + Unpin>,
+		pack_file_status_sender: Option<Sender<PackSquasherStatus>>,
+		runtime: &Handle
+	) -> Result<(), PackSquasherError> {
 		// When reading from a pack directory that is not a directory, no files will be
 		// processed. Avoid useless computation and help the user out by bailing out early
 		// with a descriptive error message in that case.
@@ -129,90 +379,188 @@ impl PackSquasher {
 		// Note that program correctness cannot depend on these conditions staying true during
 		// its execution. These checks are just meant to handle usage mistakes promptly
 		let output_file_path = &options_holder.options.global_options.output_file_path;
-		if vfs
-			.file_type(output_file_path)
-			.map_or_else(|_| false, |file_type| file_type.is_dir())
+		if output_writer.is_none()
+			&& vfs
+				.file_type(output_file_path)
+				.map_or_else(|_| false, |file_type| file_type.is_dir())
 		{
 			return Err(PackSquasherError::InvalidFileType(
 				"The output file path must refer to a file, not a directory"
 			));
 		}
 
-		let runtime = Builder::new_multi_thread()
-			.worker_threads(options_holder.options.global_options.threads.get())
-			// The actual number of blocking threads will be worker threads + 1 + this (1)
-			.max_blocking_threads(1)
-			.thread_name("packsquash-worker")
-			// 2 MiB -> 4 MiB. Avoids stack overflow during Zopfli sometimes, even on Linux
-			.thread_stack_size(4 * 1024 * 1024)
-			.build()
-			.unwrap();
+		// ZIP file obfuscation is driven by non-deterministic sources of randomness, so the
+		// generated ZIP files could not be reproduced
+		let global_options = &options_holder.options.global_options;
+		if global_options.reproducible_output
+			&& matches!(
+				global_options.zip_spec_conformance_level,
+				ZipSpecConformanceLevel::Disregard
+			) {
+			return Err(PackSquasherError::IncompatibleOptions(
+				"Reproducible output can't be used with ZIP obfuscation, which is not deterministic"
+			));
+		}
 
-		let automatic_quirk_detection = options_holder
-			.options
-			.global_options
-			.automatic_minecraft_quirks_detection;
+		// Read the pack metadata before doing the actual processing, as it may transparently
+		// modify the options and restrict the asset types to match pack files against
+		let (pack_meta, asset_types_mask) = read_pack_metadata(&vfs, &mut options_holder).await?;
 
-		let automatic_asset_type_mask_detection = options_holder
+		let quirks = options_holder
 			.options
 			.global_options
-			.automatic_asset_types_mask_detection;
+			.work_around_minecraft_quirks;
 
-		let read_pack_meta = automatic_quirk_detection
-			|| automatic_asset_type_mask_detection
-			|| options_holder
+		if let Some(pack_file_status_sender) = &pack_file_status_sender
+			&& options_holder
 				.options
 				.global_options
-				.validate_pack_metadata_file;
+				.automatic_minecraft_quirks_detection
+			&& !quirks.is_empty()
+		{
+			let notice_message = format!(
+				"Working around automatically detected Minecraft quirks: {}",
+				quirks.iter().map(|quirk| quirk.as_str()).join(", ")
+			);
 
-		// By default, allow every known asset type to match pack files. This will be adjusted later
-		// depending on the options and automatic asset type mask detection, if enabled
-		let mut asset_types_mask = EnumSet::all();
+			pack_file_status_sender
+				.send(PackSquasherStatus::Notice(Cow::Owned(notice_message)))
+				.await
+				.ok();
+		}
 
-		// Transparently modify the options before doing the actual processing we want to read the
-		// pack metadata, either to validate it, use automatic quirk detection or detect an asset
-		// type mask
-		let pack_layers = read_pack_meta
-			.then(|| {
-				runtime.block_on(async {
-					let pack_meta =
-						PackMetadata::read(&vfs, &options_holder.options.pack_directory).await?;
-
-					if automatic_quirk_detection {
-						let quirks = pack_meta.applicable_minecraft_quirks();
-
-						options_holder
-							.options
-							.global_options
-							.work_around_minecraft_quirks = quirks;
-
-						if let Some(pack_file_status_sender) = &pack_file_status_sender
-							&& !quirks.is_empty()
-						{
-							let notice_message = format!(
-								"Working around automatically detected Minecraft quirks: {}",
-								quirks.iter().map(|quirk| quirk.as_str()).join(", ")
-							);
-
-							pack_file_status_sender
-								.send(PackSquasherStatus::Notice(Cow::Owned(notice_message)))
-								.await
-								.ok();
+		// Remember the supported pack format versions for the output manifest, if any
+		let pack_format_range = pack_meta
+			.as_ref()
+			.map(PackMetadata::bounding_format_version_range);
+
+		// Packs whose metadata was not read may target any Minecraft version
+		let check_resource_location_characters = pack_meta
+			.as_ref()
+			.is_none_or(PackMetadata::restricts_resource_location_characters);
+
+		let pack_layers = pack_meta.map_or_else(
+			|| {
+				// Default to no overlays (a single base layer) for matching asset types below
+				HashMap::from_iter([(arcstr::literal!(""), HashSet::default())])
+			},
+			|pack_meta| pack_meta.layers
+		);
+
+		// Analyze the references between assets before processing any pack file, as that
+		// requires reading every pack file that may refer to others
+		let mut pruned_assets = HashSet::default();
+		let mut dangling_references = HashMap::<_, Vec<_>>::default();
+		let unreferenced_assets_mode = options_holder
+			.options
+			.global_options
+			.unreferenced_assets_mode;
+		let dangling_references_mode = options_holder
+			.options
+			.global_options
+			.dangling_references_mode;
+		if unreferenced_assets_mode != UnreferencedAssetsMode::Ignore
+			|| dangling_references_mode != DanglingReferencesMode::Ignore
+		{
+			let asset_references_analysis =
+				analyze_asset_references(&vfs, &options_holder, pack_layers.keys()).await?;
+
+			match unreferenced_assets_mode {
+				UnreferencedAssetsMode::Ignore => {}
+				UnreferencedAssetsMode::Report => {
+					if let Some(tx) = &pack_file_status_sender {
+						for unreferenced_asset in asset_references_analysis.unreferenced_assets {
+							tx.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::UnreferencedAsset(unreferenced_asset)
+							))
+							.await
+							.ok();
 						}
 					}
+				}
+				UnreferencedAssetsMode::Prune => {
+					pruned_assets.extend(asset_references_analysis.unreferenced_assets);
+				}
+			}
 
-					if automatic_asset_type_mask_detection {
-						asset_types_mask = pack_meta.applicable_asset_type_mask();
+			match dangling_references_mode {
+				DanglingReferencesMode::Ignore => {}
+				DanglingReferencesMode::Warn => {
+					if let Some(tx) = &pack_file_status_sender {
+						for dangling_reference in asset_references_analysis.dangling_references {
+							tx.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::DanglingReference {
+									referring_file: dangling_reference.referring_file,
+									referred_path: dangling_reference.referred_path
+								}
+							))
+							.await
+							.ok();
+						}
 					}
+				}
+				DanglingReferencesMode::Error => {
+					for dangling_reference in asset_references_analysis.dangling_references {
+						dangling_references
+							.entry(dangling_reference.referring_file)
+							.or_default()
+							.push(dangling_reference.referred_path);
+					}
+				}
+			}
+		}
 
-					Ok::<_, PackSquasherError>(pack_meta.layers)
-				})
-			})
-			.transpose()?
-			.unwrap_or_else(|| {
-				// Default to no overlays (a single base layer) for matching asset types below
-				HashMap::from_iter([(arcstr::literal!(""), HashSet::default())])
-			});
+		let locale_filter = options_holder
+			.options
+			.global_options
+			.included_locales
+			.as_deref()
+			.map(|included_locales| LocaleFilter::new(included_locales, pack_layers.keys()));
+
+		if options_holder
+			.options
+			.global_options
+			.check_missing_language_keys
+		{
+			let missing_language_keys =
+				find_missing_language_keys(&vfs, &options_holder, pack_layers.keys()).await?;
+
+			if let Some(tx) = &pack_file_status_sender {
+				for missing_language_keys in missing_language_keys {
+					// Excluded locales will not be shipped, so their missing keys do not matter
+					if locale_filter.as_ref().is_some_and(|locale_filter| {
+						locale_filter.excludes(&missing_language_keys.path)
+					}) {
+						continue;
+					}
+
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::MissingLanguageKeys {
+							path: missing_language_keys.path,
+							missing_keys: missing_language_keys.missing_keys
+						}
+					))
+					.await
+					.ok();
+				}
+			}
+		}
+
+		// Leave out the pack metadata language declarations of excluded locales before processing
+		// the pack metadata file, so that its processing is not different from any other file
+		let mut filtered_pack_metadata = match &locale_filter {
+			Some(locale_filter) => {
+				locale_filter
+					.filter_pack_metadata(&vfs, &options_holder.options.pack_directory)
+					.await?
+			}
+			None => None
+		};
+
+		let path_validation_mode = options_holder.options.global_options.path_validation_mode;
+		let mut path_validator = (path_validation_mode != PathValidationMode::Ignore).then(|| {
+			PackFilePathValidator::new(pack_layers.keys(), check_resource_location_characters)
+		});
 
 		let vfs = Arc::new(vfs);
 		let asset_type_matcher = Arc::new(PackFileAssetTypeMatcher::new(
@@ -220,347 +568,934 @@ impl PackSquasher {
 				asset_types_mask,
 				&options_holder.options.global_options
 			),
-			pack_layers.keys()
+			pack_layers.keys(),
+			&self.custom_pack_file_processors
 		));
 		let options_holder = Arc::new(options_holder);
 
-		runtime.block_on(async {
-			let pack_file_iter = vfs.file_iterator(
-				&options_holder.options.pack_directory,
-				IteratorTraversalOptions {
-					ignore_system_and_hidden_files: options_holder
-						.options
-						.global_options
-						.ignore_system_and_hidden_files
-				}
-			);
-
-			let squashzip_settings = options_holder
-				.options
-				.global_options
-				.as_squash_zip_settings();
-
-			// Open the previous ZIP file and buffer it, if possible. Bail out if any I/O
-			// error happens, except if the file does not exist, which is a normal condition
-			let previous_zip = if squashzip_settings.store_squash_time {
-				match File::open(&options_holder.options.global_options.output_file_path).await {
-					Ok(file) => Some(BufReader::new(file)),
-					Err(err) if err.kind() == ErrorKind::NotFound => None,
-					Err(err) => return Err(err.into())
-				}
-			} else {
-				None
-			};
-
-			let squash_zip = Arc::new(
-				match SquashZip::new(previous_zip, squashzip_settings).await {
-					Ok(squash_zip) => squash_zip,
-					Err((SquashZipError::PreviousZipParseError(err), squashzip_settings)) => {
-						// Something went wrong while reading the previous ZIP. We can continue the
-						// optimization process, albeit with reduced performance. Warn the user about
-						// that and try again without using a previous ZIP
-						if let Some(pack_file_status_sender) = &pack_file_status_sender {
-							pack_file_status_sender
-								.send(PackSquasherStatus::Warning(
-									PackSquasherWarning::UnusablePreviousZip(err)
-								))
-								.await
-								.ok();
-						}
+		let pack_file_iter = vfs.file_iterator(
+			&options_holder.options.pack_directory,
+			IteratorTraversalOptions {
+				ignore_system_and_hidden_files: options_holder
+					.options
+					.global_options
+					.ignore_system_and_hidden_files
+			}
+		);
 
-						SquashZip::new(None, squashzip_settings)
+		let squashzip_settings = options_holder
+			.options
+			.global_options
+			.as_squash_zip_settings();
+
+		// Open the previous ZIP file and buffer it, if possible. Bail out if any I/O
+		// error happens, except if the file does not exist, which is a normal condition
+		let previous_zip = if squashzip_settings.store_squash_time && output_writer.is_none() {
+			match File::open(&options_holder.options.global_options.output_file_path).await {
+				Ok(file) => Some(BufReader::new(file)),
+				Err(err) if err.kind() == ErrorKind::NotFound => None,
+				Err(err) => return Err(err.into())
+			}
+		} else {
+			None
+		};
+
+		let squash_zip = Arc::new(
+			match SquashZip::new(previous_zip, squashzip_settings).await {
+				Ok(squash_zip) => squash_zip,
+				Err((SquashZipError::PreviousZipParseError(err), squashzip_settings)) => {
+					// Something went wrong while reading the previous ZIP. We can continue the
+					// optimization process, albeit with reduced performance. Warn the user about
+					// that and try again without using a previous ZIP
+					if let Some(pack_file_status_sender) = &pack_file_status_sender {
+						pack_file_status_sender
+							.send(PackSquasherStatus::Warning(
+								PackSquasherWarning::UnusablePreviousZip(err)
+							))
 							.await
-							.map_err(|(err, _)| err)?
+							.ok();
 					}
-					Err((err, _)) => return Err(err.into())
+
+					SquashZip::new(None, squashzip_settings)
+						.await
+						.map_err(|(err, _)| err)?
 				}
-			);
+				Err((err, _)) => return Err(err.into())
+			}
+		);
 
-			let mut pack_file_tasks = Vec::with_capacity(squash_zip.previous_file_count());
+		let optimization_cache = match &options_holder
+			.options
+			.global_options
+			.optimization_cache_directory
+		{
+			Some(optimization_cache_directory) => Some(Arc::new(
+				OptimizationCache::new(optimization_cache_directory).await?
+			)),
+			None => None
+		};
+
+		let mut pack_file_tasks = JoinSet::new();
+
+		// Instantiate a semaphore that will help us limit the number of in-flight tasks.
+		// This is needed because if we spawn those tasks faster than we finish them we
+		// may end up opening a lot of files, needlessly consuming resources
+		let in_flight_tasks_semaphore = Arc::new(Semaphore::new(
+			options_holder.options.global_options.threads.get() * 2
+		));
 
-			// Instantiate a semaphore that will help us limit the number of in-flight tasks.
-			// This is needed because if we spawn those tasks faster than we finish them we
-			// may end up opening a lot of files, needlessly consuming resources
-			let in_flight_tasks_semaphore = Arc::new(Semaphore::new(
-				options_holder.options.global_options.threads.get() * 2
-			));
+		#[cfg(unix)]
+		{
+			let maximum_in_flight_tasks_count = in_flight_tasks_semaphore.available_permits() as u64;
+
+			// On Unix-like systems, exceeding open file limits may be a concern on multi-socket
+			// motherboards with high-end CPUs, or very constrained environments. In general, we
+			// need to open:
+			// - 3 files per task: the pack file itself, and two temporary files. When using an
+			//   optimization cache, a fourth file is needed to either hash the pack file or
+			//   access its cache entry.
+			// - 2 files for the output file and the previous file.
+			// - 10 files for OsFilesystem VFS operation.
+			let files_per_task = 3 + u64::from(optimization_cache.is_some());
+			let maximum_open_files_count = maximum_in_flight_tasks_count * files_per_task + 2 + 10;
+
+			// Ask the OS for a higher limit to satisfy our concurrency demands
+			let actual_open_files_limit = rlimit::increase_nofile_limit(maximum_open_files_count)?;
+
+			// If our request couldn't be satisfied, it is because we requested a too high limit.
+			// Throttle concurrency to stay below the limit
+			if actual_open_files_limit < maximum_open_files_count {
+				let maximum_semaphore_permits = std::cmp::max(
+					actual_open_files_limit.saturating_sub(2).saturating_sub(10) / files_per_task,
+					1 // Spawn at least a task at a time to avoid a deadlock
+				);
+				let semaphore_permits_diff =
+					(maximum_in_flight_tasks_count - maximum_semaphore_permits) as u32;
+
+				// Adjust the number of semaphore permits accordingly
+				in_flight_tasks_semaphore
+					.acquire_many(semaphore_permits_diff)
+					.await
+					.unwrap()
+					.forget();
 
-			#[cfg(unix)]
-			{
-				let maximum_in_flight_tasks_count =
-					in_flight_tasks_semaphore.available_permits() as u64;
-
-				// On Unix-like systems, exceeding open file limits may be a concern on multi-socket
-				// motherboards with high-end CPUs, or very constrained environments. In general, we
-				// need to open:
-				// - 3 files per task: the pack file itself, and two temporary files.
-				// - 2 files for the output file and the previous file.
-				// - 10 files for OsFilesystem VFS operation.
-				let maximum_open_files_count = maximum_in_flight_tasks_count * 3 + 2 + 10;
-
-				// Ask the OS for a higher limit to satisfy our concurrency demands
-				let actual_open_files_limit =
-					rlimit::increase_nofile_limit(maximum_open_files_count)?;
-
-				// If our request couldn't be satisfied, it is because we requested a too high limit.
-				// Throttle concurrency to stay below the limit
-				if actual_open_files_limit < maximum_open_files_count {
-					let maximum_semaphore_permits = std::cmp::max(
-						actual_open_files_limit.saturating_sub(2).saturating_sub(10) / 3,
-						1 // Spawn at least a task at a time to avoid a deadlock
-					);
-					let semaphore_permits_diff =
-						(maximum_in_flight_tasks_count - maximum_semaphore_permits) as u32;
+				if let Some(tx) = &pack_file_status_sender {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::ConcurrencyLimitedDueToOpenFdLimits
+					))
+					.await
+					.ok();
+				}
+			}
+		}
 
-					// Adjust the number of semaphore permits accordingly
-					in_flight_tasks_semaphore
-						.acquire_many(semaphore_permits_diff)
-						.await
-						.unwrap()
-						.forget();
+		let pack_file_optimization_failed = Arc::new(AtomicBool::new(false));
+
+		// In the current thread, dispatch a task for each pack file, that may execute
+		// in any thread of the Tokio runtime
+		for pack_file_data in pack_file_iter {
+			// To shield ourselves against pack file tasks that may panic, even if they
+			// shouldn't do so, register the pack file optimization as failed when a finished
+			// task is found to have panicked. The panic hook is not touched, as it is global
+			// to the process, and other tasks of the runtime or concurrent squash operations
+			// may panic for unrelated reasons
+			while let Some(task_result) = pack_file_tasks.try_join_next() {
+				register_pack_file_task_panic(task_result, &pack_file_optimization_failed);
+			}
 
+			// Stop iterating over pack files if something went wrong processing one of them,
+			// or the operation was cancelled. Use an acquire ordering to force happens-before
+			// relationships which ensure that any value stored by other threads is read
+			// promptly by this thread
+			if pack_file_optimization_failed.load(Ordering::Acquire)
+				|| self.cancellation_token.is_cancelled()
+			{
+				break;
+			}
+
+			// Validate the paths of pack files as they are found, which requires remembering
+			// the previous paths to detect collisions
+			if let Ok(pack_file_data) = &pack_file_data
+				&& let Some(path_validator) = &mut path_validator
+				&& let Err(problem) =
+					path_validator.validate(&pack_file_data.relative_path, &asset_type_matcher)
+			{
+				if path_validation_mode == PathValidationMode::Error {
 					if let Some(tx) = &pack_file_status_sender {
-						tx.send(PackSquasherStatus::Warning(
-							PackSquasherWarning::ConcurrencyLimitedDueToOpenFdLimits
-						))
+						tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+							path: pack_file_data.relative_path.clone(),
+							optimization_strategy: Cow::Borrowed("Path validation error"),
+							optimization_error: Some(problem.to_string()),
+							optimization_error_kind: Some(OptimizationErrorKind::InvalidPath),
+							skipped: false,
+							report: None
+						}))
 						.await
 						.ok();
 					}
+
+					pack_file_optimization_failed.store(true, Ordering::Release);
+					continue;
+				}
+
+				if let Some(tx) = &pack_file_status_sender {
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::InvalidPackFilePath {
+							path: pack_file_data.relative_path.clone(),
+							problem
+						}
+					))
+					.await
+					.ok();
 				}
 			}
 
-			let pack_file_optimization_failed = Arc::new(AtomicBool::new(false));
-
-			// To shield ourselves against pack file tasks that may panic, even if they shouldn't
-			// do so, install a temporary panic hook that will register the pack file optimization
-			// as failed and then invoke the already registered hook. This will "leak" two `Arc`s
-			// in case we don't get to restore the previous panic hook, but if that's the case
-			// then we will propagate the panic to the caller, which will probably not care about
-			// this anyway. This "leak" lasts until the hook is set again, because the reference
-			// count then drops to zero
-			let previous_panic_hook = Arc::new(panic::take_hook());
-			panic::set_hook({
-				let pack_file_optimization_failed = Arc::clone(&pack_file_optimization_failed);
-				let previous_panic_hook = Arc::clone(&previous_panic_hook);
-
-				Box::new(move |panic_info| {
-					pack_file_optimization_failed.store(true, Ordering::Release);
-					previous_panic_hook(panic_info);
-				})
-			});
+			// Pruned assets are skipped without spawning a task for them
+			if let Ok(pack_file_data) = &pack_file_data
+				&& pruned_assets.contains(&pack_file_data.relative_path)
+			{
+				if let Some(tx) = &pack_file_status_sender {
+					tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+						path: pack_file_data.relative_path.clone(),
+						optimization_strategy: Cow::Borrowed(
+							"Skipped because no other pack file refers to it"
+						),
+						optimization_error: None,
+						optimization_error_kind: None,
+						skipped: true,
+						report: None
+					}))
+					.await
+					.ok();
+				}
 
-			// In the current thread, dispatch a task for each pack file, that may execute
-			// in any thread of the Tokio runtime
-			for pack_file_data in pack_file_iter {
-				// Stop iterating over pack files if something went wrong processing one of them.
-				// Use an acquire ordering to force happens-before relationships which ensure that
-				// any value stored by other threads is read promptly by this thread
-				if pack_file_optimization_failed.load(Ordering::Acquire) {
-					break;
+				continue;
+			}
+
+			// Language files of excluded locales are skipped without spawning a task for them
+			if let Ok(pack_file_data) = &pack_file_data
+				&& locale_filter.as_ref().is_some_and(|locale_filter| {
+					locale_filter.excludes(&pack_file_data.relative_path)
+				}) {
+				if let Some(tx) = &pack_file_status_sender {
+					tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+						path: pack_file_data.relative_path.clone(),
+						optimization_strategy: Cow::Borrowed(
+							"Skipped because its locale is not included"
+						),
+						optimization_error: None,
+						optimization_error_kind: None,
+						skipped: true,
+						report: None
+					}))
+					.await
+					.ok();
 				}
 
-				let options_holder = Arc::clone(&options_holder);
-				let asset_type_matcher = Arc::clone(&asset_type_matcher);
-				let squash_zip = Arc::clone(&squash_zip);
-				let vfs = Arc::clone(&vfs);
-
-				let in_flight_tasks_semaphore = Arc::clone(&in_flight_tasks_semaphore);
-				let pack_file_optimization_failed = Arc::clone(&pack_file_optimization_failed);
-				let pack_file_status_sender = pack_file_status_sender.clone();
-
-				// Acquire a task permit before spawning it, and send it to the task. This
-				// stops iteration of the VFS if it is going too fast relative to the
-				// processing speed
-				let task_permit = in_flight_tasks_semaphore.acquire_owned().await.unwrap();
-
-				pack_file_tasks.push(runtime.spawn(async move {
-					let pack_file_data = match pack_file_data {
-						Ok(data) => data,
-						Err(err) => {
-							if let Some(tx) = pack_file_status_sender {
-								tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
-									path: RelativePath::from_inner(Cow::Borrowed("-")),
-									optimization_strategy: Cow::Borrowed("Pack directory scan error"),
-									optimization_error: Some(err.to_string()),
-									skipped: false
-								}))
-								.await
-								.ok();
-							}
-
-							pack_file_optimization_failed.store(true, Ordering::Release);
-							return;
-						}
-					};
+				continue;
+			}
+
+			// Pack files with dangling references fail without spawning a task for them
+			if let Ok(pack_file_data) = &pack_file_data
+				&& let Some(referred_paths) =
+					dangling_references.remove(&pack_file_data.relative_path)
+			{
+				if let Some(tx) = &pack_file_status_sender {
+					tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+						path: pack_file_data.relative_path.clone(),
+						optimization_strategy: Cow::Borrowed("Dangling reference check error"),
+						optimization_error: Some(format!(
+							"Refers to assets that do not exist in the pack: {}",
+							referred_paths.join(", ")
+						)),
+						optimization_error_kind: Some(OptimizationErrorKind::DanglingReference),
+						skipped: false,
+						report: None
+					}))
+					.await
+					.ok();
+				}
+
+				pack_file_optimization_failed.store(true, Ordering::Release);
+				continue;
+			}
+
+			// The filtered pack metadata replaces the contents of the file it was read from
+			let pack_file_contents = if let Ok(pack_file_data) = &pack_file_data
+				&& filtered_pack_metadata
+					.as_ref()
+					.is_some_and(|(relative_path, _)| *relative_path == pack_file_data.relative_path)
+			{
+				filtered_pack_metadata.take().map(|(_, contents)| contents)
+			} else {
+				None
+			};
 
-					let have_default_options;
-					let asset_type_matches = {
-						let asset_type_matches =
-							asset_type_matcher.matches_for(&pack_file_data.relative_path);
-
-						if !asset_type_matches.is_empty() {
-							// Use the found matches. Every matched asset type has default options
-							// if none are specified in the options file
-							have_default_options = true;
-							asset_type_matches
-						} else {
-							// Consider a tentative match for a custom asset, which must be specified
-							// in the file options. As such, there are no default options
-							have_default_options = false;
-							PackFileAssetTypeMatches::of_custom_asset_type()
+			let options_holder = Arc::clone(&options_holder);
+			let asset_type_matcher = Arc::clone(&asset_type_matcher);
+			let squash_zip = Arc::clone(&squash_zip);
+			let optimization_cache = optimization_cache.clone();
+			let vfs = Arc::clone(&vfs);
+
+			let in_flight_tasks_semaphore = Arc::clone(&in_flight_tasks_semaphore);
+			let pack_file_optimization_failed = Arc::clone(&pack_file_optimization_failed);
+			let pack_file_status_sender = pack_file_status_sender.clone();
+
+			// Acquire a task permit before spawning it, and send it to the task. This
+			// stops iteration of the VFS if it is going too fast relative to the
+			// processing speed. Don't keep waiting for a permit if the operation is
+			// cancelled meanwhile
+			let Some(task_permit) = self
+				.cancellation_token
+				.run_until_cancelled(in_flight_tasks_semaphore.acquire_owned())
+				.await
+			else {
+				break;
+			};
+			let task_permit = task_permit.unwrap();
+
+			let pack_file_task = async move {
+				let pack_file_data = match pack_file_data {
+					Ok(data) => data,
+					Err(err) => {
+						if let Some(tx) = pack_file_status_sender {
+							tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+								path: RelativePath::from_inner(Cow::Borrowed("-")),
+								optimization_strategy: Cow::Borrowed("Pack directory scan error"),
+								optimization_error: Some(err.to_string()),
+								optimization_error_kind: Some(OptimizationErrorKind::Io),
+								skipped: false,
+								report: None
+							}))
+							.await
+							.ok();
 						}
+
+						pack_file_optimization_failed.store(true, Ordering::Release);
+						return;
+					}
+				};
+
+				let have_default_options;
+				let asset_type_matches = {
+					let asset_type_matches =
+						asset_type_matcher.matches_for(&pack_file_data.relative_path);
+
+					if !asset_type_matches.is_empty() {
+						// Use the found matches. Every matched asset type has default options
+						// if none are specified in the options file
+						have_default_options = true;
+						asset_type_matches
+					} else {
+						// Consider a tentative match for a custom asset, which must be specified
+						// in the file options. As such, there are no default options
+						have_default_options = false;
+						PackFileAssetTypeMatches::of_custom_asset_type()
+					}
+				};
+
+				/// Ergonomic wrapper for `match_and_process_pack_file`.
+				macro_rules! try_process_with_file_options {
+					($file_options:expr, $file_options_glob:expr) => {
+						match_and_process_pack_file(
+							&options_holder.options,
+							$file_options.map(|file_options| {
+								file_options
+									.tweak_from_global_options(&options_holder.options.global_options)
+							}),
+							$file_options_glob,
+							&*squash_zip,
+							optimization_cache.as_deref(),
+							&*vfs,
+							&asset_type_matches,
+							&pack_file_data,
+							pack_file_contents.as_deref(),
+							&*pack_file_optimization_failed,
+							pack_file_status_sender.as_ref()
+						)
+						.await
 					};
+				}
 
-					/// Ergonomic wrapper for `match_and_process_pack_file`.
-					macro_rules! try_process_with_file_options {
-						($file_options:expr) => {
-							match_and_process_pack_file(
-								&options_holder.options,
-								$file_options.map(|file_options| {
-									file_options.tweak_from_global_options(
-										&options_holder.options.global_options
-									)
-								}),
-								&*squash_zip,
-								&*vfs,
-								&asset_type_matches,
-								&pack_file_data,
-								&*pack_file_optimization_failed,
-								pack_file_status_sender.as_ref()
-							)
-							.await
-						};
+				// Try to match configuration-provided file settings and process the pack file
+				// with those. The first match that contains settings for this pack file type
+				// "wins"
+				for i in options_holder
+					.file_options_globs
+					.matches(&*pack_file_data.relative_path)
+				{
+					let (file_options_glob, file_options) =
+						options_holder.options.file_options.get_index(i).unwrap();
+
+					if try_process_with_file_options!(
+						Some(*file_options),
+						Some(file_options_glob.as_str())
+					) {
+						return;
 					}
+				}
+
+				// If we get here, this pack file either did not match any file settings,
+				// in which case we should try defaults, or the file settings it matched
+				// were not appropriate for its type (i.e. all matches were for JSON files,
+				// but this is an audio file), in which case we should try defaults too
+				if have_default_options {
+					for default_file_options in default_file_options() {
+						if try_process_with_file_options!(default_file_options, None) {
+							return;
+						}
+					}
+				}
+
+				// Finally, if we get here, we did not process this pack file because
+				// it really is not a pack file, or we want to skip it. Tell caller we
+				// skipped it
+				if let Some(tx) = pack_file_status_sender {
+					tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
+						path: pack_file_data.relative_path,
+						optimization_strategy: Cow::Borrowed("Skipped"),
+						optimization_error: None,
+						optimization_error_kind: None,
+						skipped: true,
+						report: None
+					}))
+					.await
+					.ok();
+				}
+
+				// We're done with this pack file. Release the permit
+				drop(task_permit);
+			};
+
+			// Drop the task future, and thus any in-flight work, if the operation is
+			// cancelled
+			pack_file_tasks.spawn_on(
+				self.cancellation_token
+					.clone()
+					.run_until_cancelled_owned(pack_file_task),
+				runtime
+			);
+		}
+
+		// Now wait for every pack file task to finish, including those who panic,
+		// so the ZIP file is complete if everything went fine, or any pending work
+		// is done if not
+		while let Some(task_result) = pack_file_tasks.join_next().await {
+			register_pack_file_task_panic(task_result, &pack_file_optimization_failed);
+		}
+
+		// Do not try to finish the ZIP file if the operation was cancelled, to leave any
+		// previous output ZIP file untouched. Temporary files are deleted as the SquashZip
+		// and the pack file tasks that were using them are dropped
+		if self.cancellation_token.is_cancelled() {
+			return Err(PackSquasherError::Cancelled);
+		}
+
+		// Do not try to finish the ZIP file if something went wrong. We can't rely
+		// on a local variable that indicates whether the loop exited early because
+		// it may be finished by the time this is set to true, so do the atomic
+		// access. The ordering can't be relaxed because awaiting a join handle
+		// is not documented to guarantee any synchronization (maybe the thread that
+		// ran the task is still alive in the pool)
+		if pack_file_optimization_failed.load(Ordering::Acquire) {
+			return Err(PackSquasherError::PackFileError);
+		}
+
+		// Notify that we are about to finish the ZIP file
+		if let Some(tx) = &pack_file_status_sender {
+			tx.send(PackSquasherStatus::ZipFinish).await.ok();
+		}
+
+		// At this point we have only our strong reference to squash_zip, because
+		// we have just waited for the pack file tasks to conclude, and each task
+		// held one strong reference
+		let output_zip_digests = match (Arc::try_unwrap(squash_zip), &mut output_writer) {
+			(Ok(squash_zip), Some(output_writer)) => {
+				squash_zip.finish_to_writer(output_writer).await?
+			}
+			(Ok(squash_zip), None) => {
+				squash_zip
+					.finish(&options_holder.options.global_options.output_file_path)
+					.await?
+			}
+			(Err(_), _) => panic!("Unexpected number of strong references to SquashZip")
+		};
+
+		if let Some(output_manifest_file_path) = &options_holder
+			.options
+			.global_options
+			.output_manifest_file_path
+		{
+			OutputManifest::new(&output_zip_digests, pack_format_range)
+				.write(output_manifest_file_path)
+				.await?;
+		}
+
+		if let Some(tx) = &pack_file_status_sender {
+			tx.send(PackSquasherStatus::ZipDigests(output_zip_digests))
+				.await
+				.ok();
+		}
+
+		// Finally, send warnings about relevant conditions
+		if let Some(tx) = pack_file_status_sender
+			&& let Some(system_time_sanitizer) = LazyLock::get(&squash_zip::SYSTEM_TIME_SANITIZER)
+		{
+			if system_time_sanitizer.using_predictable_key() {
+				tx.send(PackSquasherStatus::Warning(
+					PackSquasherWarning::PredictableSystemTimeSanitizationKey
+				))
+				.await
+				.ok();
+			}
+
+			if system_time_sanitizer.using_volatile_key() {
+				tx.send(PackSquasherStatus::Warning(
+					PackSquasherWarning::VolatileSystemTimeSanitizationKey
+				))
+				.await
+				.ok();
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Verifies that every file of the output ZIP file previously generated with the specified
+	/// options can be read back as expected, by parsing its structure, undoing any obfuscation,
+	/// decompressing the data of every file and checking its CRC-32 and sizes. This is useful
+	/// to make sure that the obfuscation and compression techniques used did not render the
+	/// output ZIP file unusable before distributing it.
+	///
+	/// The output ZIP file is read from the output file path configured in the options, and
+	/// the settings that affect its structure, such as obfuscation ones, must match those that
+	/// were used to generate it. An error is returned if the ZIP file structure can't be parsed,
+	/// while problems found in the data of particular files are returned in the report.
+	pub fn verify<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		squash_options: O
+	) -> Result<ZipVerificationReport, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let options_holder = squash_options.try_into()?;
+		let global_options = &options_holder.options.global_options;
+
+		Builder::new_current_thread()
+			.thread_name("packsquash-worker")
+			.build()
+			.unwrap()
+			.block_on(async {
+				let output_zip = BufReader::new(File::open(&global_options.output_file_path).await?);
+
+				Ok(verify_zip(output_zip, &global_options.as_squash_zip_settings()).await?)
+			})
+	}
+
+	/// Lists the entries of the output ZIP file previously generated with the specified
+	/// options, along with the metadata PackSquash stores about them, such as their decrypted
+	/// Squash Times. The returned report also tells why the ZIP file would not be reused as a
+	/// previous ZIP file, if that is the case, which is useful to diagnose
+	/// [`PackSquasherWarning::UnusablePreviousZip`] warnings.
+	///
+	/// The output ZIP file is read from the output file path configured in the options, and
+	/// the settings that affect its structure, such as obfuscation ones, must match those that
+	/// were used to generate it. An error is returned only if the ZIP file can't be opened.
+	pub fn inspect<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		squash_options: O
+	) -> Result<ZipInspectionReport, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let options_holder = squash_options.try_into()?;
+		let global_options = &options_holder.options.global_options;
+
+		Builder::new_current_thread()
+			.thread_name("packsquash-worker")
+			.build()
+			.unwrap()
+			.block_on(async {
+				let output_zip = BufReader::new(File::open(&global_options.output_file_path).await?);
+
+				Ok(inspect_zip(output_zip, &global_options.as_squash_zip_settings()).await)
+			})
+	}
+
+	/// Computes what a squash operation configured by the specified options would do with
+	/// every file of the pack read from the provided virtual file system, without processing
+	/// the contents of any pack file or writing an output ZIP file. This is useful to check
+	/// the effects of changes to the options, such as which files match which file options.
+	///
+	/// The pack metadata file is read and validated like in squash operations, so the
+	/// returned plan takes automatically detected Minecraft quirks and asset types into
+	/// account. An error is returned if the pack metadata file or the pack files can't be
+	/// read.
+	pub fn plan<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem,
+		squash_options: O
+	) -> Result<SquashPlan, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let mut options_holder = squash_options.try_into()?;
+		let (asset_types_mask, pack_layers, check_resource_location_characters) =
+			read_asset_types_mask_and_pack_layers(&vfs, &mut options_holder)?;
 
-					// Try to match configuration-provided file settings and process the pack file
-					// with those. The first match that contains settings for this pack file type
-					// "wins"
-					for i in options_holder
-						.file_options_globs
-						.matches(&*pack_file_data.relative_path)
-					{
-						let file_options = options_holder.options.file_options[i];
+		let (pruned_assets, dangling_reference_files) =
+			find_pruned_assets_and_dangling_references(&vfs, &options_holder, &pack_layers)?;
+		let locale_filter = options_holder
+			.options
+			.global_options
+			.included_locales
+			.as_deref()
+			.map(|included_locales| LocaleFilter::new(included_locales, pack_layers.iter()));
+
+		// Path problems are only relevant here when they are errors. Collisions depend on the
+		// paths validated before, so validate every path in the same order squash operations do
+		let mut path_validator = (options_holder.options.global_options.path_validation_mode
+			== PathValidationMode::Error)
+			.then(|| {
+				PackFilePathValidator::new(pack_layers.iter(), check_resource_location_characters)
+			});
 
-						if try_process_with_file_options!(Some(file_options)) {
-							return;
-						}
-					}
+		let asset_type_matcher = PackFileAssetTypeMatcher::new(
+			asset_types_mask,
+			pack_layers.iter(),
+			&self.custom_pack_file_processors
+		);
 
-					// If we get here, this pack file either did not match any file settings,
-					// in which case we should try defaults, or the file settings it matched
-					// were not appropriate for its type (i.e. all matches were for JSON files,
-					// but this is an audio file), in which case we should try defaults too
-					if have_default_options {
-						for default_file_options in [
-							Some(FileOptions::JsonFileOptions(JsonFileOptions::default())),
-							Some(FileOptions::AudioFileOptions(AudioFileOptions::default())),
-							Some(FileOptions::PngFileOptions(PngFileOptions::default())),
-							#[cfg(feature = "optifine")]
-							Some(FileOptions::PropertiesFileOptions(
-								PropertiesFileOptions::default()
-							)),
-							Some(FileOptions::ShaderFileOptions(ShaderFileOptions::default())),
-							Some(FileOptions::LegacyLanguageFileOptions(
-								LegacyLanguageFileOptions::default()
-							)),
-							Some(FileOptions::CommandFunctionFileOptions(
-								CommandFunctionFileOptions::default()
-							)),
-							Some(FileOptions::CompressedCompoundNbtTagFileOptions(
-								CompressedCompoundNbtTagFileOptions::default()
-							)),
-							None
-						] {
-							if try_process_with_file_options!(default_file_options) {
-								return;
-							}
-						}
-					}
+		let mut pack_files = vfs
+			.file_iterator(
+				&options_holder.options.pack_directory,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: options_holder
+						.options
+						.global_options
+						.ignore_system_and_hidden_files
+				}
+			)
+			.map(|pack_file_data| {
+				pack_file_data.map(|pack_file_data| {
+					let decision_details = decide_pack_file(
+						&options_holder,
+						&asset_type_matcher,
+						path_validator.as_mut(),
+						&pruned_assets,
+						locale_filter.as_ref(),
+						&dangling_reference_files,
+						&pack_file_data.relative_path
+					);
 
-					// Finally, if we get here, we did not process this pack file because
-					// it really is not a pack file, or we want to skip it. Tell caller we
-					// skipped it
-					if let Some(tx) = pack_file_status_sender {
-						tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
-							path: pack_file_data.relative_path,
-							optimization_strategy: Cow::Borrowed("Skipped"),
-							optimization_error: None,
-							skipped: true
-						}))
-						.await
-						.ok();
+					PackFilePlan {
+						path: pack_file_data.relative_path,
+						decision: decision_details.decision,
+						asset_type: decision_details.asset_type.map(Into::into),
+						file_options_glob: decision_details.file_options_glob.cloned()
 					}
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
 
-					// We're done with this pack file. Release the permit
-					drop(task_permit);
-				}));
-			}
+		pack_files.sort_unstable_by(|a, b| a.path.as_str().cmp(b.path.as_str()));
 
-			// Now wait for every pack file task to finish, including those who panic,
-			// so the ZIP file is complete if everything went fine, or any pending work
-			// is done if not
-			for join_handle in pack_file_tasks {
-				join_handle.await.ok();
-			}
+		Ok(SquashPlan {
+			minecraft_quirks: options_holder
+				.options
+				.global_options
+				.work_around_minecraft_quirks,
+			asset_types: (asset_types_mask - PackFileAssetType::Custom)
+				.iter()
+				.map(Into::into)
+				.collect(),
+			pack_files
+		})
+	}
+
+	/// Explains what a squash operation configured by the specified options would do with the
+	/// pack file at the specified path, relative to the pack directory, and why. This is useful
+	/// to debug why a pack file is skipped or processed with unexpected options. The pack file
+	/// does not need to exist.
+	///
+	/// The pack metadata file is read and validated like in [`plan`](Self::plan). An error is
+	/// returned if it can't be read, or the path is not valid.
+	pub fn explain<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem,
+		squash_options: O,
+		relative_path: &str
+	) -> Result<PackFileExplanation, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let mut options_holder = squash_options.try_into()?;
+		let (asset_types_mask, pack_layers, check_resource_location_characters) =
+			read_asset_types_mask_and_pack_layers(&vfs, &mut options_holder)?;
 
-			// Everything's done, so restore the previous panic hook
-			drop(panic::take_hook());
-			panic::set_hook(match Arc::try_unwrap(previous_panic_hook) {
-				Ok(hook) => hook,
-				Err(_) => panic!("Unexpected number of strong references to the panic hook")
+		let (pruned_assets, dangling_reference_files) =
+			find_pruned_assets_and_dangling_references(&vfs, &options_holder, &pack_layers)?;
+		let locale_filter = options_holder
+			.options
+			.global_options
+			.included_locales
+			.as_deref()
+			.map(|included_locales| LocaleFilter::new(included_locales, pack_layers.iter()));
+
+		let relative_path = RelativePath::new("", relative_path.trim_start_matches('/'))
+			.map_err(io::Error::from)?
+			.into_owned();
+
+		let asset_type_matcher = PackFileAssetTypeMatcher::new(
+			asset_types_mask,
+			pack_layers.iter(),
+			&self.custom_pack_file_processors
+		);
+
+		// Path problems are only relevant here when they are errors. Collisions depend on the
+		// paths validated before, so validate the paths squash operations would validate before
+		// this one. Paths of files that do not exist are validated after every other path
+		let mut path_validator = (options_holder.options.global_options.path_validation_mode
+			== PathValidationMode::Error)
+			.then(|| {
+				PackFilePathValidator::new(pack_layers.iter(), check_resource_location_characters)
 			});
+		if let Some(path_validator) = &mut path_validator {
+			for pack_file_data in vfs.file_iterator(
+				&options_holder.options.pack_directory,
+				IteratorTraversalOptions {
+					ignore_system_and_hidden_files: options_holder
+						.options
+						.global_options
+						.ignore_system_and_hidden_files
+				}
+			) {
+				let pack_file_data = pack_file_data?;
+				if pack_file_data.relative_path == relative_path {
+					break;
+				}
 
-			// Do not try to finish the ZIP file if something went wrong. We can't rely
-			// on a local variable that indicates whether the loop exited early because
-			// it may be finished by the time this is set to true, so do the atomic
-			// access. The ordering can't be relaxed because awaiting a join handle
-			// is not documented to guarantee any synchronization (maybe the thread that
-			// ran the task is still alive in the pool)
-			if pack_file_optimization_failed.load(Ordering::Acquire) {
-				return Err(PackSquasherError::PackFileError);
+				path_validator
+					.validate(&pack_file_data.relative_path, &asset_type_matcher)
+					.ok();
 			}
+		}
 
-			// Notify that we are about to finish the ZIP file
-			if let Some(tx) = &pack_file_status_sender {
-				tx.send(PackSquasherStatus::ZipFinish).await.ok();
+		// Match against every asset type, so that the ones filtered out by the mask can be
+		// told apart. Custom pack file processors would hide any other match, so leave them out
+		let matched_asset_types = PackFileAssetTypeMatcher::new(
+			EnumSet::all(),
+			pack_layers.iter(),
+			&CustomPackFileProcessors::default()
+		)
+		.matches_for(&relative_path)
+		.asset_types()
+		.to_vec();
+
+		let custom_pack_file_processor_matched = self
+			.custom_pack_file_processors
+			.matcher()
+			.processor_for(&relative_path)
+			.is_some();
+
+		let decision_details = decide_pack_file(
+			&options_holder,
+			&asset_type_matcher,
+			path_validator.as_mut(),
+			&pruned_assets,
+			locale_filter.as_ref(),
+			&dangling_reference_files,
+			&relative_path
+		);
+
+		let processor = match (decision_details.decision, decision_details.file_options) {
+			(
+				PackFileDecision::SkipUnknown
+				| PackFileDecision::SkipUnreferenced
+				| PackFileDecision::SkipExcludedLocale
+				| PackFileDecision::FailInvalidPath
+				| PackFileDecision::FailDanglingReference,
+				_
+			) => None,
+			(_, Some(file_options)) if !matches!(file_options, FileOptions::CustomFileOptions(_)) => {
+				Some(file_options.file_type_name())
+			}
+			// Custom pack file processors take precedence over passing files through
+			_ => Some(if custom_pack_file_processor_matched {
+				"custom"
+			} else {
+				"passthrough"
+			})
+		};
+
+		Ok(PackFileExplanation {
+			masked_asset_types: matched_asset_types
+				.iter()
+				.filter(|asset_type| !asset_types_mask.contains(**asset_type))
+				.map(|asset_type| (*asset_type).into())
+				.collect(),
+			matched_asset_types: matched_asset_types.into_iter().map(Into::into).collect(),
+			custom_pack_file_processor_matched,
+			matched_file_options_globs: options_holder
+				.file_options_globs
+				.matches(&*relative_path)
+				.into_iter()
+				.map(|i| {
+					options_holder
+						.options
+						.file_options
+						.get_index(i)
+						.unwrap()
+						.0
+						.clone()
+				})
+				.collect(),
+			processor,
+			effective_file_options: decision_details.file_options,
+			plan: PackFilePlan {
+				decision: decision_details.decision,
+				asset_type: decision_details.asset_type.map(Into::into),
+				file_options_glob: decision_details.file_options_glob.cloned(),
+				path: relative_path
 			}
+		})
+	}
 
-			// At this point we have only our strong reference to squash_zip, because
-			// we have just waited for the pack file tasks to conclude, and each task
-			// held one strong reference
-			match Arc::try_unwrap(squash_zip) {
-				Ok(squash_zip) => {
-					squash_zip
-						.finish(&options_holder.options.global_options.output_file_path)
-						.await?
-				}
-				Err(_) => panic!("Unexpected number of strong references to SquashZip")
-			};
+	/// Resolves the options that a squash operation configured by the specified options would
+	/// actually use, after automatically detecting the Minecraft quirks to work around and the
+	/// asset types to match. This is useful to check what configuration applies to a pack.
+	///
+	/// The pack metadata file is read and validated like in [`plan`](Self::plan). An error is
+	/// returned if it can't be read.
+	pub fn resolve_options<O: TryInto<ProcessedSquashOptions>>(
+		&self,
+		vfs: impl VirtualFileSystem,
+		squash_options: O
+	) -> Result<ResolvedSquashOptions, PackSquasherError>
+	where
+		PackSquasherError: From<<O as TryInto<ProcessedSquashOptions>>::Error>
+	{
+		let mut options_holder = squash_options.try_into()?;
+		let (asset_types_mask, _, _) =
+			read_asset_types_mask_and_pack_layers(&vfs, &mut options_holder)?;
+
+		let global_options = &options_holder.options.global_options;
+		Ok(ResolvedSquashOptions {
+			asset_types: (asset_types_mask - PackFileAssetType::Custom)
+				.iter()
+				.map(Into::into)
+				.collect(),
+			default_file_options: default_file_options()
+				.flatten()
+				.map(|file_options| {
+					let file_options = file_options.tweak_from_global_options(global_options);
+					(file_options.file_type_name(), file_options)
+				})
+				.collect(),
+			options: options_holder.options
+		})
+	}
+}
 
-			// Finally, send warnings about relevant conditions
-			if let Some(tx) = pack_file_status_sender
-				&& let Some(system_time_sanitizer) = LazyLock::get(&squash_zip::SYSTEM_TIME_SANITIZER)
-			{
-				if system_time_sanitizer.using_predictable_key() {
-					tx.send(PackSquasherStatus::Warning(
-						PackSquasherWarning::PredictableSystemTimeSanitizationKey
-					))
-					.await
-					.ok();
-				}
+/// Reads the pack metadata file with a new runtime like [`read_pack_metadata`] does, for
+/// operations that tell what a squash operation would do. The returned asset types mask is
+/// tweaked according to the global options, and pack layers default to a single base layer
+/// if the pack metadata file is not read. Whether the characters of resource locations should
+/// be validated is returned too.
+fn read_asset_types_mask_and_pack_layers(
+	vfs: &impl VirtualFileSystem,
+	options_holder: &mut ProcessedSquashOptions
+) -> Result<
+	(
+		EnumSet<PackFileAssetType>,
+		Vec<PackLayerDirectoryName>,
+		bool
+	),
+	PackSquasherError
+> {
+	if !vfs
+		.file_type(&options_holder.options.pack_directory)
+		.map_or_else(|_| true, |file_type| file_type.is_dir())
+	{
+		return Err(PackSquasherError::InvalidFileType(
+			"The pack directory path must refer to a directory, not a file"
+		));
+	}
 
-				if system_time_sanitizer.using_volatile_key() {
-					tx.send(PackSquasherStatus::Warning(
-						PackSquasherWarning::VolatileSystemTimeSanitizationKey
-					))
-					.await
-					.ok();
-				}
-			}
+	let (pack_meta, asset_types_mask) = Builder::new_current_thread()
+		.thread_name("packsquash-worker")
+		.build()
+		.unwrap()
+		.block_on(read_pack_metadata(vfs, options_holder))?;
+
+	let check_resource_location_characters = pack_meta
+		.as_ref()
+		.is_none_or(PackMetadata::restricts_resource_location_characters);
+	let pack_layers = pack_meta.map_or_else(
+		|| vec![arcstr::literal!("")],
+		|pack_meta| pack_meta.layers.into_keys().collect()
+	);
 
-			Ok(())
-		})
+	Ok((
+		tweak_asset_types_mask_from_global_options(
+			asset_types_mask,
+			&options_holder.options.global_options
+		),
+		pack_layers,
+		check_resource_location_characters
+	))
+}
+
+/// Finds the unreferenced assets that a squash operation would prune, and the pack files that
+/// would fail because of their dangling references, if the options say so, with a new runtime,
+/// for operations that tell what a squash operation would do.
+fn find_pruned_assets_and_dangling_references(
+	vfs: &impl VirtualFileSystem,
+	options_holder: &ProcessedSquashOptions,
+	pack_layers: &[PackLayerDirectoryName]
+) -> Result<
+	(
+		HashSet<RelativePath<'static>>,
+		HashSet<RelativePath<'static>>
+	),
+	PackSquasherError
+> {
+	let global_options = &options_holder.options.global_options;
+	let prune_unreferenced_assets =
+		global_options.unreferenced_assets_mode == UnreferencedAssetsMode::Prune;
+	let fail_dangling_references =
+		global_options.dangling_references_mode == DanglingReferencesMode::Error;
+
+	if !prune_unreferenced_assets && !fail_dangling_references {
+		return Ok(Default::default());
 	}
+
+	let asset_references_analysis = Builder::new_current_thread()
+		.thread_name("packsquash-worker")
+		.build()
+		.unwrap()
+		.block_on(analyze_asset_references(
+			vfs,
+			options_holder,
+			pack_layers.iter()
+		))?;
+
+	Ok((
+		if prune_unreferenced_assets {
+			HashSet::from_iter(asset_references_analysis.unreferenced_assets)
+		} else {
+			HashSet::default()
+		},
+		if fail_dangling_references {
+			asset_references_analysis
+				.dangling_references
+				.into_iter()
+				.map(|dangling_reference| dangling_reference.referring_file)
+				.collect()
+		} else {
+			HashSet::default()
+		}
+	))
 }
 
 /// An error that may occur during a pack squashing operation.
@@ -577,6 +1512,9 @@ pub enum PackSquasherError {
 	/// path is a directory.
 	#[error("Invalid file type: {0}")]
 	InvalidFileType(&'static str),
+	/// Thrown when some options were set to values that can't be used together.
+	#[error("Incompatible options: {0}")]
+	IncompatibleOptions(&'static str),
 	/// Thrown when some error occurs in a ZIP file operation.
 	#[error("Error while performing a ZIP file operation: {0}")]
 	SquashZip(#[from] SquashZipError),
@@ -588,7 +1526,11 @@ pub enum PackSquasherError {
 	/// Thrown when an error happened while parsing the pack metadata manifest,
 	/// which defines some basic characteristics of a pack.
 	#[error("Pack metadata file error: {0}")]
-	PackMetadataError(#[from] PackMetadataError)
+	PackMetadataError(#[from] PackMetadataError),
+	/// Thrown when the operation was cancelled via the cancellation token set with
+	/// [`PackSquasher::with_cancellation_token`] before finishing.
+	#[error("The operation was cancelled")]
+	Cancelled
 }
 
 impl From<Infallible> for PackSquasherError {
@@ -620,7 +1562,41 @@ pub enum PackSquasherWarning {
 	/// The number of parallel tasks used to process pack files was limited
 	/// due to limits on the number of concurrent open file descriptors.
 	#[cfg(unix)]
-	ConcurrencyLimitedDueToOpenFdLimits
+	ConcurrencyLimitedDueToOpenFdLimits,
+	/// A processed pack file could not be stored in the optimization cache due to an
+	/// I/O error. The pack file was still added to the output ZIP file, but it will have
+	/// to be processed again in future runs.
+	OptimizationCacheWriteError(io::Error),
+	/// A texture or sound of the pack is not referred to by any model, texture atlas, font or
+	/// sound events file of the pack, so it is likely unused. These warnings are only emitted
+	/// when reporting unreferenced assets is enabled in the options.
+	UnreferencedAsset(RelativePath<'static>),
+	/// A model, blockstate, texture atlas, font or sound events file of the pack refers to an
+	/// asset that does not exist in the pack. These warnings are only emitted when warning about
+	/// dangling references is enabled in the options.
+	DanglingReference {
+		/// The relative path of the pack file that contains the reference.
+		referring_file: RelativePath<'static>,
+		/// The path, relative to its pack layer, where the referred asset was expected to be.
+		referred_path: String
+	},
+	/// The relative path of a pack file may prevent Minecraft from using it. These warnings are
+	/// only emitted when warning about path problems is enabled in the options.
+	InvalidPackFilePath {
+		/// The relative path of the pack file.
+		path: RelativePath<'static>,
+		/// The problem found with the path.
+		problem: PackFilePathProblem
+	},
+	/// A language file of the pack does not define some keys that the `en_us` language file of
+	/// its namespace defines, so Minecraft will show them untranslated. These warnings are only
+	/// emitted when checking for missing language keys is enabled in the options.
+	MissingLanguageKeys {
+		/// The relative path of a language file of the locale that lacks the keys.
+		path: RelativePath<'static>,
+		/// The keys that no language file of the locale defines.
+		missing_keys: Vec<String>
+	}
 }
 
 /// A status message concerning an in-progress squash operation.
@@ -631,6 +1607,9 @@ pub enum PackSquasherStatus {
 	/// Every pack file was processed, and the output ZIP file is being
 	/// finished up.
 	ZipFinish,
+	/// The output ZIP file was finished up and written, and these are its
+	/// digests.
+	ZipDigests(OutputZipDigests),
 	/// An informational message that does not indicate a potential problem.
 	Notice(Cow<'static, str>),
 	/// A condition about the squash operation that may indicate a potential
@@ -644,7 +1623,9 @@ pub struct PackFileStatus {
 	path: RelativePath<'static>,
 	optimization_strategy: Cow<'static, str>,
 	optimization_error: Option<String>,
-	skipped: bool
+	optimization_error_kind: Option<OptimizationErrorKind>,
+	skipped: bool,
+	report: Option<PackFileReport>
 }
 
 impl PackFileStatus {
@@ -669,12 +1650,460 @@ impl PackFileStatus {
 		self.optimization_error.as_deref()
 	}
 
+	/// Gets the kind of the error that occurred while optimizing this file, which is
+	/// `Some` if and only if `optimization_error` is. Unlike the error message, the
+	/// kind is stable between versions, so it can be matched against to handle
+	/// different errors in different ways.
+	pub const fn optimization_error_kind(&self) -> Option<OptimizationErrorKind> {
+		self.optimization_error_kind
+	}
+
 	/// Checks whether this file was processed successfully, but not included in
 	/// the generated ZIP file either because it was deemed to be unnecessary or
 	/// PackSquash did not recognize it.
 	pub const fn skipped(&self) -> bool {
 		self.skipped
 	}
+
+	/// Gets the machine-readable report of how this file was added to the generated ZIP
+	/// file. This returns `None` if the file was not added to the ZIP file, either because
+	/// it was skipped or an error occurred.
+	pub const fn report(&self) -> Option<&PackFileReport> {
+		self.report.as_ref()
+	}
+}
+
+/// Structured information about how a pack file was added to the generated ZIP file,
+/// meant to be consumed by programs. Unlike the user-friendly strings of a
+/// [`PackFileStatus`], the values returned by this struct are stable between releases,
+/// which makes them suitable to keep track of the space savings of each pack file over
+/// time.
+#[derive(Debug, Clone)]
+pub struct PackFileReport {
+	input_size: u64,
+	processed_size: u32,
+	compressed_size: u32,
+	compression_method: PackFileCompressionMethod,
+	asset_type: &'static str,
+	file_options_glob: Option<String>,
+	copied_from_previous_zip: bool,
+	copied_from_optimization_cache: bool,
+	processing_time: Duration
+}
+
+impl PackFileReport {
+	/// Gets the size of the pack file, as read from the pack, in bytes. Depending on
+	/// the virtual filesystem the pack is read from, this size may be an estimate.
+	pub const fn input_size(&self) -> u64 {
+		self.input_size
+	}
+
+	/// Gets the size of the processed pack file data, before compressing it, in bytes.
+	pub const fn processed_size(&self) -> u32 {
+		self.processed_size
+	}
+
+	/// Gets the size of the pack file data as stored in the generated ZIP file, in bytes.
+	/// This size does not account for ZIP file headers.
+	pub const fn compressed_size(&self) -> u32 {
+		self.compressed_size
+	}
+
+	/// Gets the compression method used to store the pack file data in the generated ZIP
+	/// file.
+	pub const fn compression_method(&self) -> PackFileCompressionMethod {
+		self.compression_method
+	}
+
+	/// Gets the name of the asset type the pack file was processed as, in snake case (for
+	/// example, `generic_texture`).
+	pub const fn asset_type(&self) -> &'static str {
+		self.asset_type
+	}
+
+	/// Gets the glob pattern of the file options that were used to process the pack file,
+	/// as written in the options file. This returns `None` if the default options for its
+	/// asset type were used.
+	pub fn file_options_glob(&self) -> Option<&str> {
+		self.file_options_glob.as_deref()
+	}
+
+	/// Checks whether the pack file was copied from the ZIP file generated in a previous
+	/// run, instead of being processed again.
+	pub const fn copied_from_previous_zip(&self) -> bool {
+		self.copied_from_previous_zip
+	}
+
+	/// Checks whether the optimized pack file was copied from the optimization cache,
+	/// instead of being processed again.
+	pub const fn copied_from_optimization_cache(&self) -> bool {
+		self.copied_from_optimization_cache
+	}
+
+	/// Gets the time it took to process the pack file and add it to the generated ZIP file.
+	pub const fn processing_time(&self) -> Duration {
+		self.processing_time
+	}
+}
+
+/// The compression method used to store a pack file in the generated ZIP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PackFileCompressionMethod {
+	/// The pack file data was stored without compression.
+	Store,
+	/// The pack file data was compressed with the DEFLATE algorithm.
+	Deflate
+}
+
+impl PackFileCompressionMethod {
+	/// Returns the lowercase name of this compression method.
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Store => "store",
+			Self::Deflate => "deflate"
+		}
+	}
+}
+
+/// What a squash operation would do with the files of a pack, as computed by
+/// [`PackSquasher::plan`].
+#[derive(Debug)]
+pub struct SquashPlan {
+	minecraft_quirks: EnumSet<MinecraftQuirk>,
+	asset_types: Vec<&'static str>,
+	pack_files: Vec<PackFilePlan>
+}
+
+impl SquashPlan {
+	/// Gets the Minecraft quirks that would be worked around, either because they were
+	/// automatically detected or because the options say so.
+	pub const fn minecraft_quirks(&self) -> EnumSet<MinecraftQuirk> {
+		self.minecraft_quirks
+	}
+
+	/// Gets the names of the asset types that pack files may match, either because they were
+	/// automatically detected or because the options say so. These names are the same as
+	/// the ones returned by [`PackFileReport::asset_type`].
+	pub fn asset_types(&self) -> &[&'static str] {
+		&self.asset_types
+	}
+
+	/// Gets what would be done with every file of the pack, sorted by path.
+	pub fn pack_files(&self) -> &[PackFilePlan] {
+		&self.pack_files
+	}
+}
+
+/// What a squash operation would do with a pack file, as computed by [`PackSquasher::plan`].
+#[derive(Debug)]
+pub struct PackFilePlan {
+	path: RelativePath<'static>,
+	decision: PackFileDecision,
+	asset_type: Option<&'static str>,
+	file_options_glob: Option<String>
+}
+
+impl PackFilePlan {
+	/// Gets the relative path of the pack file.
+	pub const fn path(&self) -> &RelativePath<'static> {
+		&self.path
+	}
+
+	/// Gets whether the pack file would be added to the generated ZIP file.
+	pub const fn decision(&self) -> PackFileDecision {
+		self.decision
+	}
+
+	/// Gets the name of the asset type the pack file would be processed as, or `None` if it
+	/// would be skipped. This name is the same as the one returned by
+	/// [`PackFileReport::asset_type`].
+	pub const fn asset_type(&self) -> Option<&'static str> {
+		self.asset_type
+	}
+
+	/// Gets the glob pattern of the file options that would be used to process the pack file,
+	/// or `None` if it would be processed with default options or skipped.
+	pub fn file_options_glob(&self) -> Option<&str> {
+		self.file_options_glob.as_deref()
+	}
+}
+
+/// Why a squash operation would do what it would do with a pack file, as computed by
+/// [`PackSquasher::explain`].
+pub struct PackFileExplanation {
+	plan: PackFilePlan,
+	matched_asset_types: Vec<&'static str>,
+	masked_asset_types: Vec<&'static str>,
+	custom_pack_file_processor_matched: bool,
+	matched_file_options_globs: Vec<String>,
+	processor: Option<&'static str>,
+	effective_file_options: Option<FileOptions>
+}
+
+impl PackFileExplanation {
+	/// Gets what would be done with the pack file.
+	pub const fn plan(&self) -> &PackFilePlan {
+		&self.plan
+	}
+
+	/// Gets the names of every asset type whose path pattern matches the pack file path in
+	/// some pack layer, including the ones filtered out by the asset types mask.
+	pub fn matched_asset_types(&self) -> &[&'static str] {
+		&self.matched_asset_types
+	}
+
+	/// Gets the names of the matched asset types that are filtered out by the asset types
+	/// mask, either because they were not automatically detected as applicable to the pack
+	/// or because the options say so.
+	pub fn masked_asset_types(&self) -> &[&'static str] {
+		&self.masked_asset_types
+	}
+
+	/// Checks whether the pack file path matches the glob pattern of some custom pack file
+	/// processor, which takes precedence over any matched asset type.
+	pub const fn custom_pack_file_processor_matched(&self) -> bool {
+		self.custom_pack_file_processor_matched
+	}
+
+	/// Gets the glob patterns of every file options entry that matches the pack file path, in
+	/// the order they are tried. The first entry whose options are appropriate for a matched
+	/// asset type is used.
+	pub fn matched_file_options_globs(&self) -> &[String] {
+		&self.matched_file_options_globs
+	}
+
+	/// Gets a stable, lowercase name for the processor that would optimize the pack file,
+	/// such as `json`, `png`, `passthrough` or `custom`, or `None` if it would be skipped.
+	pub const fn processor(&self) -> Option<&'static str> {
+		self.processor
+	}
+
+	/// Gets the options the processor would use, after taking global options into account,
+	/// or `None` if it does not have options or the pack file would be skipped.
+	pub const fn effective_file_options(&self) -> Option<FileOptions> {
+		self.effective_file_options
+	}
+}
+
+/// Whether a squash operation would add a pack file to the generated ZIP file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PackFileDecision {
+	/// The pack file would be processed and added to the generated ZIP file.
+	Include,
+	/// The pack file is not recognized as any asset type, but it would be added to the
+	/// generated ZIP file because its file options force it to be included.
+	ForceInclude,
+	/// The pack file would be skipped, because it is not recognized as any asset type.
+	SkipUnknown,
+	/// The pack file would be skipped, because it is a texture or sound that no other pack
+	/// file refers to, and the options say that such assets should be pruned.
+	SkipUnreferenced,
+	/// The pack file would be skipped, because it is a language file of a locale that the
+	/// options do not include.
+	SkipExcludedLocale,
+	/// Processing the pack file would fail, because its relative path may prevent Minecraft
+	/// from using it, and the options say that such path problems are errors.
+	FailInvalidPath,
+	/// Processing the pack file would fail, because it refers to assets that do not exist in
+	/// the pack, and the options say that such dangling references are errors.
+	FailDanglingReference
+}
+
+impl PackFileDecision {
+	/// Returns a stable, lowercase name for this decision.
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Include => "include",
+			Self::ForceInclude => "force_include",
+			Self::SkipUnknown => "skip_unknown",
+			Self::SkipUnreferenced => "skip_unreferenced",
+			Self::SkipExcludedLocale => "skip_excluded_locale",
+			Self::FailInvalidPath => "fail_invalid_path",
+			Self::FailDanglingReference => "fail_dangling_reference"
+		}
+	}
+}
+
+/// Reads the pack metadata file if the specified options require it, either to validate it, use
+/// automatic quirk detection or detect an asset type mask. The options are transparently modified
+/// to work around the detected quirks, if that is enabled. The read pack metadata, if any, is
+/// returned along with the mask of asset types that pack files may match.
+async fn read_pack_metadata(
+	vfs: &impl VirtualFileSystem,
+	options_holder: &mut ProcessedSquashOptions
+) -> Result<(Option<PackMetadata>, EnumSet<PackFileAssetType>), PackMetadataError> {
+	let global_options = &mut options_holder.options.global_options;
+
+	// By default, allow every known asset type to match pack files
+	let mut asset_types_mask = EnumSet::all();
+
+	if !global_options.automatic_minecraft_quirks_detection
+		&& !global_options.automatic_asset_types_mask_detection
+		&& !global_options.validate_pack_metadata_file
+	{
+		return Ok((None, asset_types_mask));
+	}
+
+	let pack_meta = PackMetadata::read(vfs, &options_holder.options.pack_directory).await?;
+
+	if global_options.automatic_minecraft_quirks_detection {
+		global_options.work_around_minecraft_quirks = pack_meta.applicable_minecraft_quirks();
+	}
+
+	if global_options.automatic_asset_types_mask_detection {
+		asset_types_mask = pack_meta.applicable_asset_type_mask();
+	}
+
+	Ok((Some(pack_meta), asset_types_mask))
+}
+
+/// Returns the file options to try, in order, for pack files that did not match any
+/// configuration-provided file options appropriate for their asset type. `None` stands
+/// for the asset types that have no options.
+fn default_file_options() -> impl Iterator<Item = Option<FileOptions>> {
+	[
+		// Language files are JSON files too, so their options must be tried first to not
+		// optimize them as generic JSON files
+		Some(FileOptions::LanguageFileOptions(
+			LanguageFileOptions::default()
+		)),
+		Some(FileOptions::JsonFileOptions(JsonFileOptions::default())),
+		Some(FileOptions::AudioFileOptions(AudioFileOptions::default())),
+		Some(FileOptions::PngFileOptions(PngFileOptions::default())),
+		#[cfg(feature = "optifine")]
+		Some(FileOptions::PropertiesFileOptions(
+			PropertiesFileOptions::default()
+		)),
+		Some(FileOptions::ShaderFileOptions(ShaderFileOptions::default())),
+		Some(FileOptions::LegacyLanguageFileOptions(
+			LegacyLanguageFileOptions::default()
+		)),
+		Some(FileOptions::CommandFunctionFileOptions(
+			CommandFunctionFileOptions::default()
+		)),
+		Some(FileOptions::CompressedCompoundNbtTagFileOptions(
+			CompressedCompoundNbtTagFileOptions::default()
+		)),
+		None
+	]
+	.into_iter()
+}
+
+/// Registers the pack file optimization as failed if the pack file task that finished with
+/// the specified result panicked.
+fn register_pack_file_task_panic<T>(
+	task_result: Result<T, JoinError>,
+	pack_file_optimization_failed: &AtomicBool
+) {
+	if task_result.is_err_and(|err| err.is_panic()) {
+		pack_file_optimization_failed.store(true, Ordering::Release);
+	}
+}
+
+/// What a squash operation would do with a pack file, as decided by [`decide_pack_file`].
+struct PackFileDecisionDetails<'options> {
+	decision: PackFileDecision,
+	asset_type: Option<PackFileAssetType>,
+	file_options_glob: Option<&'options String>,
+	file_options: Option<FileOptions>
+}
+
+/// Decides what a squash operation would do with the pack file at the specified path, in the
+/// same way pack file processing tasks do, but without opening the file. Pack files whose path
+/// is rejected by the path validator, if any, and pack files among the specified dangling
+/// reference files fail. Pack files among the specified pruned assets, and language files
+/// excluded by the locale filter, are skipped.
+fn decide_pack_file<'options>(
+	options_holder: &'options ProcessedSquashOptions,
+	asset_type_matcher: &PackFileAssetTypeMatcher,
+	path_validator: Option<&mut PackFilePathValidator>,
+	pruned_assets: &HashSet<RelativePath<'static>>,
+	locale_filter: Option<&LocaleFilter>,
+	dangling_reference_files: &HashSet<RelativePath<'static>>,
+	relative_path: &RelativePath<'static>
+) -> PackFileDecisionDetails<'options> {
+	// Check the same conditions as squash operations do before processing pack files, in the
+	// same order
+	let decision = if path_validator.is_some_and(|path_validator| {
+		path_validator
+			.validate(relative_path, asset_type_matcher)
+			.is_err()
+	}) {
+		Some(PackFileDecision::FailInvalidPath)
+	} else if pruned_assets.contains(relative_path) {
+		Some(PackFileDecision::SkipUnreferenced)
+	} else if locale_filter.is_some_and(|locale_filter| locale_filter.excludes(relative_path)) {
+		Some(PackFileDecision::SkipExcludedLocale)
+	} else if dangling_reference_files.contains(relative_path) {
+		Some(PackFileDecision::FailDanglingReference)
+	} else {
+		None
+	};
+
+	if let Some(decision) = decision {
+		return PackFileDecisionDetails {
+			decision,
+			asset_type: None,
+			file_options_glob: None,
+			file_options: None
+		};
+	}
+
+	let mut asset_type_matches = asset_type_matcher.matches_for(relative_path);
+	let have_default_options = !asset_type_matches.is_empty();
+	if !have_default_options {
+		asset_type_matches = PackFileAssetTypeMatches::of_custom_asset_type();
+	}
+
+	// Pack files are not read until their processing starts, so a dummy empty file is
+	// enough to learn what would be done with the pack file
+	let decide_with_file_options =
+		|file_options: Option<FileOptions>, file_options_glob: Option<&'options String>| {
+			let file_options = file_options.map(|file_options| {
+				file_options.tweak_from_global_options(&options_holder.options.global_options)
+			});
+
+			asset_type_matches
+				.process_data(file_options, || Some((tokio::io::empty(), 0)))
+				.map(|process_data| PackFileDecisionDetails {
+					decision: if process_data.listing_circumstances.is_force_included {
+						PackFileDecision::ForceInclude
+					} else {
+						PackFileDecision::Include
+					},
+					asset_type: Some(process_data.asset_type),
+					file_options_glob,
+					file_options
+				})
+		};
+
+	// Try file options in the same order as pack file processing tasks do
+	options_holder
+		.file_options_globs
+		.matches(&**relative_path)
+		.into_iter()
+		.find_map(|i| {
+			let (file_options_glob, file_options) =
+				options_holder.options.file_options.get_index(i).unwrap();
+
+			decide_with_file_options(Some(*file_options), Some(file_options_glob))
+		})
+		.or_else(|| {
+			if have_default_options {
+				default_file_options()
+					.find_map(|file_options| decide_with_file_options(file_options, None))
+			} else {
+				None
+			}
+		})
+		.unwrap_or(PackFileDecisionDetails {
+			decision: PackFileDecision::SkipUnknown,
+			asset_type: None,
+			file_options_glob: None,
+			file_options: None
+		})
 }
 
 /// Processes the given pack file according to the provided file options and the asset types that
@@ -689,21 +2118,34 @@ impl PackFileStatus {
 async fn match_and_process_pack_file(
 	squash_options: &SquashOptions,
 	file_options: Option<FileOptions>,
+	file_options_glob: Option<&str>,
 	squash_zip: &SquashZip<impl AsyncRead + AsyncSeek + Unpin>,
+	optimization_cache: Option<&OptimizationCache>,
 	vfs: &impl VirtualFileSystem,
 	asset_type_matches: &PackFileAssetTypeMatches,
 	pack_file_data: &VfsPackFileIterEntry,
+	pack_file_contents: Option<&[u8]>,
 	pack_file_optimization_failed: &AtomicBool,
 	pack_file_status_sender: Option<&Sender<PackSquasherStatus>>
 ) -> bool {
 	let mut pack_file_open_error = None;
 	let mut vfs_file_meta = None;
 
+	// Pack files whose contents were rewritten before processing are read from memory, but
+	// their metadata still comes from the VFS
 	let process_data =
 		asset_type_matches.process_data(file_options, || match vfs.open(&pack_file_data.file_path) {
 			Ok(vfs_file) => {
-				vfs_file_meta = Some((vfs_file.metadata, vfs_file.file_size_hint));
-				Some((vfs_file.file_read, vfs_file.file_size_hint))
+				let (file_read, file_size_hint) = match pack_file_contents {
+					Some(contents) => (
+						Either::Right(Cursor::new(contents.to_vec())),
+						contents.len() as u64
+					),
+					None => (Either::Left(vfs_file.file_read), vfs_file.file_size_hint)
+				};
+
+				vfs_file_meta = Some((vfs_file.metadata, file_size_hint));
+				Some((file_read, file_size_hint))
 			}
 			Err(err) => {
 				pack_file_open_error = Some(err);
@@ -716,12 +2158,55 @@ async fn match_and_process_pack_file(
 	if let Some(process_data) = process_data {
 		let (vfs_file_meta, pack_file_size_hint) = vfs_file_meta.unwrap();
 
+		// The source digest is only needed for the optimization cache and content hash-based
+		// previous ZIP reuse. Errors while computing it are not fatal, because the pack file can
+		// still be processed without it. If the error persists, it will be reported when
+		// processing the pack file anyway. Pack files whose processing results can't be reused,
+		// such as those handled by custom processors without an identity, have no source digest
+		let source_digest = if let Some(processing_identity) = &process_data.processing_identity
+			&& (optimization_cache.is_some()
+				|| squash_options.global_options.previous_zip_reuse_mode
+					== PreviousZipReuseMode::ContentHash)
+		{
+			match pack_file_contents.map_or_else(
+				|| {
+					vfs.open(&pack_file_data.file_path)
+						.map(|vfs_file| Either::Left(vfs_file.file_read))
+				},
+				|contents| Ok(Either::Right(Cursor::new(contents)))
+			) {
+				Ok(pack_file_read) => compute_source_digest(
+					pack_file_read,
+					process_data.asset_type.into(),
+					processing_identity,
+					file_options.as_ref(),
+					squash_options.global_options.zip_compression_iterations,
+					!squash_options.global_options.recompress_compressed_files
+						&& process_data.is_compressed
+				)
+				.await
+				.ok(),
+				Err(_) => None
+			}
+		} else {
+			None
+		};
+
+		// The modification time of pack files whose contents were rewritten in memory does not
+		// reflect whether their rewritten contents changed, so never reuse them from a previous
+		// ZIP file based on it
 		pack_file_process_failed = !process_pack_file(
 			process_data,
 			pack_file_data.relative_path.as_owned(),
-			vfs_file_meta.modification_time,
+			vfs_file_meta
+				.modification_time
+				.filter(|_| pack_file_contents.is_none()),
 			pack_file_size_hint,
+			file_options_glob,
 			squash_zip,
+			optimization_cache,
+			source_digest,
+			squash_options.global_options.previous_zip_reuse_mode,
 			pack_file_status_sender,
 			squash_options.global_options.recompress_compressed_files
 		)
@@ -738,7 +2223,9 @@ async fn match_and_process_pack_file(
 				path: pack_file_data.relative_path.as_owned(),
 				optimization_strategy: Cow::Borrowed("Error opening pack file"),
 				optimization_error: Some(err.to_string()),
-				skipped: false
+				optimization_error_kind: Some(OptimizationErrorKind::Io),
+				skipped: false,
+				report: None
 			}))
 			.await
 			.ok();
@@ -758,15 +2245,22 @@ async fn match_and_process_pack_file(
 /// should be processed and added to it.
 ///
 /// The return value is `true` if no error occurred, and `false` if some error happened.
+#[allow(clippy::too_many_arguments)] // Alternatives are not really more readable
 async fn process_pack_file(
 	pack_file_process_data: PackFileProcessData,
 	relative_path: RelativePath<'static>,
 	edit_time: Option<SystemTime>,
 	file_size_hint: u64,
+	file_options_glob: Option<&str>,
 	squash_zip: &SquashZip<impl AsyncRead + AsyncSeek + Unpin>,
+	optimization_cache: Option<&OptimizationCache>,
+	source_digest: Option<SourceDigest>,
+	previous_zip_reuse_mode: PreviousZipReuseMode,
 	pack_file_status_sender: Option<&Sender<PackSquasherStatus>>,
 	compress_already_compressed: bool
 ) -> bool {
+	let processing_start_instant = Instant::now();
+
 	// We may have to change the file extension to a canonical one that's accepted by Minecraft.
 	// Do that early, because we store the file with the canonical extension in the ZIP
 	let pack_file_path = match pack_file_process_data.canonical_extension {
@@ -780,25 +2274,64 @@ async fn process_pack_file(
 		None => relative_path
 	};
 
-	let copy_previous_file = squash_zip.file_process_time(&pack_file_path).map_or_else(
-		|| false,
-		|squash_time| edit_time.is_some() && Some(squash_time) >= edit_time
-	);
+	let copy_previous_file = match previous_zip_reuse_mode {
+		PreviousZipReuseMode::ModificationTime => {
+			squash_zip.file_process_time(&pack_file_path).map_or_else(
+				|| false,
+				|squash_time| edit_time.is_some() && Some(squash_time) >= edit_time
+			)
+		}
+		PreviousZipReuseMode::ContentHash => {
+			source_digest.is_some() && squash_zip.file_source_digest(&pack_file_path) == source_digest
+		}
+	};
+
+	// Only store source digests in the output ZIP file when they will be used to reuse it,
+	// because they take space
+	let stored_source_digest =
+		source_digest.filter(|_| previous_zip_reuse_mode == PreviousZipReuseMode::ContentHash);
 
 	let mut optimization_error = None;
+	let mut optimization_error_kind = None;
 	let optimization_strategy;
+	let squash_zip_result;
+	let mut copied_from_optimization_cache = false;
 
 	if copy_previous_file {
-		optimization_error = squash_zip
+		squash_zip_result = squash_zip
 			.add_previous_file(
 				&pack_file_path,
 				pack_file_process_data.listing_circumstances
 			)
-			.await
-			.err()
-			.map(|err| err.to_string());
+			.await;
 
 		optimization_strategy = Cow::Borrowed("Copied from previous run");
+	} else if let Some(optimization_cache) = optimization_cache
+		&& let Some(source_digest) = &source_digest
+		&& let Some(cached_entry) = optimization_cache.get(source_digest).await
+	{
+		squash_zip_result = match squash_zip
+			.precompressed_file(cached_entry.info, &cached_entry.compressed_data)
+			.await
+		{
+			Ok(mut compressed_file) => {
+				if let Some(source_digest) = stored_source_digest {
+					compressed_file.set_source_digest(source_digest);
+				}
+
+				squash_zip
+					.add_compressed_file(
+						&pack_file_path,
+						compressed_file,
+						pack_file_process_data.listing_circumstances
+					)
+					.await
+			}
+			Err(err) => Err(err)
+		};
+
+		copied_from_optimization_cache = true;
+		optimization_strategy = Cow::Borrowed("Copied from optimization cache");
 	} else {
 		let mut processed_pack_file_chunks = pack_file_process_data
 			.optimized_byte_chunks_stream
@@ -827,6 +2360,7 @@ async fn process_pack_file(
 			.take_while(|chunk| {
 				future::ready(if let Err(err) = chunk {
 					optimization_error = Some(err.to_string());
+					optimization_error_kind = Some(err.kind());
 
 					false
 				} else {
@@ -835,29 +2369,91 @@ async fn process_pack_file(
 			})
 			.map(|chunk| BoxedDynAsByteSliceRef(chunk.unwrap().1));
 
-		let squash_zip_error = squash_zip
-			.add_file(
-				&pack_file_path,
+		let compress_result = squash_zip
+			.compress_file(
 				processed_pack_file_chunks,
 				!compress_already_compressed && pack_file_process_data.is_compressed,
-				file_size_hint.try_into().unwrap_or(0),
-				pack_file_process_data.listing_circumstances
+				file_size_hint.try_into().unwrap_or(0)
 			)
-			.await
-			.err()
-			.map(|err| err.to_string());
+			.await;
+
+		squash_zip_result = match compress_result {
+			Ok(mut compressed_file) => {
+				// Only store files that were processed successfully in the cache, so that
+				// errors are reported again in future runs
+				if let Some(optimization_cache) = optimization_cache
+					&& let Some(source_digest) = &source_digest
+					&& optimization_error.is_none()
+					&& let Err(err) = optimization_cache
+						.put(source_digest, &mut compressed_file)
+						.await && let Some(tx) = pack_file_status_sender
+				{
+					tx.send(PackSquasherStatus::Warning(
+						PackSquasherWarning::OptimizationCacheWriteError(err)
+					))
+					.await
+					.ok();
+				}
+
+				if let Some(source_digest) = stored_source_digest
+					&& optimization_error.is_none()
+				{
+					compressed_file.set_source_digest(source_digest);
+				}
 
-		optimization_error = optimization_error.or(squash_zip_error);
+				squash_zip
+					.add_compressed_file(
+						&pack_file_path,
+						compressed_file,
+						pack_file_process_data.listing_circumstances
+					)
+					.await
+			}
+			Err(err) => Err(err)
+		};
 	}
 
+	// Errors that happened while processing the file take precedence over the errors
+	// that happened while adding it to the ZIP file, as the former cause the latter
+	let stored_file_info = match squash_zip_result {
+		Ok(stored_file_info) => Some(stored_file_info),
+		Err(err) => {
+			if optimization_error.is_none() {
+				optimization_error = Some(err.to_string());
+				optimization_error_kind = Some(OptimizationErrorKind::SquashZip);
+			}
+			None
+		}
+	};
+
 	let all_ok = optimization_error.is_none();
 
 	if let Some(tx) = pack_file_status_sender {
+		let report = stored_file_info
+			.filter(|_| all_ok)
+			.map(|stored_file_info| PackFileReport {
+				input_size: file_size_hint,
+				processed_size: stored_file_info.uncompressed_size,
+				compressed_size: stored_file_info.compressed_size,
+				compression_method: if stored_file_info.deflated {
+					PackFileCompressionMethod::Deflate
+				} else {
+					PackFileCompressionMethod::Store
+				},
+				asset_type: pack_file_process_data.asset_type.into(),
+				file_options_glob: file_options_glob.map(str::to_owned),
+				copied_from_previous_zip: copy_previous_file,
+				copied_from_optimization_cache,
+				processing_time: processing_start_instant.elapsed()
+			});
+
 		tx.send(PackSquasherStatus::PackFileProcessed(PackFileStatus {
 			path: pack_file_path,
 			optimization_strategy,
 			optimization_error,
-			skipped: false
+			optimization_error_kind,
+			skipped: false,
+			report
 		}))
 		.await
 		.ok();
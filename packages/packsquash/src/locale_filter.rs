@@ -0,0 +1,122 @@
+//! Contains the filtering of the translations of a pack by locale, which leaves out the language
+//! files of the locales that are not included, and their declarations in the pack metadata file.
+
+use std::{io, path::Path};
+
+use ahash::HashSet;
+use json_comments::StripComments;
+use serde_json::Value;
+use tokio::io::AsyncReadExt;
+
+use crate::RelativePath;
+use crate::pack_file::strip_utf8_bom;
+use crate::pack_metadata::{PackLayerDirectoryName, PackLayerPathPrefixes};
+use crate::vfs::VirtualFileSystem;
+
+#[cfg(test)]
+mod tests;
+
+/// The locale that is always included, because Minecraft falls back to its strings when the
+/// selected locale does not define some key.
+const FALLBACK_LOCALE: &str = "en_us";
+
+/// Tells apart the language files of the locales to include in a pack from the rest.
+pub(crate) struct LocaleFilter {
+	layer_path_prefixes: PackLayerPathPrefixes,
+	/// The lowercase codes of the included locales.
+	included_locales: HashSet<String>
+}
+
+impl LocaleFilter {
+	/// Creates a filter that includes the specified locales, and the fallback locale, in a pack
+	/// with the specified pack layers. Locale codes are compared case-insensitively, as legacy
+	/// language files are named after locale codes with uppercase letters, like `en_US`.
+	pub(crate) fn new<'layers>(
+		included_locales: &[String],
+		pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName>
+	) -> Self {
+		Self {
+			layer_path_prefixes: PackLayerPathPrefixes::new(pack_layer_directory_names),
+			included_locales: included_locales
+				.iter()
+				.map(|locale| locale.to_lowercase())
+				.chain([FALLBACK_LOCALE.to_string()])
+				.collect()
+		}
+	}
+
+	/// Checks whether the pack file at the specified relative path is a language file of a
+	/// locale that is not included.
+	pub(crate) fn excludes(&self, relative_path: &RelativePath<'_>) -> bool {
+		let layer_relative_path = self
+			.layer_path_prefixes
+			.layer_relative_path(relative_path.as_str());
+
+		let Some((namespace, file_name)) = layer_relative_path
+			.strip_prefix("assets/")
+			.and_then(|path| path.split_once("/lang/"))
+		else {
+			return false;
+		};
+
+		let Some(locale) = [".json", ".jsonc", ".lang"]
+			.into_iter()
+			.find_map(|extension| file_name.strip_suffix(extension))
+		else {
+			return false;
+		};
+
+		!namespace.contains('/') && !locale.contains('/') && !self.includes_locale(locale)
+	}
+
+	/// Checks whether the specified locale is included.
+	fn includes_locale(&self, locale: &str) -> bool {
+		self.included_locales.contains(&locale.to_lowercase())
+	}
+
+	/// Reads the pack metadata file in the specified pack directory, returning its relative path
+	/// and its contents without the language declarations of the locales that are not included,
+	/// if it declares any. `None` is returned if there is nothing to leave out, or the file can't
+	/// be parsed, in which case it will be reported as an error when processed.
+	pub(crate) async fn filter_pack_metadata(
+		&self,
+		vfs: &impl VirtualFileSystem,
+		pack_directory: &Path
+	) -> Result<Option<(RelativePath<'static>, Vec<u8>)>, io::Error> {
+		// The pack metadata file with comments takes precedence, as when reading pack metadata
+		let (relative_path, mut vfs_file) = match vfs.open(pack_directory.join("pack.mcmetac")) {
+			Ok(vfs_file) => ("pack.mcmetac", vfs_file),
+			Err(_) => match vfs.open(pack_directory.join("pack.mcmeta")) {
+				Ok(vfs_file) => ("pack.mcmeta", vfs_file),
+				Err(_) => return Ok(None)
+			}
+		};
+
+		let mut pack_metadata_buf = vec![];
+		vfs_file
+			.file_read
+			.read_to_end(&mut pack_metadata_buf)
+			.await?;
+
+		let Ok(mut pack_metadata) = serde_json::from_reader::<_, Value>(StripComments::new(
+			strip_utf8_bom(&pack_metadata_buf)
+		)) else {
+			return Ok(None);
+		};
+
+		let Some(Value::Object(language_section)) = pack_metadata.get_mut("language") else {
+			return Ok(None);
+		};
+
+		let declared_locales = language_section.len();
+		language_section.retain(|locale, _| self.includes_locale(locale));
+		if language_section.len() == declared_locales {
+			return Ok(None);
+		}
+
+		Ok(Some((
+			RelativePath::from_inner(relative_path),
+			serde_json::to_vec(&pack_metadata)?
+		)))
+	}
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use pretty_assertions::assert_eq;
+
+use crate::RelativePath;
+use crate::vfs::memory_fs::MemoryFilesystem;
+
+use super::LocaleFilter;
+
+#[test]
+fn language_files_of_other_locales_are_excluded() {
+	let locale_filter = LocaleFilter::new(
+		&["es_es".to_string(), "DE_de".to_string()],
+		[arcstr::literal!(""), arcstr::literal!("overlay")].iter()
+	);
+
+	assert_eq!(
+		[
+			"assets/my_pack/lang/en_us.json",
+			"assets/my_pack/lang/es_es.json",
+			"assets/my_pack/lang/de_de.jsonc",
+			"assets/minecraft/lang/en_US.lang",
+			"assets/minecraft/lang/es_ES.lang",
+			"assets/my_pack/lang/fr_fr.json",
+			"assets/minecraft/lang/fr_FR.lang",
+			"overlay/assets/my_pack/lang/fr_fr.json",
+			"assets/my_pack/textures/lang/fr_fr.json",
+			"assets/my_pack/lang/fr_fr.txt"
+		]
+		.map(|relative_path| locale_filter.excludes(&RelativePath::from_inner(relative_path))),
+		[
+			false, false, false, false, false, true, true, true, false, false
+		]
+	);
+}
+
+#[tokio::test]
+async fn pack_metadata_languages_of_other_locales_are_left_out() {
+	let mut vfs = MemoryFilesystem::new();
+	vfs.add_file(
+		"pack.mcmeta",
+		r#"{
+			"pack": { "pack_format": 4, "description": "My pack" },
+			// Custom languages declared by the pack
+			"language": {
+				"tlh_aa": { "name": "tlhIngan Hol", "region": "Qo'noS", "bidirectional": false },
+				"qya_aa": { "name": "Quenya", "region": "Arda", "bidirectional": false }
+			}
+		}"#,
+		None
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	let (relative_path, pack_metadata) =
+		LocaleFilter::new(&["qya_aa".to_string()], [arcstr::literal!("")].iter())
+			.filter_pack_metadata(&vfs, Path::new(""))
+			.await
+			.expect("I/O operations are assumed not to fail during tests")
+			.expect("Some language should be left out");
+
+	assert_eq!(relative_path.as_str(), "pack.mcmeta");
+	assert_eq!(
+		String::from_utf8(pack_metadata).expect("The result should be a UTF-8 string"),
+		r#"{"pack":{"pack_format":4,"description":"My pack"},"language":{"qya_aa":{"name":"Quenya","region":"Arda","bidirectional":false}}}"#
+	);
+}
+
+#[tokio::test]
+async fn pack_metadata_without_other_locales_is_left_alone() {
+	let mut vfs = MemoryFilesystem::new();
+	vfs.add_file(
+		"pack.mcmeta",
+		r#"{ "pack": { "pack_format": 4, "description": "My pack" } }"#,
+		None
+	)
+	.expect("I/O operations are assumed not to fail during tests");
+
+	assert!(
+		LocaleFilter::new(&["qya_aa".to_string()], [arcstr::literal!("")].iter())
+			.filter_pack_metadata(&vfs, Path::new(""))
+			.await
+			.expect("I/O operations are assumed not to fail during tests")
+			.is_none()
+	);
+}
@@ -0,0 +1,138 @@
+//! Contains the data types that support a persistent, content-addressed cache of optimized and
+//! compressed pack files, which allows reusing them in future runs.
+
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::output_manifest::hex_string;
+use crate::squash_zip::{CompressedFile, SourceDigest, StoredFileInfo};
+
+#[cfg(test)]
+mod tests;
+
+/// The magic bytes that every optimization cache entry starts with. The last byte is the
+/// version of the entry format, which must be changed whenever that format changes.
+const ENTRY_MAGIC: [u8; 5] = *b"PSOC\x01";
+
+/// The size of the header of an optimization cache entry, which precedes the compressed
+/// data of the pack file. After the magic bytes, it contains the CRC-32 of the pack file
+/// data, its uncompressed and compressed sizes, whether it was deflated, and the CRC-32
+/// of the compressed data, which is used to detect corrupt entries.
+const ENTRY_HEADER_SIZE: usize = ENTRY_MAGIC.len() + 4 + 4 + 4 + 1 + 4;
+
+/// A counter used to generate unique names for temporary entry files.
+static TEMPORARY_ENTRY_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A persistent cache of optimized and compressed pack files, stored in a directory. Entries
+/// are looked up by the source digest of the pack files, which is derived from their contents
+/// and every parameter that affects how those contents are optimized and compressed, so the
+/// cache can be safely shared among different packs, runs and machines.
+pub(crate) struct OptimizationCache {
+	directory: PathBuf
+}
+
+/// An entry read from an [`OptimizationCache`], which describes how a pack file was compressed
+/// and contains its compressed data.
+pub(crate) struct OptimizationCacheEntry {
+	pub info: StoredFileInfo,
+	pub compressed_data: Vec<u8>
+}
+
+impl OptimizationCache {
+	/// Opens the optimization cache stored in the specified directory, creating the directory
+	/// if it does not exist.
+	pub async fn new(directory: impl Into<PathBuf>) -> Result<Self, io::Error> {
+		let directory = directory.into();
+
+		tokio::fs::create_dir_all(&directory).await?;
+
+		Ok(Self { directory })
+	}
+
+	/// Gets the entry for the specified source digest from this cache. Entries that can't be read or
+	/// are corrupt are treated as missing, because they will be overwritten when the pack file
+	/// is processed again.
+	pub async fn get(&self, source_digest: &SourceDigest) -> Option<OptimizationCacheEntry> {
+		let mut entry = tokio::fs::read(self.entry_path(source_digest)).await.ok()?;
+
+		if entry.len() < ENTRY_HEADER_SIZE || entry[..ENTRY_MAGIC.len()] != ENTRY_MAGIC {
+			return None;
+		}
+
+		let header_field = |offset: usize| {
+			u32::from_le_bytes(
+				entry[ENTRY_MAGIC.len() + offset..ENTRY_MAGIC.len() + offset + 4]
+					.try_into()
+					.unwrap()
+			)
+		};
+		let info = StoredFileInfo {
+			crc32: header_field(0),
+			uncompressed_size: header_field(4),
+			compressed_size: header_field(8),
+			deflated: entry[ENTRY_MAGIC.len() + 12] != 0
+		};
+		let compressed_data_crc32 = header_field(13);
+
+		let compressed_data = entry.split_off(ENTRY_HEADER_SIZE);
+		(compressed_data.len() == info.compressed_size as usize
+			&& crc32fast::hash(&compressed_data) == compressed_data_crc32)
+			.then_some(OptimizationCacheEntry {
+				info,
+				compressed_data
+			})
+	}
+
+	/// Stores the specified compressed file in this cache, for the specified source digest.
+	/// Entries are written atomically, so that concurrent readers, which may be other PackSquash
+	/// processes sharing the cache directory, never read partially written entries.
+	pub async fn put(
+		&self,
+		source_digest: &SourceDigest,
+		compressed_file: &mut CompressedFile
+	) -> Result<(), io::Error> {
+		let info = compressed_file.info();
+
+		let mut entry = Vec::with_capacity(ENTRY_HEADER_SIZE + info.compressed_size as usize);
+		entry.extend_from_slice(&ENTRY_MAGIC);
+		entry.extend_from_slice(&info.crc32.to_le_bytes());
+		entry.extend_from_slice(&info.uncompressed_size.to_le_bytes());
+		entry.extend_from_slice(&info.compressed_size.to_le_bytes());
+		entry.push(info.deflated.into());
+		entry.extend_from_slice(&[0; 4]); // Compressed data CRC-32, filled in later
+		compressed_file.read_data_to_end(&mut entry).await?;
+
+		let compressed_data_crc32 = crc32fast::hash(&entry[ENTRY_HEADER_SIZE..]);
+		entry[ENTRY_HEADER_SIZE - 4..ENTRY_HEADER_SIZE]
+			.copy_from_slice(&compressed_data_crc32.to_le_bytes());
+
+		let entry_path = self.entry_path(source_digest);
+		tokio::fs::create_dir_all(entry_path.parent().unwrap()).await?;
+
+		let temporary_entry_path = entry_path.with_extension(format!(
+			"{}-{}.tmp",
+			process::id(),
+			TEMPORARY_ENTRY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+		));
+		tokio::fs::write(&temporary_entry_path, entry).await?;
+
+		if let Err(err) = tokio::fs::rename(&temporary_entry_path, &entry_path).await {
+			tokio::fs::remove_file(&temporary_entry_path).await.ok();
+			return Err(err);
+		}
+
+		Ok(())
+	}
+
+	/// Returns the path of the file that stores the entry for the specified source digest.
+	/// Entries are spread among subdirectories named after the first byte of their content
+	/// hash, to keep the size of each directory manageable.
+	fn entry_path(&self, source_digest: &SourceDigest) -> PathBuf {
+		let key =
+			hex_string(&source_digest.content_hash) + &hex_string(&source_digest.options_fingerprint);
+
+		self.directory.join(&key[..2]).join(&key[2..])
+	}
+}
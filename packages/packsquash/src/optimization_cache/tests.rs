@@ -0,0 +1,151 @@
+use pretty_assertions::assert_eq;
+use tokio::fs::File;
+
+use crate::squash_zip::{SquashZip, SquashZipSettings, ZipArchiveCommentString};
+
+use super::*;
+
+static UNEXPECTED_IO_FAILURE: &str = "I/O operations are assumed not to fail during tests";
+
+async fn example_cache_and_directory() -> (OptimizationCache, tempfile::TempDir) {
+	let cache_directory = tempfile::tempdir().expect(UNEXPECTED_IO_FAILURE);
+
+	(
+		OptimizationCache::new(cache_directory.path().join("cache"))
+			.await
+			.expect(UNEXPECTED_IO_FAILURE),
+		cache_directory
+	)
+}
+
+fn example_source_digest() -> SourceDigest {
+	SourceDigest {
+		content_hash: [1; 32],
+		options_fingerprint: [2; 32]
+	}
+}
+
+async fn compressed_file(data: &[u8]) -> CompressedFile {
+	SquashZip::<File>::new(
+		None,
+		SquashZipSettings {
+			zopfli_iterations: 20,
+			store_squash_time: false,
+			enable_obfuscation: false,
+			enable_deduplication: false,
+			enable_size_increasing_obfuscation: false,
+			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+			workaround_old_java_obfuscation_quirks: false,
+			spool_buffer_size: 64 * 1024,
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
+		}
+	)
+	.await
+	.map_err(|(err, _)| err)
+	.expect("No error should happen while creating the SquashZip instance")
+	.compress_file(tokio_stream::iter([data]), false, data.len())
+	.await
+	.expect("Compressing data should not fail")
+}
+
+#[tokio::test]
+async fn entry_paths_depend_on_every_digest_field() {
+	let (cache, _cache_directory) = example_cache_and_directory().await;
+	let source_digest = example_source_digest();
+	let reference_path = cache.entry_path(&source_digest);
+
+	assert_eq!(
+		cache.entry_path(&example_source_digest()),
+		reference_path,
+		"Entry paths should be deterministic"
+	);
+
+	// The first byte of the content hash names the entry subdirectory, so change it, as well
+	// as the bytes at both ends of the rest of the entry path
+	let other_paths =
+		[(true, 0), (true, 31), (false, 0), (false, 31)].map(|(change_content_hash, byte_index)| {
+			let mut source_digest = example_source_digest();
+			let digest_field = if change_content_hash {
+				&mut source_digest.content_hash
+			} else {
+				&mut source_digest.options_fingerprint
+			};
+			digest_field[byte_index] = 0;
+
+			cache.entry_path(&source_digest)
+		});
+
+	for (i, other_path) in other_paths.iter().enumerate() {
+		assert_ne!(
+			*other_path, reference_path,
+			"Entry paths should change when any digest byte changes"
+		);
+
+		for another_path in &other_paths[i + 1..] {
+			assert_ne!(
+				other_path, another_path,
+				"Distinct digests should not share entries"
+			);
+		}
+	}
+}
+
+#[tokio::test]
+async fn put_and_get_work() {
+	let (cache, _cache_directory) = example_cache_and_directory().await;
+	let data = b"Hello, world! Hello, world! Hello, world!";
+
+	let source_digest = example_source_digest();
+
+	assert!(
+		cache.get(&source_digest).await.is_none(),
+		"The cache should be empty"
+	);
+
+	let mut compressed_file = compressed_file(data).await;
+	cache
+		.put(&source_digest, &mut compressed_file)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	let mut compressed_data = Vec::new();
+	compressed_file
+		.read_data_to_end(&mut compressed_data)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	let entry = cache
+		.get(&source_digest)
+		.await
+		.expect("The cache should contain the stored entry");
+	assert_eq!(entry.info, compressed_file.info());
+	assert_eq!(entry.compressed_data, compressed_data);
+}
+
+#[tokio::test]
+async fn corrupt_entries_are_ignored() {
+	let (cache, _cache_directory) = example_cache_and_directory().await;
+	let data = b"Hello, world!";
+
+	let source_digest = example_source_digest();
+
+	cache
+		.put(&source_digest, &mut compressed_file(data).await)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	let entry_path = cache.entry_path(&source_digest);
+	let mut entry = tokio::fs::read(&entry_path)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+	*entry.last_mut().unwrap() ^= 0xFF;
+	tokio::fs::write(&entry_path, entry)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	assert!(
+		cache.get(&source_digest).await.is_none(),
+		"Corrupt entries should be treated as missing"
+	);
+}
@@ -0,0 +1,84 @@
+//! Contains the data types that describe the manifest file that may be written alongside the
+//! output ZIP file, to ease its distribution.
+
+use std::fmt::Write;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::pack_metadata::PackFormatVersion;
+use crate::squash_zip::OutputZipDigests;
+
+#[cfg(test)]
+mod tests;
+
+/// A manifest that describes a generated output ZIP file, containing the information needed to
+/// distribute it to Minecraft clients, such as the SHA-1 digest that servers send to clients
+/// so they can check the integrity of the downloaded pack.
+#[derive(Serialize)]
+pub(crate) struct OutputManifest {
+	/// The size of the output ZIP file, in bytes.
+	size: u64,
+	/// The hexadecimal SHA-1 digest of the output ZIP file.
+	sha1: String,
+	/// The hexadecimal SHA-256 digest of the output ZIP file.
+	sha256: String,
+	/// The minimum pack format version supported by the pack, if known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	min_pack_format: Option<PackFormatVersion>,
+	/// The maximum pack format version supported by the pack, if known.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	max_pack_format: Option<PackFormatVersion>
+}
+
+impl OutputManifest {
+	/// Creates a new output manifest for an output ZIP file with the specified digests, which
+	/// contains a pack that supports the specified range of pack format versions, if known.
+	pub fn new(
+		digests: &OutputZipDigests,
+		pack_format_range: Option<RangeInclusive<PackFormatVersion>>
+	) -> Self {
+		let (min_pack_format, max_pack_format) =
+			pack_format_range.map(RangeInclusive::into_inner).unzip();
+
+		Self {
+			size: digests.size(),
+			sha1: digests.sha1_hex(),
+			sha256: digests.sha256_hex(),
+			min_pack_format,
+			max_pack_format
+		}
+	}
+
+	/// Serializes this manifest to the contents of a manifest file at the specified path. The
+	/// TOML format is used if the path has a `toml` extension, and JSON is used otherwise.
+	pub fn to_file_contents(&self, path: &Path) -> Result<String, io::Error> {
+		if path
+			.extension()
+			.is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+		{
+			toml::to_string(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+		} else {
+			serde_json::to_string_pretty(self).map_err(io::Error::from)
+		}
+	}
+
+	/// Writes this manifest to a file at the specified path, in the format expected for
+	/// its extension.
+	pub async fn write(&self, path: &Path) -> Result<(), io::Error> {
+		tokio::fs::write(path, self.to_file_contents(path)?).await
+	}
+}
+
+/// Returns the lowercase hexadecimal representation of the specified bytes.
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+	bytes.iter().fold(
+		String::with_capacity(bytes.len() * 2),
+		|mut hex_string, byte| {
+			write!(hex_string, "{byte:02x}").unwrap();
+			hex_string
+		}
+	)
+}
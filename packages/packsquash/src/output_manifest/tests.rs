@@ -0,0 +1,52 @@
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+fn example_manifest() -> OutputManifest {
+	OutputManifest {
+		size: 1024,
+		sha1: hex_string(&[0xAB; 20]),
+		sha256: hex_string(&[0x01; 32]),
+		min_pack_format: None,
+		max_pack_format: None
+	}
+}
+
+#[test]
+fn hex_string_works() {
+	assert_eq!(hex_string(&[0x00, 0x0F, 0xA0, 0xFF]), "000fa0ff");
+	assert_eq!(hex_string(&[]), "");
+}
+
+#[test]
+fn json_manifest_works() {
+	let manifest_contents = example_manifest()
+		.to_file_contents(Path::new("pack.json"))
+		.expect("Serializing the manifest should not fail");
+
+	assert_eq!(
+		serde_json::from_str::<serde_json::Value>(&manifest_contents)
+			.expect("The manifest should be valid JSON"),
+		serde_json::json!({
+			"size": 1024,
+			"sha1": "ab".repeat(20),
+			"sha256": "01".repeat(32)
+		})
+	);
+}
+
+#[test]
+fn toml_manifest_works() {
+	let manifest_contents = example_manifest()
+		.to_file_contents(Path::new("pack.TOML"))
+		.expect("Serializing the manifest should not fail");
+
+	assert_eq!(
+		manifest_contents,
+		format!(
+			"size = 1024\nsha1 = \"{}\"\nsha256 = \"{}\"\n",
+			"ab".repeat(20),
+			"01".repeat(32)
+		)
+	);
+}
@@ -11,6 +11,20 @@ use thiserror::Error;
 use tokio::io::AsyncRead;
 use tokio_stream::Stream;
 
+pub use audio_file::OptimizationErrorKind as AudioFileOptimizationErrorKind;
+pub use command_function_file::OptimizationErrorKind as CommandFunctionFileOptimizationErrorKind;
+pub use compressed_compound_nbt_tag_file::OptimizationErrorKind as CompressedCompoundNbtTagFileOptimizationErrorKind;
+pub use custom_pack_file::{
+	CustomOptimizationError, CustomOptimizedByteChunksStream, CustomPackFileProcessor,
+	CustomPackFileProcessors
+};
+pub use json_file::OptimizationErrorKind as JsonFileOptimizationErrorKind;
+pub use language_file::OptimizationErrorKind as LanguageFileOptimizationErrorKind;
+pub use legacy_lang_file::OptimizationErrorKind as LegacyLanguageFileOptimizationErrorKind;
+pub use png_file::OptimizationErrorKind as PngFileOptimizationErrorKind;
+#[cfg(feature = "optifine")]
+pub use properties_file::OptimizationErrorKind as PropertiesFileOptimizationErrorKind;
+pub use shader_file::OptimizationErrorKind as ShaderFileOptimizationErrorKind;
 pub use util::strip_utf8_bom;
 
 use crate::pack_file::asset_type::PackFileAssetType;
@@ -23,7 +37,9 @@ mod util;
 mod audio_file;
 mod command_function_file;
 mod compressed_compound_nbt_tag_file;
+mod custom_pack_file;
 mod json_file;
+mod language_file;
 mod legacy_lang_file;
 mod passthrough_file;
 mod png_file;
@@ -44,12 +60,80 @@ pub enum OptimizationError {
 	#[doc(cfg(feature = "optifine"))]
 	PropertiesFile(#[from] properties_file::OptimizationError),
 	ShaderFile(#[from] shader_file::OptimizationError),
+	LanguageFile(#[from] language_file::OptimizationError),
 	LegacyLanguageFile(#[from] legacy_lang_file::OptimizationError),
 	CommandFunctionFile(#[from] command_function_file::OptimizationError),
 	CompressedCompoundNbtTagFile(#[from] compressed_compound_nbt_tag_file::OptimizationError),
+	CustomFile(#[from] CustomOptimizationError),
 	IoError(#[from] io::Error)
 }
 
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::AudioFile(err) => OptimizationErrorKind::AudioFile(err.kind()),
+			Self::JsonFile(err) => OptimizationErrorKind::JsonFile(err.kind()),
+			Self::PngFile(err) => OptimizationErrorKind::PngFile(err.kind()),
+			#[cfg(feature = "optifine")]
+			Self::PropertiesFile(err) => OptimizationErrorKind::PropertiesFile(err.kind()),
+			Self::ShaderFile(err) => OptimizationErrorKind::ShaderFile(err.kind()),
+			Self::LanguageFile(err) => OptimizationErrorKind::LanguageFile(err.kind()),
+			Self::LegacyLanguageFile(err) => OptimizationErrorKind::LegacyLanguageFile(err.kind()),
+			Self::CommandFunctionFile(err) => OptimizationErrorKind::CommandFunctionFile(err.kind()),
+			Self::CompressedCompoundNbtTagFile(err) => {
+				OptimizationErrorKind::CompressedCompoundNbtTagFile(err.kind())
+			}
+			Self::CustomFile(_) => OptimizationErrorKind::CustomFile,
+			Self::IoError(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
+/// The kind of an error that occurred while processing a pack file, which allows telling
+/// errors apart programmatically. Unlike error messages, kinds are stable between releases,
+/// although new kinds may be added.
+///
+/// Errors that happen while optimizing files of some type have a kind specific to that type,
+/// which includes the line number where the error was detected, if available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// An error that happened while optimizing an audio file.
+	AudioFile(AudioFileOptimizationErrorKind),
+	/// An error that happened while optimizing a JSON file.
+	JsonFile(JsonFileOptimizationErrorKind),
+	/// An error that happened while optimizing a PNG file.
+	PngFile(PngFileOptimizationErrorKind),
+	/// An error that happened while optimizing a properties file.
+	#[cfg(feature = "optifine")]
+	#[doc(cfg(feature = "optifine"))]
+	PropertiesFile(PropertiesFileOptimizationErrorKind),
+	/// An error that happened while optimizing a shader file.
+	ShaderFile(ShaderFileOptimizationErrorKind),
+	/// An error that happened while optimizing a language file.
+	LanguageFile(LanguageFileOptimizationErrorKind),
+	/// An error that happened while optimizing a legacy language file.
+	LegacyLanguageFile(LegacyLanguageFileOptimizationErrorKind),
+	/// An error that happened while optimizing a command function file.
+	CommandFunctionFile(CommandFunctionFileOptimizationErrorKind),
+	/// An error that happened while optimizing a compressed compound NBT tag file.
+	CompressedCompoundNbtTagFile(CompressedCompoundNbtTagFileOptimizationErrorKind),
+	/// An error yielded by a custom pack file processor.
+	CustomFile,
+	/// An I/O error that happened while reading the pack file, or listing the files of
+	/// the pack.
+	Io,
+	/// An error that happened while adding the pack file to the output ZIP file.
+	SquashZip,
+	/// The pack file refers to some asset that does not exist in the pack. These errors are
+	/// only reported when dangling references are configured to be errors.
+	DanglingReference,
+	/// The relative path of the pack file may prevent Minecraft from using it. These errors are
+	/// only reported when path problems are configured to be errors.
+	InvalidPath
+}
+
 /// The result of processing a chunk of pack file bytes to an optimized representation, boxed to
 /// use dynamic dispatch.
 pub type OptimizedBoxedBytesChunk =
@@ -111,6 +195,14 @@ trait PackFile {
 	fn is_force_included(&self) -> bool {
 		false
 	}
+
+	/// Returns a string that identifies how this pack file is processed, beyond its asset type and
+	/// optimization settings, or `None` if the results of processing it can't be reused for
+	/// identical inputs. Most pack files are always processed in the same way for a given asset
+	/// type and settings, so they should return an empty string here.
+	fn processing_identity(&self) -> Option<Cow<'static, str>> {
+		Some(Cow::Borrowed(""))
+	}
 }
 
 /// Factory trait for a [`PackFile`] that allows it to be instantiated in a standard way. It is separated
@@ -150,5 +242,11 @@ pub struct PackFileProcessData {
 	pub canonical_extension: Option<&'static str>,
 	/// The circumstances affecting how this file is listed (i.e., enumerated) alongside other
 	/// pack files of its type by the game.
-	pub listing_circumstances: FileListingCircumstances
+	pub listing_circumstances: FileListingCircumstances,
+	/// The asset type this pack file was processed as.
+	pub asset_type: PackFileAssetType,
+	/// A string that identifies how this pack file is processed, beyond its asset type and
+	/// optimization settings. If `None`, the results of processing this pack file must not be
+	/// reused from the optimization cache or previous ZIP files based on its source digest.
+	pub processing_identity: Option<Cow<'static, str>>
 }
@@ -1,12 +1,13 @@
 //! Contains code to identify a pack file's asset type, which is used to define the optimizations
 //! that can be done to a file.
 
-use std::{borrow::Cow, fmt::Debug};
+use std::{borrow::Cow, fmt::Debug, sync::Arc};
 
 use enumset::{EnumSet, EnumSetType};
 use futures::StreamExt;
 use globset::{GlobSet, GlobSetBuilder};
 use itertools::Itertools;
+use strum::IntoStaticStr;
 use tokio::io::AsyncRead;
 
 use super::{AsyncReadAndSizeHint, PackFile, PackFileConstructor, PackFileProcessData};
@@ -14,7 +15,11 @@ use crate::config::{GlobalOptions, compile_pack_file_glob_pattern};
 use crate::pack_file::audio_file::AudioFile;
 use crate::pack_file::command_function_file::CommandFunctionFile;
 use crate::pack_file::compressed_compound_nbt_tag_file::CompressedCompoundNbtTagFile;
+use crate::pack_file::custom_pack_file::{
+	CustomPackFile, CustomPackFileProcessor, CustomPackFileProcessorMatcher, CustomPackFileProcessors
+};
 use crate::pack_file::json_file::JsonFile;
+use crate::pack_file::language_file::LanguageFile;
 use crate::pack_file::legacy_lang_file::LegacyLanguageFile;
 use crate::pack_file::passthrough_file::PassthroughFile;
 use crate::pack_file::png_file::PngFile;
@@ -34,7 +39,8 @@ use crate::{
 // When adding or removing variants from this enumeration, make sure to update the PackFileAssetTypeMatches
 // and tweak_asset_types_mask_from_global_options implementations too. Also check out file-type
 // specific code that may do different things depending on the asset type
-#[derive(Debug, EnumSetType)]
+#[derive(Debug, EnumSetType, IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
 #[repr(usize)]
 pub enum PackFileAssetType {
 	/// A Minecraft texture metadata asset, with `.mcmeta` extension. These files describe
@@ -97,6 +103,11 @@ pub enum PackFileAssetType {
 	#[cfg(feature = "mtr3")]
 	#[doc(cfg(feature = "mtr3"))]
 	Mtr3CustomTrainModelWithComments,
+	/// A language strings file, used in Minecraft version 1.13 and above, with `.json`
+	/// extension.
+	LanguageFile,
+	/// A language strings file, maybe with comments and `.jsonc` extension.
+	LanguageFileWithComments,
 	/// Any asset in JSON format, with `.json` extension. Because this is a generic asset type,
 	/// no optimizations specific to a particular JSON structure will be done.
 	GenericJson,
@@ -198,8 +209,9 @@ pub enum PackFileAssetType {
 	CommandFunction,
 
 	/// A custom asset type, defined by the end user, whose contents are opaque to PackSquash and
-	/// processed without any specific optimizations. Custom assets can never be matched by using
-	/// [`PackFileAssetTypeMatcher`].
+	/// processed without any specific optimizations, unless a custom pack file processor is
+	/// registered for them. Custom assets can only be matched by using [`PackFileAssetTypeMatcher`]
+	/// when such a processor is registered.
 	// For better style, keep this variant last (i.e. only add new ones above)
 	Custom
 }
@@ -266,6 +278,8 @@ impl PackFileAssetType {
 			}
 			#[cfg(feature = "mtr3")]
 			Self::Mtr3CustomTrainModelWithComments => "assets/mtr/**/?*.bbmodelc",
+			Self::LanguageFile => "assets/*/lang/?*.json",
+			Self::LanguageFileWithComments => "assets/*/lang/?*.jsonc",
 			Self::GenericJson => {
 				// This is really generic on purpose, as exhaustively matching all the JSON
 				// files a Minecraft resource pack can contain, even if we limit ourselves
@@ -447,6 +461,8 @@ impl PackFileAssetType {
 			Self::Mtr3CustomTrainModel => None,
 			#[cfg(feature = "mtr3")]
 			Self::Mtr3CustomTrainModelWithComments => Some("bbmodel"),
+			Self::LanguageFile => None,
+			Self::LanguageFileWithComments => Some("json"),
 			Self::GenericJson => None,
 			Self::GenericJsonWithComments => Some("json"),
 			Self::GenericOggVorbisAudio => Some("ogg"),
@@ -494,17 +510,21 @@ impl PackFileAssetType {
 pub struct PackFileAssetTypeMatcher {
 	asset_type_globset: GlobSet,
 	base_layer_only_asset_types_mask: EnumSet<PackFileAssetType>,
-	any_layer_asset_types_mask: EnumSet<PackFileAssetType>
+	any_layer_asset_types_mask: EnumSet<PackFileAssetType>,
+	custom_pack_file_processor_matcher: CustomPackFileProcessorMatcher
 }
 
 impl PackFileAssetTypeMatcher {
 	/// Returns a new matcher that can be used to determine the pack file's asset type, given its
 	/// [`RelativePath`]. A mask is used to limit what asset types can match, and an iterator over
 	/// pack layers to root the matches in the defined layers. If the mask contains the custom asset
-	/// type, [PackFileAssetType::Custom], it will be silently excluded from the mask.
+	/// type, [PackFileAssetType::Custom], it will be silently excluded from the mask: pack files
+	/// only match that asset type if they match the glob pattern of some of the specified custom
+	/// pack file processors, which take precedence over any other asset type.
 	pub fn new<'layers>(
 		asset_types_mask: EnumSet<PackFileAssetType>,
-		pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName> + Clone
+		pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName> + Clone,
+		custom_pack_file_processors: &CustomPackFileProcessors
 	) -> Self {
 		let mut globset_builder = GlobSetBuilder::new();
 		let asset_types_mask = asset_types_mask - PackFileAssetType::Custom;
@@ -545,7 +565,8 @@ impl PackFileAssetTypeMatcher {
 		Self {
 			asset_type_globset: globset_builder.build().unwrap(),
 			base_layer_only_asset_types_mask,
-			any_layer_asset_types_mask
+			any_layer_asset_types_mask,
+			custom_pack_file_processor_matcher: custom_pack_file_processors.matcher()
 		}
 	}
 
@@ -553,6 +574,15 @@ impl PackFileAssetTypeMatcher {
 	/// potentially involves regular expressions and heap allocations, so users of this method
 	/// are encouraged to not do gratuitous matches.
 	pub fn matches_for(&self, path: &RelativePath<'_>) -> PackFileAssetTypeMatches {
+		if let Some(custom_pack_file_processor) =
+			self.custom_pack_file_processor_matcher.processor_for(path)
+		{
+			return PackFileAssetTypeMatches {
+				matches: Cow::Borrowed(&[PackFileAssetType::Custom]),
+				custom_pack_file_processor: Some(custom_pack_file_processor)
+			};
+		}
+
 		PackFileAssetTypeMatches {
 			matches: Cow::Owned(
 				self.asset_type_globset
@@ -572,7 +602,8 @@ impl PackFileAssetTypeMatcher {
 						.unwrap()
 					})
 					.collect()
-			)
+			),
+			custom_pack_file_processor: None
 		}
 	}
 }
@@ -580,7 +611,8 @@ impl PackFileAssetTypeMatcher {
 /// A set of asset type matches for a pack file, given its [`RelativePath`]. This struct is
 /// constructed by the [`PackFileAssetTypeMatcher::matches_for`] method.
 pub struct PackFileAssetTypeMatches {
-	matches: Cow<'static, [PackFileAssetType]>
+	matches: Cow<'static, [PackFileAssetType]>,
+	custom_pack_file_processor: Option<Arc<dyn CustomPackFileProcessor>>
 }
 
 impl PackFileAssetTypeMatches {
@@ -588,10 +620,24 @@ impl PackFileAssetTypeMatches {
 	/// [`PackFileAssetType::Custom`] asset type.
 	pub fn of_custom_asset_type() -> Self {
 		Self {
-			matches: Cow::Borrowed(&[PackFileAssetType::Custom])
+			matches: Cow::Borrowed(&[PackFileAssetType::Custom]),
+			custom_pack_file_processor: None
 		}
 	}
 
+	/// Returns the asset types in this set, in matching order.
+	pub fn asset_types(&self) -> &[PackFileAssetType] {
+		&self.matches
+	}
+
+	/// Returns the canonical extension of the first asset type in this set, which is the
+	/// extension the pack file will most likely be stored with, if it needs to be changed.
+	pub(crate) fn canonical_extension(&self) -> Option<&'static str> {
+		self.matches
+			.first()
+			.and_then(|asset_type| asset_type.canonical_extension())
+	}
+
 	/// Checks whether there are no matches in this set, so that `process_data` would always
 	/// return `None`.
 	pub fn is_empty(&self) -> bool {
@@ -734,6 +780,12 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(JsonFile, optimization_settings)
 				}
+				PackFileAssetType::LanguageFile | PackFileAssetType::LanguageFileWithComments
+					if let Some(FileOptions::LanguageFileOptions(optimization_settings)) =
+						file_options =>
+				{
+					return_pack_file_to_process_data!(LanguageFile, optimization_settings)
+				}
 				PackFileAssetType::GenericJson
 					if let Some(FileOptions::JsonFileOptions(optimization_settings)) =
 						file_options =>
@@ -860,6 +912,17 @@ impl PackFileAssetTypeMatches {
 				{
 					return_pack_file_to_process_data!(PassthroughFile, ())
 				}
+				// Custom pack file processors handle the files they match with default options,
+				// and options meant for custom files
+				PackFileAssetType::Custom
+					if let Some(custom_pack_file_processor) = &self.custom_pack_file_processor
+						&& matches!(file_options, None | Some(FileOptions::CustomFileOptions(_))) =>
+				{
+					return_pack_file_to_process_data!(
+						CustomPackFile,
+						Arc::clone(custom_pack_file_processor)
+					)
+				}
 				PackFileAssetType::Custom
 					if let Some(FileOptions::CustomFileOptions(CustomFileOptions {
 						force_include: true,
@@ -940,6 +1003,8 @@ fn pack_file_to_process_data(
 			may_be_read_and_provided_by_mods: pack_file.may_be_read_and_provided_by_mods(),
 			is_force_included: pack_file.is_force_included()
 		},
+		asset_type,
+		processing_identity: pack_file.processing_identity(),
 		optimized_byte_chunks_stream: Box::new(pack_file.process().map(|byte_chunk_result| {
 			match byte_chunk_result {
 				Ok((optimization_strategy, optimized_bytes)) => Ok((
@@ -112,6 +112,64 @@ pub enum OptimizationError {
 	Io(#[from] std::io::Error)
 }
 
+/// The kind of an error that may happen while optimizing audio files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The audio data could not be decoded.
+	Decoding,
+	/// The audio data could not be encoded to Vorbis.
+	Encoding,
+	/// The file does not contain a decodable audio track.
+	NoAudioTrack,
+	/// The codec parameters of the audio track could not be retrieved.
+	MissingCodecParameters,
+	/// The audio track has a channel count that Minecraft does not support.
+	UnsupportedChannelCount,
+	/// The sampling frequency of the audio track is unknown.
+	UnknownSamplingFrequency,
+	/// The sampling frequency of the audio track is too high to resample from it.
+	InvalidSourceSamplingFrequency,
+	/// The sampling frequency to resample to is too high.
+	InvalidTargetSamplingFrequency,
+	/// The target bitrate specified in the options is invalid.
+	InvalidTargetBitrate,
+	/// An error happened while resampling the audio data.
+	ResamplingFailure,
+	/// An error happened while optimizing the encoded Ogg Vorbis stream.
+	TwoPassOptimization,
+	/// The audio track has more samples than Minecraft can play.
+	TooLongForMinecraft,
+	/// An I/O error happened while reading the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::Symphonia(_) => OptimizationErrorKind::Decoding,
+			Self::Vorbis(_) => OptimizationErrorKind::Encoding,
+			Self::NoAudioTrack => OptimizationErrorKind::NoAudioTrack,
+			Self::MissingCodecParameters => OptimizationErrorKind::MissingCodecParameters,
+			Self::UnsupportedChannelCount => OptimizationErrorKind::UnsupportedChannelCount,
+			Self::UnknownSamplingFrequency => OptimizationErrorKind::UnknownSamplingFrequency,
+			Self::InvalidSourceSamplingFrequency { .. } => {
+				OptimizationErrorKind::InvalidSourceSamplingFrequency
+			}
+			Self::InvalidTargetSamplingFrequency { .. } => {
+				OptimizationErrorKind::InvalidTargetSamplingFrequency
+			}
+			Self::InvalidTargetBitrate => OptimizationErrorKind::InvalidTargetBitrate,
+			Self::ResamplingFailure(_) => OptimizationErrorKind::ResamplingFailure,
+			Self::TwoPassOptimization(_) => OptimizationErrorKind::TwoPassOptimization,
+			Self::TooLongForMinecraft => OptimizationErrorKind::TooLongForMinecraft,
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 /// Helper enum to allow clients of [AudioFile] to consume bytes from different
 /// owned representations, which skips costly conversions.
 #[derive(Debug)]
@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use futures::{StreamExt, future};
 use thiserror::Error;
 use tokio::io::AsyncRead;
@@ -46,6 +48,42 @@ pub enum OptimizationError {
 	DoubleSlashComment(LineNumber)
 }
 
+/// The kind of an error that may happen while optimizing command function files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// A line could not be read, due to an I/O error or it being too long.
+	LineRead,
+	/// A command starts with a slash, which the game does not accept in functions.
+	GratuitousLeadingSlash {
+		/// The line number of the offending command, if known.
+		line: Option<NonZeroUsize>
+	},
+	/// A comment is delimited by a double slash instead of `#`.
+	DoubleSlashComment {
+		/// The line number of the offending comment, if known.
+		line: Option<NonZeroUsize>
+	}
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::TextLineRead(_) => OptimizationErrorKind::LineRead,
+			Self::GratuitousLeadingSlash(line_number) => {
+				OptimizationErrorKind::GratuitousLeadingSlash {
+					line: line_number.get()
+				}
+			}
+			Self::DoubleSlashComment(line_number) => OptimizationErrorKind::DoubleSlashComment {
+				line: line_number.get()
+			}
+		}
+	}
+}
+
 impl<T: AsyncRead + Send + Unpin + 'static> PackFile for CommandFunctionFile<T> {
 	type ByteChunkType = Vec<u8>;
 	type OptimizationError = OptimizationError;
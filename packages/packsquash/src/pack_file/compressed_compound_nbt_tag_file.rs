@@ -48,6 +48,27 @@ pub enum OptimizationError {
 	Io(#[from] io::Error)
 }
 
+/// The kind of an error that may happen while optimizing compressed compound NBT tag files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file does not contain a valid NBT compound tag.
+	InvalidNbt,
+	/// An I/O error happened while reading or decompressing the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::Nbt(_) => OptimizationErrorKind::InvalidNbt,
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 // FIXME: actual framing?
 // (i.e. do not hold the entire file in memory before decoding, so that frame != file)
 impl Decoder for OptimizerDecoder {
@@ -0,0 +1,165 @@
+//! Contains code to optimize files with processors provided by library users, which allow
+//! handling file formats that PackSquash does not know about.
+
+use std::{borrow::Cow, error::Error, pin::Pin, sync::Arc};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tokio::io::AsyncRead;
+use tokio_stream::Stream;
+
+use super::{AsyncReadAndSizeHint, PackFile, PackFileAssetType, PackFileConstructor};
+use crate::RelativePath;
+
+#[cfg(test)]
+mod tests;
+
+/// An error that a [`CustomPackFileProcessor`] may yield while optimizing a pack file.
+pub type CustomOptimizationError = Box<dyn Error + Send + Sync>;
+
+/// The stream of optimized byte chunks returned by a [`CustomPackFileProcessor`]. Each chunk has
+/// a string that briefly describes the optimization strategy that was applied to it, which is
+/// shown in the pack file status updates.
+pub type CustomOptimizedByteChunksStream =
+	Pin<Box<dyn Stream<Item = Result<(Cow<'static, str>, Vec<u8>), CustomOptimizationError>> + Send>>;
+
+/// A processor for pack files in some format unknown to PackSquash, such as proprietary files
+/// read by server plugins, that library users can register with
+/// [`PackSquasher::with_custom_pack_file_processor`](crate::PackSquasher::with_custom_pack_file_processor).
+///
+/// The pack files handled by a custom processor get the same treatment as the pack files of
+/// built-in asset types: they are read and optimized concurrently in a streaming fashion,
+/// compressed in the output ZIP file as configured, and their processing is reported in the
+/// pack file status updates as a file of the `custom` asset type.
+pub trait CustomPackFileProcessor: Send + Sync + 'static {
+	/// Processes the pack file read from `read`, whose size is estimated to be `file_size_hint`
+	/// bytes, returning a stream of chunks of optimized bytes. If the stream returns no items, the
+	/// file is treated as if its optimized representation contains no bytes.
+	///
+	/// # Errors
+	/// The returned stream may yield an error if some I/O operation goes wrong, the pack file is
+	/// malformed, or any other unrecoverable condition is encountered. In this case, the stream is
+	/// not polled again, and the processing of the pack file is considered to have failed.
+	fn process(
+		&self,
+		read: Box<dyn AsyncRead + Send + Unpin>,
+		file_size_hint: u64
+	) -> CustomOptimizedByteChunksStream;
+
+	/// Returns whether the optimized pack files returned by this processor are already internally
+	/// compressed, and as such any attempt to further compress them will likely result in lower
+	/// than usual space savings.
+	fn is_compressed(&self) -> bool {
+		false
+	}
+
+	/// Returns whether the pack files handled by this processor may be read and provided by
+	/// potentially unknown client mods in practical cases.
+	fn may_be_read_and_provided_by_mods(&self) -> bool {
+		false
+	}
+
+	/// Returns a non-empty string that uniquely identifies this processor and the version of its
+	/// optimization logic, which must change whenever the processor may optimize the same pack
+	/// file differently. When `Some`, the results of this processor may be reused from the
+	/// optimization cache and previous ZIP files whose source digests match.
+	///
+	/// By default, `None` is returned, so the results of this processor are never reused by
+	/// those means.
+	fn identity(&self) -> Option<Cow<'static, str>> {
+		None
+	}
+}
+
+/// A list of custom pack file processors, each associated with a glob pattern that matches the
+/// relative paths of the pack files it should process.
+#[derive(Clone, Default)]
+pub struct CustomPackFileProcessors {
+	globs: Vec<Glob>,
+	processors: Vec<Arc<dyn CustomPackFileProcessor>>
+}
+
+impl CustomPackFileProcessors {
+	/// Adds a custom pack file processor to this list, which has lower priority than the
+	/// processors added before.
+	pub fn push(&mut self, glob: Glob, processor: Arc<dyn CustomPackFileProcessor>) {
+		self.globs.push(glob);
+		self.processors.push(processor);
+	}
+
+	/// Returns a matcher for the processors in this list.
+	pub fn matcher(&self) -> CustomPackFileProcessorMatcher {
+		let mut globset_builder = GlobSetBuilder::new();
+		for glob in &self.globs {
+			globset_builder.add(glob.clone());
+		}
+
+		CustomPackFileProcessorMatcher {
+			processor_globset: globset_builder.build().unwrap(),
+			processors: self.processors.clone()
+		}
+	}
+}
+
+/// A matcher that finds the custom pack file processor that should process a pack file, given
+/// its [`RelativePath`].
+pub struct CustomPackFileProcessorMatcher {
+	processor_globset: GlobSet,
+	processors: Vec<Arc<dyn CustomPackFileProcessor>>
+}
+
+impl CustomPackFileProcessorMatcher {
+	/// Returns the highest priority custom pack file processor whose glob pattern matches the
+	/// specified path, if any.
+	pub fn processor_for(&self, path: &RelativePath<'_>) -> Option<Arc<dyn CustomPackFileProcessor>> {
+		self.processor_globset
+			.matches(path) // Returns indices in ascending order, i.e., by priority
+			.first()
+			.map(|&processor_index| Arc::clone(&self.processors[processor_index]))
+	}
+}
+
+/// Represents a pack file that is optimized by a [`CustomPackFileProcessor`].
+pub struct CustomPackFile<T: AsyncRead + Send + Unpin + 'static> {
+	read: T,
+	file_size_hint: u64,
+	processor: Arc<dyn CustomPackFileProcessor>
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFile for CustomPackFile<T> {
+	type ByteChunkType = Vec<u8>;
+	type OptimizationError = CustomOptimizationError;
+	type OptimizedByteChunksStream = CustomOptimizedByteChunksStream;
+
+	fn process(self) -> CustomOptimizedByteChunksStream {
+		self.processor
+			.process(Box::new(self.read), self.file_size_hint)
+	}
+
+	fn is_compressed(&self) -> bool {
+		self.processor.is_compressed()
+	}
+
+	fn may_be_read_and_provided_by_mods(&self) -> bool {
+		self.processor.may_be_read_and_provided_by_mods()
+	}
+
+	fn processing_identity(&self) -> Option<Cow<'static, str>> {
+		self.processor.identity()
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for CustomPackFile<T> {
+	type OptimizationSettings = Arc<dyn CustomPackFileProcessor>;
+
+	fn new(
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
+		_: PackFileAssetType,
+		processor: Self::OptimizationSettings
+	) -> Option<Self> {
+		file_read_producer().map(|(read, file_size_hint)| Self {
+			read,
+			file_size_hint,
+			processor
+		})
+	}
+}
@@ -0,0 +1,148 @@
+use pretty_assertions::assert_eq;
+use tokio::io::AsyncReadExt;
+use tokio_stream::StreamExt;
+use tokio_test::io::Builder;
+
+use super::*;
+use crate::config::compile_pack_file_glob_pattern;
+
+/// A custom pack file processor that converts its input to ASCII uppercase.
+struct UppercaseProcessor;
+
+impl CustomPackFileProcessor for UppercaseProcessor {
+	fn process(
+		&self,
+		mut read: Box<dyn AsyncRead + Send + Unpin>,
+		_: u64
+	) -> CustomOptimizedByteChunksStream {
+		Box::pin(futures::stream::once(async move {
+			let mut data = Vec::new();
+			read.read_to_end(&mut data).await?;
+			data.make_ascii_uppercase();
+
+			Ok::<_, CustomOptimizationError>((Cow::Borrowed("Uppercased"), data))
+		}))
+	}
+}
+
+/// A custom pack file processor that always fails.
+struct FailingProcessor;
+
+impl CustomPackFileProcessor for FailingProcessor {
+	fn process(
+		&self,
+		_: Box<dyn AsyncRead + Send + Unpin>,
+		_: u64
+	) -> CustomOptimizedByteChunksStream {
+		Box::pin(futures::stream::once(async {
+			Err::<(Cow<'static, str>, Vec<u8>), _>(CustomOptimizationError::from("Unsupported file"))
+		}))
+	}
+}
+
+#[tokio::test]
+async fn processing_uses_the_custom_processor() {
+	let pack_file = CustomPackFile::new(
+		|| Some((Builder::new().read(b"hello").build(), 5)),
+		PackFileAssetType::Custom,
+		Arc::new(UppercaseProcessor) as Arc<dyn CustomPackFileProcessor>
+	)
+	.expect("The pack file should be instantiated");
+
+	let process_result: Vec<(Cow<'static, str>, Vec<u8>)> = pack_file
+		.process()
+		.map(|result| result.expect("No error should happen while processing"))
+		.collect()
+		.await;
+
+	assert_eq!(
+		process_result,
+		[(Cow::Borrowed("Uppercased"), b"HELLO".to_vec())]
+	);
+}
+
+#[test]
+fn processing_identity_comes_from_the_processor() {
+	/// A custom pack file processor with an identity.
+	struct IdentifiedProcessor;
+
+	impl CustomPackFileProcessor for IdentifiedProcessor {
+		fn process(
+			&self,
+			read: Box<dyn AsyncRead + Send + Unpin>,
+			file_size_hint: u64
+		) -> CustomOptimizedByteChunksStream {
+			UppercaseProcessor.process(read, file_size_hint)
+		}
+
+		fn identity(&self) -> Option<Cow<'static, str>> {
+			Some(Cow::Borrowed("uppercase/1"))
+		}
+	}
+
+	let pack_file = |processor: Arc<dyn CustomPackFileProcessor>| {
+		CustomPackFile::new(
+			|| Some((Builder::new().build(), 0)),
+			PackFileAssetType::Custom,
+			processor
+		)
+		.expect("The pack file should be instantiated")
+	};
+
+	assert_eq!(
+		pack_file(Arc::new(UppercaseProcessor)).processing_identity(),
+		None
+	);
+	assert_eq!(
+		pack_file(Arc::new(IdentifiedProcessor)).processing_identity(),
+		Some(Cow::Borrowed("uppercase/1"))
+	);
+}
+
+#[tokio::test]
+async fn processing_errors_are_yielded() {
+	let pack_file = CustomPackFile::new(
+		|| Some((Builder::new().build(), 0)),
+		PackFileAssetType::Custom,
+		Arc::new(FailingProcessor) as Arc<dyn CustomPackFileProcessor>
+	)
+	.expect("The pack file should be instantiated");
+
+	let process_result = pack_file.process().collect::<Vec<_>>().await;
+
+	assert_eq!(process_result.len(), 1);
+	assert_eq!(
+		process_result[0].as_ref().unwrap_err().to_string(),
+		"Unsupported file"
+	);
+}
+
+#[test]
+fn first_matching_processor_has_priority() {
+	let mut processors = CustomPackFileProcessors::default();
+	processors.push(
+		compile_pack_file_glob_pattern("plugin/special.dat").unwrap(),
+		Arc::new(FailingProcessor)
+	);
+	processors.push(
+		compile_pack_file_glob_pattern("plugin/*.dat").unwrap(),
+		Arc::new(UppercaseProcessor)
+	);
+	let matcher = processors.matcher();
+
+	let processor_for = |path: &'static str| {
+		matcher
+			.processor_for(&RelativePath::from_inner(path))
+			.map(|processor| Arc::as_ptr(&processor) as *const ())
+	};
+
+	assert_eq!(
+		processor_for("plugin/special.dat"),
+		Some(Arc::as_ptr(&processors.processors[0]) as *const ())
+	);
+	assert_eq!(
+		processor_for("plugin/other.dat"),
+		Some(Arc::as_ptr(&processors.processors[1]) as *const ())
+	);
+	assert_eq!(processor_for("assets/minecraft/other.dat"), None);
+}
@@ -1,6 +1,6 @@
 //! Contains code to optimize JSON files.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, num::NonZeroUsize};
 
 use bytes::{BufMut, BytesMut};
 use json_comments::StripComments;
@@ -55,6 +55,40 @@ pub enum OptimizationError {
 	Io(#[from] std::io::Error)
 }
 
+/// The kind of an error that may happen while optimizing JSON files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file is not valid JSON, or it ends unexpectedly.
+	Syntax {
+		/// The line number where the error was detected, if known.
+		line: Option<NonZeroUsize>,
+		/// The column number where the error was detected, if known.
+		column: Option<NonZeroUsize>
+	},
+	/// The file is valid JSON, but it contains some value that is not appropriate for its
+	/// asset type.
+	UnexpectedValue,
+	/// An I/O error happened while reading or writing the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::JsonSerde(err) if err.is_io() => OptimizationErrorKind::Io,
+			Self::JsonSerde(err) => OptimizationErrorKind::Syntax {
+				line: NonZeroUsize::new(err.line()),
+				column: NonZeroUsize::new(err.column())
+			},
+			Self::UnexpectedValue(_) => OptimizationErrorKind::UnexpectedValue,
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 thread_local!(static DEBLOATER: Debloater = const { Debloater::new() });
 
 // FIXME: actual framing?
@@ -0,0 +1,254 @@
+//! Contains code to optimize JSON language files.
+
+use std::{borrow::Cow, fmt, num::NonZeroUsize, sync::LazyLock};
+
+use bytes::{BufMut, BytesMut};
+use json_comments::StripComments;
+use patricia_tree::PatriciaSet;
+use regex::Regex;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{Decoder, FramedRead};
+
+use crate::config::LanguageFileOptions;
+use crate::pack_file::AsyncReadAndSizeHint;
+use crate::pack_file::asset_type::PackFileAssetType;
+
+use super::{PackFile, PackFileConstructor, util::strip_utf8_bom};
+
+#[cfg(test)]
+mod tests;
+
+/// A regular expression that matches the format specifiers Minecraft accepts in language
+/// strings: `%s`, `%n$s` with a positive argument index `n`, and `%%`, which stands for a
+/// literal percent sign.
+static FORMAT_SPECIFIER_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new("%(?:[1-9]\\d*\\$)?s|%%").unwrap());
+
+/// Represents a JSON file that contains a single object with pairs of string keys and their
+/// value for a given locale, which Minecraft uses as format strings for translated text.
+///
+/// These files superseded legacy language files in Minecraft 1.13. Unlike the generic JSON
+/// optimization process, the keys and values of these files are validated as Minecraft would
+/// interpret them.
+///
+/// References:
+/// - <https://minecraft.wiki/w/Resource_pack#Language>
+/// - Minecraft classes `net.minecraft.locale.Language` and
+///   `net.minecraft.network.chat.contents.TranslatableContents` (Mojang mappings names)
+pub struct LanguageFile<T: AsyncRead + Send + Unpin + 'static> {
+	read: T,
+	file_length_hint: usize,
+	optimization_settings: LanguageFileOptions
+}
+
+/// Optimizer decoder that transforms JSON language files to an optimized representation.
+pub struct OptimizerDecoder {
+	optimization_settings: LanguageFileOptions,
+	reached_eof: bool
+}
+
+/// Represents an error that may happen while optimizing JSON language files.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum OptimizationError {
+	#[error("JSON error: {0}")]
+	JsonSerde(#[from] serde_json::Error),
+	#[error(
+		"Unexpected JSON value for key \"{0}\": language strings must be strings, numbers or booleans"
+	)]
+	UnexpectedValue(String),
+	#[error("Format error: Duplicate key \"{0}\"")]
+	DuplicateKey(String),
+	#[error("Format error: Invalid format string in value of key \"{0}\"")]
+	InvalidFormatString(String),
+	#[error("I/O error: {0}")]
+	Io(#[from] std::io::Error)
+}
+
+/// The kind of an error that may happen while optimizing JSON language files, which allows
+/// telling errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file is not valid JSON, its root value is not an object, or it ends unexpectedly.
+	Syntax {
+		/// The line number where the error was detected, if known.
+		line: Option<NonZeroUsize>,
+		/// The column number where the error was detected, if known.
+		column: Option<NonZeroUsize>
+	},
+	/// The value of a key is a JSON array, object or null, which Minecraft does not accept as a
+	/// language string.
+	UnexpectedValue,
+	/// A key was defined more than once.
+	DuplicateKey,
+	/// A value contains a format string that the game would not accept.
+	InvalidFormatString,
+	/// An I/O error happened while reading or writing the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::JsonSerde(err) if err.is_io() => OptimizationErrorKind::Io,
+			Self::JsonSerde(err) => OptimizationErrorKind::Syntax {
+				line: NonZeroUsize::new(err.line()),
+				column: NonZeroUsize::new(err.column())
+			},
+			Self::UnexpectedValue(_) => OptimizationErrorKind::UnexpectedValue,
+			Self::DuplicateKey(_) => OptimizationErrorKind::DuplicateKey,
+			Self::InvalidFormatString(_) => OptimizationErrorKind::InvalidFormatString,
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
+/// The key-value pairs of a language file, in file order. Unlike a [`Map`], duplicate keys are
+/// kept, so they can be told apart.
+struct LanguageStrings(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for LanguageStrings {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct LanguageStringsVisitor;
+
+		impl<'de> Visitor<'de> for LanguageStringsVisitor {
+			type Value = LanguageStrings;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a JSON object")
+			}
+
+			fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+				let mut strings = Vec::with_capacity(map.size_hint().unwrap_or(0));
+				while let Some(key_value_pair) = map.next_entry()? {
+					strings.push(key_value_pair);
+				}
+
+				Ok(LanguageStrings(strings))
+			}
+		}
+
+		deserializer.deserialize_map(LanguageStringsVisitor)
+	}
+}
+
+// FIXME: actual framing?
+// (i.e. do not hold the entire file in memory before decoding, so that frame != file)
+impl Decoder for OptimizerDecoder {
+	type Item = (Cow<'static, str>, BytesMut);
+	type Error = OptimizationError;
+
+	fn decode(&mut self, _: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		Ok(None)
+	}
+
+	fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		// This method will be called when EOF is reached until it returns None. Because we
+		// will only ever output a single item in the stream, always return None if we have
+		// executed once already
+		if self.reached_eof {
+			return Ok(None);
+		}
+		self.reached_eof = true;
+
+		// Minecraft reads language files with a lenient JSON parser that accepts comments, so
+		// always accept them too
+		let LanguageStrings(strings) =
+			serde_json::from_reader(StripComments::new(strip_utf8_bom(src)))?;
+
+		let mut processed_keys = PatriciaSet::new();
+		for (key, value) in &strings {
+			// Minecraft keeps the last value of a key when it is defined several times, but
+			// serde_json would silently do the same. To guarantee that our output is minimal
+			// and that pack authors are not surprised when a value overrides another,
+			// consider duplicate keys as an error, like for legacy language files
+			if !processed_keys.insert(key) {
+				return Err(OptimizationError::DuplicateKey(key.clone()));
+			}
+
+			// Minecraft converts any JSON primitive to a string, but rejects other values
+			let Value::String(value) = value else {
+				if value.is_number() || value.is_boolean() {
+					continue;
+				}
+
+				return Err(OptimizationError::UnexpectedValue(key.clone()));
+			};
+
+			// Minecraft will use the value as a format string. If we find a format specifier
+			// delimiter character that was not matched by the regex, then some format specifier
+			// is not valid, and the game would show the value verbatim instead of formatting it
+			if FORMAT_SPECIFIER_REGEX
+				.split(value)
+				.any(|literal_text| literal_text.contains('%'))
+			{
+				return Err(OptimizationError::InvalidFormatString(key.clone()));
+			}
+		}
+
+		let strings = Map::from_iter(strings);
+
+		// Now that we have the language strings, clear the input buffer to reuse it for the
+		// optimized JSON serialization
+		src.clear();
+		let mut json_writer = src.split_off(0).writer();
+
+		let description = if self.optimization_settings.minify {
+			serde_json::to_writer(&mut json_writer, &strings)?;
+			"Validated and minified"
+		} else {
+			serde_json::to_writer_pretty(&mut json_writer, &strings)?;
+			"Validated and prettified"
+		};
+
+		// Cheaply get an owned BytesMut with the serialized JSON data
+		Ok(Some((
+			Cow::Borrowed(description),
+			json_writer.get_mut().split_off(0)
+		)))
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFile for LanguageFile<T> {
+	type ByteChunkType = BytesMut;
+	type OptimizationError = OptimizationError;
+	type OptimizedByteChunksStream = FramedRead<T, OptimizerDecoder>;
+
+	fn process(self) -> FramedRead<T, OptimizerDecoder> {
+		FramedRead::with_capacity(
+			self.read,
+			OptimizerDecoder {
+				optimization_settings: self.optimization_settings,
+				reached_eof: false
+			},
+			// FIXME consider refactoring this when we have a global memory budget
+			self.file_length_hint
+		)
+	}
+
+	fn is_compressed(&self) -> bool {
+		false
+	}
+}
+
+impl<T: AsyncRead + Send + Unpin + 'static> PackFileConstructor<T> for LanguageFile<T> {
+	type OptimizationSettings = LanguageFileOptions;
+
+	fn new(
+		file_read_producer: impl FnOnce() -> Option<AsyncReadAndSizeHint<T>>,
+		_: PackFileAssetType,
+		optimization_settings: Self::OptimizationSettings
+	) -> Option<Self> {
+		file_read_producer().map(|(read, file_length_hint)| Self {
+			read,
+			// The file is too big to fit in memory if this conversion fails anyway
+			file_length_hint: file_length_hint.try_into().unwrap_or(usize::MAX),
+			optimization_settings
+		})
+	}
+}
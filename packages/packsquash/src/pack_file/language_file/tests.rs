@@ -0,0 +1,152 @@
+use crate::pack_file::util::BOM;
+use pretty_assertions::assert_eq;
+use tokio_stream::StreamExt;
+use tokio_test::io::Builder;
+
+use super::*;
+
+static LANGUAGE_DATA: &str = include_str!("example.json");
+static MINIFIED_LANGUAGE_DATA: &str = include_str!("example_minified.json");
+static LANGUAGE_DATA_WITH_DUPLICATE_KEY: &str = include_str!("duplicate_key.json");
+static LANGUAGE_DATA_WITH_BAD_FORMAT_STRING: &str = include_str!("bad_format_string.json");
+static LANGUAGE_DATA_WITH_UNEXPECTED_VALUE: &str = include_str!("unexpected_value.json");
+
+/// Processes the given input data as a [LanguageFile], using the provided settings,
+/// expecting a successful result that equals the expected string.
+async fn successful_process_test(
+	input_data: &str,
+	settings: LanguageFileOptions,
+	expected_result: &str
+) {
+	let input_data = input_data.as_bytes();
+
+	let data_stream = LanguageFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		optimization_settings: settings
+	}
+	.process();
+
+	let process_result: Vec<(Cow<'static, str>, BytesMut)> = data_stream
+		.map(|result| result.expect("No error should happen while decoding"))
+		.collect()
+		.await;
+
+	assert!(
+		!process_result.is_empty(),
+		"Some data was expected for this input"
+	);
+
+	let mut data = Vec::with_capacity(input_data.len());
+	for (_, partial_data) in process_result {
+		data.extend_from_slice(&partial_data);
+	}
+
+	let data = String::from_utf8(data).expect("The result should be a UTF-8 string");
+	assert_eq!(&data, expected_result);
+}
+
+/// Processes the given input data as a [LanguageFile], using the default settings,
+/// expecting an error that satisfies the specified matcher.
+async fn unsuccessful_process_test(
+	input_data: &str,
+	error_matcher: impl FnOnce(&OptimizationError) -> bool,
+	failed_assertion_message: &'static str
+) {
+	let input_data = input_data.as_bytes();
+
+	let mut data_stream = LanguageFile {
+		read: Builder::new().read(input_data).build(),
+		file_length_hint: input_data.len(),
+		optimization_settings: Default::default()
+	}
+	.process();
+
+	let error = data_stream
+		.next()
+		.await
+		.expect("Expected some result for this input")
+		.expect_err("Expected an error for this input");
+
+	assert!(
+		error_matcher(&error),
+		"{failed_assertion_message}, got {error:?}"
+	);
+}
+
+#[tokio::test]
+async fn minifying_works() {
+	successful_process_test(
+		LANGUAGE_DATA,
+		LanguageFileOptions { minify: true },
+		MINIFIED_LANGUAGE_DATA
+	)
+	.await
+}
+
+#[tokio::test]
+async fn minifying_with_bom_works() {
+	let mut language_data_with_bom = String::from(LANGUAGE_DATA);
+	language_data_with_bom.insert(0, BOM);
+
+	successful_process_test(
+		&language_data_with_bom,
+		LanguageFileOptions { minify: true },
+		MINIFIED_LANGUAGE_DATA
+	)
+	.await
+}
+
+#[tokio::test]
+async fn prettifying_works() {
+	successful_process_test(
+		MINIFIED_LANGUAGE_DATA,
+		LanguageFileOptions { minify: false },
+		&serde_json::to_string_pretty(
+			&serde_json::from_str::<Value>(MINIFIED_LANGUAGE_DATA)
+				.expect("The test data should be valid JSON")
+		)
+		.unwrap()
+	)
+	.await
+}
+
+#[tokio::test]
+async fn duplicate_key_is_handled() {
+	unsuccessful_process_test(
+		LANGUAGE_DATA_WITH_DUPLICATE_KEY,
+		|err| matches!(err, OptimizationError::DuplicateKey(key) if key == "block.my_pack.crystal"),
+		"Expected a duplicate key error"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn invalid_format_string_is_handled() {
+	unsuccessful_process_test(
+		LANGUAGE_DATA_WITH_BAD_FORMAT_STRING,
+		|err| err.kind() == OptimizationErrorKind::InvalidFormatString,
+		"Expected an invalid format string error"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn unexpected_value_is_handled() {
+	unsuccessful_process_test(
+		LANGUAGE_DATA_WITH_UNEXPECTED_VALUE,
+		|err| err.kind() == OptimizationErrorKind::UnexpectedValue,
+		"Expected an unexpected value error"
+	)
+	.await
+}
+
+#[tokio::test]
+async fn non_object_root_value_is_handled() {
+	unsuccessful_process_test(
+		"[\"Crystal\"]",
+		|err| matches!(err.kind(), OptimizationErrorKind::Syntax { .. }),
+		"Expected a syntax error"
+	)
+	.await
+}
@@ -1,4 +1,4 @@
-use std::sync::LazyLock;
+use std::{num::NonZeroUsize, sync::LazyLock};
 
 use futures::{StreamExt, future};
 use patricia_tree::PatriciaSet;
@@ -74,6 +74,48 @@ pub enum OptimizationError {
 	TextLineRead(#[from] LinesCodecError)
 }
 
+/// The kind of an error that may happen while optimizing legacy language files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// A line has no `=` separator between its key and value.
+	MissingSeparator {
+		/// The line number of the offending line, if known.
+		line: Option<NonZeroUsize>
+	},
+	/// A key was defined more than once.
+	DuplicateKey {
+		/// The line number of the repeated definition, if known.
+		line: Option<NonZeroUsize>
+	},
+	/// A value contains a format string that the game would not accept.
+	InvalidFormatString {
+		/// The line number of the offending line, if known.
+		line: Option<NonZeroUsize>
+	},
+	/// A line could not be read, due to an I/O error or it being too long.
+	LineRead
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::MissingSeparator(line_number) => OptimizationErrorKind::MissingSeparator {
+				line: line_number.get()
+			},
+			Self::DuplicateKey(_, line_number) => OptimizationErrorKind::DuplicateKey {
+				line: line_number.get()
+			},
+			Self::InvalidFormatString(line_number) => OptimizationErrorKind::InvalidFormatString {
+				line: line_number.get()
+			},
+			Self::TextLineRead(_) => OptimizationErrorKind::LineRead
+		}
+	}
+}
+
 impl<T: AsyncRead + Send + Unpin + 'static> PackFile for LegacyLanguageFile<T> {
 	type ByteChunkType = Vec<u8>;
 	type OptimizationError = OptimizationError;
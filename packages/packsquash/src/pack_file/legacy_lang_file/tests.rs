@@ -199,3 +199,20 @@ async fn invalid_format_string_is_handled() {
 	)
 	.await
 }
+
+#[tokio::test]
+async fn error_kind_has_line_number() {
+	unsuccessful_process_test(
+		LANG_DATA_WITH_MISSING_SEPARATOR,
+		false,
+		Default::default(),
+		|err| {
+			err.kind()
+				== OptimizationErrorKind::MissingSeparator {
+					line: NonZeroUsize::new(1)
+				}
+		},
+		"Expected a missing separator error kind at line 1"
+	)
+	.await
+}
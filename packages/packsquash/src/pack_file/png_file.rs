@@ -45,6 +45,44 @@ pub enum OptimizationError {
 	Io(#[from] std::io::Error)
 }
 
+/// The kind of an error that may happen while optimizing PNG files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file is not a valid PNG image, or its dimensions are too big.
+	InvalidPng,
+	/// The PNG image could not be decoded.
+	Decoding,
+	/// An error happened while losslessly optimizing the PNG image.
+	LosslessOptimization,
+	/// An error happened while quantizing the colors of the PNG image.
+	ColorQuantization,
+	/// An I/O error happened while reading the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::OptimizationError(ImageProcessingError::StripValidateError(_)) => {
+				OptimizationErrorKind::InvalidPng
+			}
+			Self::OptimizationError(ImageProcessingError::PngDecoding(_)) => {
+				OptimizationErrorKind::Decoding
+			}
+			Self::OptimizationError(ImageProcessingError::OxipngError(_)) => {
+				OptimizationErrorKind::LosslessOptimization
+			}
+			Self::OptimizationError(ImageProcessingError::LiqError(_)) => {
+				OptimizationErrorKind::ColorQuantization
+			}
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 // FIXME: actual framing?
 // (i.e. do not hold the entire file in memory before decoding, so that frame != file)
 impl Decoder for OptimizerDecoder {
@@ -1,6 +1,6 @@
 //! Contains code to optimize properties files.
 
-use std::{borrow::Cow, io};
+use std::{borrow::Cow, io, num::NonZeroUsize};
 
 use bytes::BytesMut;
 use java_properties::{LineEnding, PropertiesError, PropertiesIter, PropertiesWriter};
@@ -48,6 +48,32 @@ pub enum OptimizationError {
 	Io(#[from] io::Error)
 }
 
+/// The kind of an error that may happen while optimizing properties files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file is not a valid properties file.
+	InvalidProperties {
+		/// The line number where the error was detected, if known.
+		line: Option<NonZeroUsize>
+	},
+	/// An I/O error happened while reading the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::InvalidProperties(err) => OptimizationErrorKind::InvalidProperties {
+				line: err.line_number().and_then(NonZeroUsize::new)
+			},
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 /// Helper enum to allow clients of [PropertiesFile] consume bytes from different
 /// owned representations, which skips costly conversions.
 #[derive(Debug)]
@@ -54,6 +54,38 @@ pub enum OptimizationError {
 	Io(#[from] io::Error)
 }
 
+/// The kind of an error that may happen while optimizing GLSL shader files, which allows telling
+/// errors apart without relying on their user-friendly messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OptimizationErrorKind {
+	/// The file is not valid UTF-8 text.
+	InvalidEncoding,
+	/// The shader has a syntax error.
+	Syntax,
+	/// The shader does not define a main function, which is required for shaders that are
+	/// not included by other shaders.
+	MissingMainFunction,
+	/// An I/O error happened while reading the file.
+	Io
+}
+
+impl OptimizationError {
+	/// Returns the kind of this error.
+	pub fn kind(&self) -> OptimizationErrorKind {
+		match self {
+			Self::InvalidEncoding(_) | Self::InvalidShader(ParseError::InvalidEncoding(_)) => {
+				OptimizationErrorKind::InvalidEncoding
+			}
+			Self::InvalidShader(ParseError::Syntax { .. }) => OptimizationErrorKind::Syntax,
+			Self::InvalidShader(ParseError::MissingMainFunction { .. }) => {
+				OptimizationErrorKind::MissingMainFunction
+			}
+			Self::Io(_) => OptimizationErrorKind::Io
+		}
+	}
+}
+
 impl Decoder for OptimizerDecoder {
 	type Item = (Cow<'static, str>, BytesMut);
 	type Error = OptimizationError;
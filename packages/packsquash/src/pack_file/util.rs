@@ -61,6 +61,11 @@ impl LineNumber {
 		}
 	}
 
+	/// Returns the line number this counter points to, or `None` if it overflowed.
+	pub const fn get(&self) -> Option<NonZeroUsize> {
+		self.0
+	}
+
 	/// Increments the line number counter to point to the next line. This method
 	/// is overflow-safe.
 	pub fn increment(&mut self) {
@@ -377,6 +377,33 @@ impl Deref for SupportedLegacyPackFormatVersionRange {
 /// Such directory names can only contain the following characters: `a-zA-Z0-9_-.`
 pub type PackLayerDirectoryName = ArcStr;
 
+/// The relative path prefixes of the overlay layers of a pack, which allow getting the paths of
+/// its files relative to the root directory of their layer.
+pub(crate) struct PackLayerPathPrefixes(Vec<String>);
+
+impl PackLayerPathPrefixes {
+	/// Gets the path prefixes of the specified pack layers. The base pack has no path prefix.
+	pub(crate) fn new<'layers>(
+		pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName>
+	) -> Self {
+		Self(
+			pack_layer_directory_names
+				.filter(|layer_directory_name| !layer_directory_name.is_empty())
+				.map(|layer_directory_name| format!("{layer_directory_name}/"))
+				.collect()
+		)
+	}
+
+	/// Returns the specified pack file relative path, relative to the root directory of the pack
+	/// layer that contains it.
+	pub(crate) fn layer_relative_path<'path>(&self, relative_path: &'path str) -> &'path str {
+		self.0
+			.iter()
+			.find_map(|layer_path_prefix| relative_path.strip_prefix(layer_path_prefix.as_str()))
+			.unwrap_or(relative_path)
+	}
+}
+
 /// Defines the serialized format of overlays contained in a pack.
 ///
 /// Overlays are logical packs within a pack that are conditionally applied on top of the base pack
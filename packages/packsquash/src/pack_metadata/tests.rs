@@ -1,84 +1,35 @@
-use std::{ffi::OsStr, fs, io, iter::Empty, path::Path};
+use std::path::Path;
 
 use itertools::Itertools;
 use pretty_assertions::assert_eq;
-use tempfile::{Builder as TempDirBuilder, TempDir};
-use tokio_test::io::{Builder as MockFileBuilder, Mock};
-
-use crate::vfs::{
-	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VfsPackFileMetadata, VirtualFileSystem
-};
-
-use super::{PackMetadata, PackMetadataError, PackType};
-
-/// A mock virtual file system that reads a fixed pack metadata JSON payload from `pack.mcmeta`, and
-/// whose root directory contains marker directories for the given [`PackType`]s, so that
-/// [`PackMetadata::read`]'s pack type detection logic can be exercised realistically.
-///
-/// Marker directories are backed by a real temporary directory, because
-/// [`VirtualFileSystem::file_type`] must return a [`std::fs::FileType`] that cannot be instantiated
-/// otherwise.
-struct MockVfs {
-	metadata_json: &'static str,
-	root_dir: TempDir
-}
-
-impl MockVfs {
-	fn new(metadata_json: &'static str, pack_types: impl IntoIterator<Item = PackType>) -> Self {
-		let root_dir = TempDirBuilder::new()
-			.prefix("ps-pack-metadata-test")
-			.tempdir()
-			.expect("I/O operations are assumed not to fail during tests");
-
-		for pack_type in pack_types {
-			fs::create_dir(root_dir.path().join(pack_type.prefix_directory()))
-				.expect("I/O operations are assumed not to fail during tests");
-		}
-
-		Self {
-			metadata_json,
-			root_dir
-		}
-	}
-}
 
-impl VirtualFileSystem for MockVfs {
-	type FileRead = Mock;
-
-	type FileIter = Empty<Result<VfsPackFileIterEntry, io::Error>>;
-
-	fn file_iterator(&self, _: &Path, _: IteratorTraversalOptions) -> Self::FileIter {
-		unimplemented!()
-	}
-
-	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
-		let path = path.as_ref().as_os_str();
-		if path == OsStr::new("pack.mcmeta") || path == OsStr::new("pack.mcmetac") {
-			Ok(VfsFile {
-				file_read: MockFileBuilder::new()
-					.read(self.metadata_json.as_bytes())
-					.build(),
-				file_size_hint: self.metadata_json.len().try_into().unwrap_or(u64::MAX),
-				metadata: VfsPackFileMetadata {
-					modification_time: None
-				}
-			})
-		} else {
-			unreachable!()
-		}
-	}
+use crate::vfs::memory_fs::MemoryFilesystem;
 
-	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<fs::FileType, io::Error> {
-		fs::metadata(self.root_dir.path().join(path.as_ref())).map(|metadata| metadata.file_type())
-	}
-}
+use super::{PackLayerPathPrefixes, PackMetadata, PackMetadataError, PackType};
 
-/// Convenience façade for [`PackMetadata::read`] over a [`MockVfs`].
+/// Convenience façade for [`PackMetadata::read`] over an in-memory virtual file system that
+/// contains a `pack.mcmeta` file with the given JSON payload, and whose root directory contains
+/// marker directories for the given [`PackType`]s, so that [`PackMetadata::read`]'s pack type
+/// detection logic can be exercised realistically.
 async fn read_metadata(
 	metadata_json: &'static str,
 	pack_types: impl IntoIterator<Item = PackType>
 ) -> Result<PackMetadata, PackMetadataError> {
-	PackMetadata::read(&MockVfs::new(metadata_json, pack_types), "").await
+	let mut vfs = MemoryFilesystem::new();
+
+	vfs.add_file("pack.mcmeta", metadata_json, None)
+		.expect("I/O operations are assumed not to fail during tests");
+
+	for pack_type in pack_types {
+		vfs.add_file(
+			Path::new(pack_type.prefix_directory()).join("file.bin"),
+			"",
+			None
+		)
+		.expect("I/O operations are assumed not to fail during tests");
+	}
+
+	PackMetadata::read(&vfs, "").await
 }
 
 #[tokio::test]
@@ -486,3 +437,25 @@ async fn well_formed_real_and_complex_pack_mcmeta_works() {
 		"Unexpected bounding format version range"
 	);
 }
+
+#[test]
+fn layer_relative_paths_work() {
+	let layer_path_prefixes =
+		PackLayerPathPrefixes::new([arcstr::literal!(""), arcstr::literal!("overlay")].iter());
+
+	assert_eq!(
+		[
+			"assets/minecraft/lang/en_us.json",
+			"overlay/assets/minecraft/lang/en_us.json",
+			"overlay_2/assets/minecraft/lang/en_us.json",
+			"overlay"
+		]
+		.map(|relative_path| layer_path_prefixes.layer_relative_path(relative_path)),
+		[
+			"assets/minecraft/lang/en_us.json",
+			"assets/minecraft/lang/en_us.json",
+			"overlay_2/assets/minecraft/lang/en_us.json",
+			"overlay"
+		]
+	);
+}
@@ -12,6 +12,10 @@ use crate::{
 };
 
 impl PackFormatVersion {
+	/// The pack format version used in Minecraft versions from 1.11 to 1.12.2, for resource
+	/// packs.
+	const RELEASE_1_11: Self = Self::single_component(3);
+
 	/// The pack format version used in Minecraft versions from 1.13 to 1.14.4, for both resource
 	/// and data packs.
 	const RELEASE_1_13: Self = Self::single_component(4);
@@ -97,6 +101,14 @@ impl PackMetadata {
 		quirks
 	}
 
+	/// Checks whether some Minecraft version targeted by the pack restricts the characters that
+	/// can be used in resource locations, which was first done in Minecraft 1.11. Packs that may
+	/// target such a version are considered to do so.
+	pub fn restricts_resource_location_characters(&self) -> bool {
+		self.bounding_format_version_range()
+			.overlaps(PackFormatVersion::RELEASE_1_11..)
+	}
+
 	/// Returns a maybe pessimistic set of pack file asset types that Minecraft and
 	/// its mods can read from a pack.
 	///
@@ -184,6 +196,8 @@ impl PackMetadata {
 			*asset_type_mask |= PackFileAssetType::TrueTypeFont;
 		}
 		if versions_range.overlaps(PackFormatVersion::RELEASE_1_13..) {
+			*asset_type_mask |= PackFileAssetType::LanguageFile;
+			*asset_type_mask |= PackFileAssetType::LanguageFileWithComments;
 			*asset_type_mask |= PackFileAssetType::TrueTypeOrOpenTypeFont;
 		}
 
@@ -0,0 +1,149 @@
+//! Contains the validation of pack file paths against the restrictions that Minecraft places on
+//! resource locations, and the file systems packs may be extracted to.
+
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+
+use ahash::HashMap;
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::RelativePath;
+use crate::pack_file::asset_type::PackFileAssetTypeMatcher;
+use crate::pack_metadata::{PackLayerDirectoryName, PackLayerPathPrefixes};
+
+#[cfg(test)]
+mod tests;
+
+/// A problem with the relative path of a pack file, which may prevent Minecraft from using it.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum PackFilePathProblem {
+	/// The namespace of the file contains a character that is not allowed in resource locations.
+	#[error("The namespace contains the character {0:?}, which is not allowed in resource locations")]
+	InvalidNamespaceCharacter(char),
+	/// The path of the file within its namespace contains a character that is not allowed in
+	/// resource locations.
+	#[error("The path contains the character {0:?}, which is not allowed in resource locations")]
+	InvalidPathCharacter(char),
+	/// The path of the file collides with the path of another pack file, contained in this
+	/// variant, when compared case-insensitively, after Unicode normalization, or after changing
+	/// their extensions to the canonical ones they are stored with in the output ZIP file. Such
+	/// files can't coexist in the output ZIP file, or the file systems of most Windows and macOS
+	/// computers.
+	#[error(
+		"The path collides with {}, which only differs in letter case, Unicode normalization or \
+		extension",
+		.0.as_str()
+	)]
+	Collision(RelativePath<'static>)
+}
+
+/// Validates the relative paths of the pack files, one at a time, remembering the paths
+/// validated before to detect collisions between them.
+pub(crate) struct PackFilePathValidator {
+	layer_path_prefixes: PackLayerPathPrefixes,
+	/// Whether the characters of resource locations should be checked, which is only useful
+	/// when some targeted Minecraft version restricts them.
+	check_resource_location_characters: bool,
+	/// The already validated relative paths, indexed by the case-folded, normalized form of
+	/// their path in the output ZIP file.
+	validated_paths: HashMap<String, RelativePath<'static>>
+}
+
+impl PackFilePathValidator {
+	/// Creates a validator for the paths of a pack with the specified pack layers. The characters
+	/// of resource locations are only checked if `check_resource_location_characters` is `true`.
+	pub(crate) fn new<'layers>(
+		pack_layer_directory_names: impl Iterator<Item = &'layers PackLayerDirectoryName>,
+		check_resource_location_characters: bool
+	) -> Self {
+		Self {
+			layer_path_prefixes: PackLayerPathPrefixes::new(pack_layer_directory_names),
+			check_resource_location_characters,
+			validated_paths: HashMap::default()
+		}
+	}
+
+	/// Validates the specified pack file relative path, returning the first problem found
+	/// with it, if any. The asset type matcher is used to find out the canonical extension
+	/// the pack file will be stored with.
+	pub(crate) fn validate(
+		&mut self,
+		relative_path: &RelativePath<'static>,
+		asset_type_matcher: &PackFileAssetTypeMatcher
+	) -> Result<(), PackFilePathProblem> {
+		// Pack files may have their extension changed when added to the output ZIP file, so
+		// compare the paths they will be stored at
+		let output_path = match asset_type_matcher
+			.matches_for(relative_path)
+			.canonical_extension()
+		{
+			Some(canonical_extension) => Cow::Owned(
+				relative_path
+					.with_extension(canonical_extension)
+					.into_os_string()
+					.into_string()
+					.unwrap()
+			),
+			None => Cow::Borrowed(relative_path.as_str())
+		};
+
+		match self
+			.validated_paths
+			.entry(output_path.nfc().flat_map(char::to_lowercase).collect())
+		{
+			Entry::Occupied(colliding_path) => {
+				return Err(PackFilePathProblem::Collision(colliding_path.get().clone()));
+			}
+			Entry::Vacant(entry) => {
+				entry.insert(relative_path.clone());
+			}
+		}
+
+		if !self.check_resource_location_characters {
+			return Ok(());
+		}
+
+		let layer_relative_path = self
+			.layer_path_prefixes
+			.layer_relative_path(relative_path.as_str());
+
+		let mut path_components = layer_relative_path.splitn(3, '/');
+		let (Some("assets" | "data"), Some(namespace), Some(path)) = (
+			path_components.next(),
+			path_components.next(),
+			path_components.next()
+		) else {
+			return Ok(());
+		};
+
+		// Minecraft versions before 1.11 did not restrict resource locations, and their
+		// language files were named after locale codes with uppercase letters, like en_US
+		if path.starts_with("lang/") && path.ends_with(".lang") {
+			return Ok(());
+		}
+
+		if let Some(invalid_char) = namespace
+			.chars()
+			.find(|&c| !is_valid_resource_location_char(c))
+		{
+			return Err(PackFilePathProblem::InvalidNamespaceCharacter(invalid_char));
+		}
+
+		if let Some(invalid_char) = path
+			.chars()
+			.find(|&c| c != '/' && !is_valid_resource_location_char(c))
+		{
+			return Err(PackFilePathProblem::InvalidPathCharacter(invalid_char));
+		}
+
+		Ok(())
+	}
+}
+
+/// Checks whether the specified character is allowed in both the namespace and path of a
+/// resource location.
+fn is_valid_resource_location_char(c: char) -> bool {
+	matches!(c, 'a'..='z' | '0'..='9' | '_' | '.' | '-')
+}
@@ -0,0 +1,185 @@
+use enumset::EnumSet;
+use pretty_assertions::assert_eq;
+
+use crate::RelativePath;
+use crate::pack_file::CustomPackFileProcessors;
+use crate::pack_file::asset_type::PackFileAssetTypeMatcher;
+
+use super::PackFilePathValidator;
+
+/// Validates the specified relative paths in order with a fresh validator for a pack with the
+/// specified layers, which checks resource location characters, returning the user-friendly
+/// description of the problem found with each path, if any.
+fn validate_paths<'a>(
+	relative_paths: impl IntoIterator<Item = &'static str>,
+	pack_layer_directory_names: impl IntoIterator<Item = &'a str>
+) -> Vec<Option<String>> {
+	validate_paths_with(relative_paths, pack_layer_directory_names, true)
+}
+
+/// Like [`validate_paths`], but with the specified resource location characters checking
+/// setting.
+fn validate_paths_with<'a>(
+	relative_paths: impl IntoIterator<Item = &'static str>,
+	pack_layer_directory_names: impl IntoIterator<Item = &'a str>,
+	check_resource_location_characters: bool
+) -> Vec<Option<String>> {
+	let pack_layer_directory_names = pack_layer_directory_names
+		.into_iter()
+		.map(arcstr::ArcStr::from)
+		.collect::<Vec<_>>();
+	let asset_type_matcher = PackFileAssetTypeMatcher::new(
+		EnumSet::all(),
+		pack_layer_directory_names.iter(),
+		&CustomPackFileProcessors::default()
+	);
+	let mut validator = PackFilePathValidator::new(
+		pack_layer_directory_names.iter(),
+		check_resource_location_characters
+	);
+
+	relative_paths
+		.into_iter()
+		.map(|relative_path| {
+			validator
+				.validate(
+					&RelativePath::from_inner(relative_path),
+					&asset_type_matcher
+				)
+				.err()
+				.map(|problem| problem.to_string())
+		})
+		.collect()
+}
+
+#[test]
+fn valid_paths_have_no_problems() {
+	assert_eq!(
+		validate_paths(
+			[
+				"pack.mcmeta",
+				"Pack Icon.png",
+				"assets/my_pack/textures/block/stone-1.png",
+				"data/my_pack/function/setup.mcfunction",
+				"assets/minecraft/lang/en_US.lang",
+				"overlay/assets/my_pack/textures/block/stone-1.png"
+			],
+			["", "overlay"]
+		),
+		[None, None, None, None, None, None]
+	);
+}
+
+#[test]
+fn invalid_resource_location_characters_are_found() {
+	assert_eq!(
+		validate_paths(
+			[
+				"assets/MyPack/textures/block/stone.png",
+				"assets/my_pack/textures/block/Stone Brick.png",
+				"overlay/data/my_pack/function/ñandú.mcfunction"
+			],
+			["", "overlay"]
+		),
+		[
+			Some(
+				"The namespace contains the character 'M', which is not allowed in resource locations"
+					.to_string()
+			),
+			Some(
+				"The path contains the character 'S', which is not allowed in resource locations"
+					.to_string()
+			),
+			Some(
+				"The path contains the character 'ñ', which is not allowed in resource locations"
+					.to_string()
+			)
+		]
+	);
+}
+
+#[test]
+fn colliding_paths_are_found() {
+	assert_eq!(
+		validate_paths(
+			[
+				"assets/my_pack/textures/block/stone.png",
+				"assets/my_pack/textures/block/Stone.png",
+				"overlay/assets/my_pack/textures/block/stone.png",
+				"Caf\u{e9}.txt",
+				"Cafe\u{301}.txt"
+			],
+			["", "overlay"]
+		),
+		[
+			None,
+			Some(
+				"The path collides with assets/my_pack/textures/block/stone.png, which only differs \
+				in letter case, Unicode normalization or extension"
+					.to_string()
+			),
+			None,
+			None,
+			Some(
+				"The path collides with Caf\u{e9}.txt, which only differs in letter case, Unicode \
+				normalization or extension"
+					.to_string()
+			)
+		]
+	);
+}
+
+#[test]
+fn paths_colliding_after_extension_canonicalization_are_found() {
+	assert_eq!(
+		validate_paths(
+			[
+				"assets/my_pack/models/block/stone.json",
+				"assets/my_pack/models/block/stone.jsonc",
+				"assets/my_pack/sounds/step.oga",
+				"assets/my_pack/sounds/Step.wav",
+				"assets/my_pack/texts/notes.txt",
+				"assets/my_pack/texts/notes.text"
+			],
+			[""]
+		),
+		[
+			None,
+			Some(
+				"The path collides with assets/my_pack/models/block/stone.json, which only differs \
+				in letter case, Unicode normalization or extension"
+					.to_string()
+			),
+			None,
+			Some(
+				"The path collides with assets/my_pack/sounds/step.oga, which only differs in \
+				letter case, Unicode normalization or extension"
+					.to_string()
+			),
+			None,
+			None
+		]
+	);
+}
+
+#[test]
+fn resource_location_characters_are_only_checked_when_requested() {
+	assert_eq!(
+		validate_paths_with(
+			[
+				"assets/MyPack/textures/block/Stone.png",
+				"assets/mypack/textures/block/stone.png"
+			],
+			[""],
+			false
+		),
+		[
+			None,
+			Some(
+				"The path collides with assets/MyPack/textures/block/Stone.png, which only differs \
+				in letter case, Unicode normalization or extension"
+					.to_string()
+			)
+		]
+	);
+}
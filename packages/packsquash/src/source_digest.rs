@@ -0,0 +1,57 @@
+//! Contains the logic to compute the digests of pack file sources, which identify a pack file
+//! and every parameter that affects how it is processed.
+
+use std::io;
+
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::config::FileOptions;
+use crate::squash_zip::SourceDigest;
+
+#[cfg(test)]
+mod tests;
+
+/// Computes the source digest of a pack file with the contents read from the specified reader,
+/// which is processed as the specified asset type, in the way identified by the specified
+/// processing identity, with the specified effective file options, and compressed with the
+/// specified number of Zopfli iterations, unless its compression is skipped.
+pub(crate) async fn compute_source_digest(
+	mut pack_file_read: impl AsyncRead + Unpin,
+	asset_type: &str,
+	processing_identity: &str,
+	file_options: Option<&FileOptions>,
+	zopfli_iterations: u8,
+	skip_compression: bool
+) -> Result<SourceDigest, io::Error> {
+	let mut options_hasher = Sha256::new();
+
+	// Parameters are prefixed with their length so that different values never hash the
+	// same bytes
+	for parameter in [
+		env!("CARGO_PKG_VERSION").as_bytes(),
+		asset_type.as_bytes(),
+		processing_identity.as_bytes(),
+		&file_options.map_or_else(Vec::new, FileOptions::fingerprint)[..]
+	] {
+		options_hasher.update((parameter.len() as u64).to_le_bytes());
+		options_hasher.update(parameter);
+	}
+	options_hasher.update([zopfli_iterations, skip_compression.into()]);
+
+	let mut content_hasher = Sha256::new();
+	let mut buf = vec![0; 64 * 1024];
+	loop {
+		let read_bytes = pack_file_read.read(&mut buf).await?;
+		if read_bytes == 0 {
+			break;
+		}
+
+		content_hasher.update(&buf[..read_bytes]);
+	}
+
+	Ok(SourceDigest {
+		content_hash: content_hasher.finalize().into(),
+		options_fingerprint: options_hasher.finalize().into()
+	})
+}
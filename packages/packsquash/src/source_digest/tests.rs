@@ -0,0 +1,172 @@
+use crate::config::{FileOptions, JsonFileOptions, PngFileOptions};
+
+use super::*;
+
+static UNEXPECTED_IO_FAILURE: &str = "I/O operations are assumed not to fail during tests";
+
+#[tokio::test]
+async fn digests_depend_on_every_parameter() {
+	let json_file_options = FileOptions::JsonFileOptions(JsonFileOptions::default());
+	let mut prettified_json_file_options = JsonFileOptions::default();
+	prettified_json_file_options.minify = false;
+	let prettified_json_file_options = FileOptions::JsonFileOptions(prettified_json_file_options);
+
+	let digest = |data: &'static [u8],
+	              asset_type,
+	              processing_identity,
+	              file_options,
+	              zopfli_iterations,
+	              skip_compression| {
+		compute_source_digest(
+			data,
+			asset_type,
+			processing_identity,
+			file_options,
+			zopfli_iterations,
+			skip_compression
+		)
+	};
+
+	let reference_digest = digest(
+		b"{}",
+		"generic_json",
+		"",
+		Some(&json_file_options),
+		20,
+		false
+	)
+	.await
+	.expect(UNEXPECTED_IO_FAILURE);
+
+	assert_eq!(
+		digest(
+			b"{}",
+			"generic_json",
+			"",
+			Some(&json_file_options),
+			20,
+			false
+		)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE),
+		reference_digest,
+		"Digests should be deterministic"
+	);
+
+	for (other_digest, content_changed) in [
+		(
+			digest(
+				b"[]",
+				"generic_json",
+				"",
+				Some(&json_file_options),
+				20,
+				false
+			)
+			.await,
+			true
+		),
+		(
+			digest(
+				b"{}",
+				"minecraft_model",
+				"",
+				Some(&json_file_options),
+				20,
+				false
+			)
+			.await,
+			false
+		),
+		(
+			digest(
+				b"{}",
+				"generic_json",
+				"",
+				Some(&prettified_json_file_options),
+				20,
+				false
+			)
+			.await,
+			false
+		),
+		(
+			digest(
+				b"{}",
+				"generic_json",
+				"my_processor",
+				Some(&json_file_options),
+				20,
+				false
+			)
+			.await,
+			false
+		),
+		(
+			digest(b"{}", "generic_json", "", None, 20, false).await,
+			false
+		),
+		(
+			digest(
+				b"{}",
+				"generic_json",
+				"",
+				Some(&json_file_options),
+				5,
+				false
+			)
+			.await,
+			false
+		),
+		(
+			digest(
+				b"{}",
+				"generic_json",
+				"",
+				Some(&json_file_options),
+				20,
+				true
+			)
+			.await,
+			false
+		)
+	] {
+		let other_digest = other_digest.expect(UNEXPECTED_IO_FAILURE);
+
+		assert_ne!(
+			other_digest, reference_digest,
+			"Digests should change when any parameter changes"
+		);
+		assert_eq!(
+			other_digest.content_hash != reference_digest.content_hash,
+			content_changed,
+			"Content hashes should only change when the content changes"
+		);
+	}
+}
+
+#[tokio::test]
+async fn digests_depend_on_derived_file_options() {
+	let png_file_options = PngFileOptions::default();
+	let mut quirk_png_file_options = PngFileOptions::default();
+	quirk_png_file_options.working_around_grayscale_reduction_quirk = true;
+
+	let digest = async |png_file_options: PngFileOptions| {
+		compute_source_digest(
+			&b""[..],
+			"png",
+			"",
+			Some(&FileOptions::PngFileOptions(png_file_options)),
+			20,
+			false
+		)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE)
+	};
+
+	assert_ne!(
+		digest(quirk_png_file_options).await,
+		digest(png_file_options).await,
+		"Digests should change when crate-private settings derived from options change"
+	);
+}
@@ -14,30 +14,36 @@ use std::{
 use aes::Aes256;
 use ahash::AHashMap;
 use futures::{StreamExt, TryStreamExt, future};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
 use thiserror::Error;
 use tokio::{
 	fs::File,
-	io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWriteExt},
+	io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
 	sync::Mutex
 };
 use tokio_stream::Stream;
-use tokio_util::io::ReaderStream;
+use tokio_util::io::{InspectReader, ReaderStream};
 use zopfli::Format;
 
 use self::{
 	obfuscation_engine::ObfuscationEngine,
 	system_time_sanitizer::{SystemTimeSanitizationError, SystemTimeSanitizer},
 	zip_file_record::{
-		CentralDirectoryHeader, CompressionMethod, EndOfCentralDirectory, LocalFileHeader
+		CentralDirectoryHeader, CompressionMethod, EndOfCentralDirectory, LocalFileHeader,
+		system_time_to_dos_date_time
 	}
 };
 use crate::{
 	RelativePath, buffered_async_spooled_temp_file::BufferedAsyncSpooledTempFile,
-	config::PercentageInteger, zopfli_iterations_time_model::ZopfliIterationsTimeModel
+	config::PercentageInteger, output_manifest::hex_string,
+	zopfli_iterations_time_model::ZopfliIterationsTimeModel
 };
 
 pub use self::obfuscation_engine::FileListingCircumstances;
 pub use self::zip_archive_comment_string::ZipArchiveCommentString;
+pub use self::zip_inspection::{ZipEntryInspection, ZipInspectionReport, inspect_zip};
+pub use self::zip_verification::{ZipVerificationProblem, ZipVerificationReport, verify_zip};
 
 mod obfuscation_engine;
 pub mod relative_path;
@@ -45,6 +51,8 @@ mod system_id;
 pub mod system_time_sanitizer;
 mod zip_archive_comment_string;
 mod zip_file_record;
+mod zip_inspection;
+mod zip_verification;
 
 #[cfg(test)]
 mod tests;
@@ -56,8 +64,15 @@ const MAXIMUM_ZOPFLI_ITERATIONS: u8 = 20;
 /// Contains information about a file that was processed in a previous
 /// run of PackSquash; i.e., already present in a generated ZIP file.
 struct PreviousFile {
-	/// Time when this file was processed in the previous run.
-	squash_time: SystemTime,
+	/// Time when this file was processed in the previous run. This may be `None`
+	/// only if the file has a source digest, which can be used instead to tell
+	/// whether the file can be reused.
+	squash_time: Option<SystemTime>,
+	/// The Squash Time of this file as stored in the previous ZIP file, before
+	/// decrypting it. This is kept for diagnostic purposes only.
+	sanitized_squash_time: [u8; 4],
+	/// The digest of the source of this file in the previous run, if it was stored.
+	source_digest: Option<SourceDigest>,
 	/// The offset to (seek position in the file of) the processed,
 	/// compressed data.
 	data_offset: u64,
@@ -84,6 +99,7 @@ struct PartialCentralDirectoryHeader {
 	crc32: u32,
 	compressed_size: u32,
 	uncompressed_size: u32,
+	source_digest: Option<SourceDigest>,
 	listing_circumstances: FileListingCircumstances
 }
 
@@ -94,6 +110,136 @@ struct HashAndSize {
 	size: u32
 }
 
+/// The digests of a ZIP file generated by SquashZip, which are computed while the ZIP file
+/// is written to its final destination. These digests are useful to verify the integrity of
+/// the ZIP file when it is distributed: for example, Minecraft servers can be configured to
+/// make clients check the SHA-1 digest of a resource pack they download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputZipDigests {
+	sha1: [u8; 20],
+	sha256: [u8; 32],
+	size: u64
+}
+
+impl OutputZipDigests {
+	/// Returns the SHA-1 digest of the ZIP file.
+	pub fn sha1(&self) -> &[u8; 20] {
+		&self.sha1
+	}
+
+	/// Returns the SHA-256 digest of the ZIP file.
+	pub fn sha256(&self) -> &[u8; 32] {
+		&self.sha256
+	}
+
+	/// Returns the lowercase hexadecimal representation of the SHA-1 digest of the ZIP file,
+	/// which is the usual way to display it.
+	pub fn sha1_hex(&self) -> String {
+		hex_string(&self.sha1)
+	}
+
+	/// Returns the lowercase hexadecimal representation of the SHA-256 digest of the ZIP file,
+	/// which is the usual way to display it.
+	pub fn sha256_hex(&self) -> String {
+		hex_string(&self.sha256)
+	}
+
+	/// Returns the size of the ZIP file, in bytes.
+	pub fn size(&self) -> u64 {
+		self.size
+	}
+}
+
+/// Describes how a file was stored in a ZIP file generated by SquashZip, after
+/// adding it with [`SquashZip::add_file()`] or [`SquashZip::add_previous_file()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoredFileInfo {
+	/// The CRC-32 checksum of the file data, before compressing it.
+	pub crc32: u32,
+	/// The size of the file data, before compressing it.
+	pub uncompressed_size: u32,
+	/// The size of the file data, after compressing it.
+	pub compressed_size: u32,
+	/// Whether the file data was compressed with the DEFLATE method, instead of being
+	/// stored as-is.
+	pub deflated: bool
+}
+
+impl StoredFileInfo {
+	/// Describes the file that the specified local file header refers to, which must
+	/// not be obfuscated yet.
+	fn of_local_file_header(local_file_header: &LocalFileHeader<'_>) -> Self {
+		Self {
+			crc32: local_file_header.crc32,
+			uncompressed_size: local_file_header.uncompressed_size,
+			compressed_size: local_file_header.compressed_size,
+			deflated: local_file_header.compression_method == CompressionMethod::Deflate
+		}
+	}
+}
+
+/// A digest of the source of a file stored in a ZIP file generated by SquashZip, which
+/// identifies the file contents before processing them and the options that affected their
+/// processing. When stored, these digests allow reusing files from a previous run whenever
+/// their sources did not change, no matter what their modification times are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceDigest {
+	/// A SHA-256 hash of the unprocessed file contents.
+	pub content_hash: [u8; 32],
+	/// A fingerprint of every option and parameter that affected how the file contents were
+	/// processed and compressed, which is a SHA-256 hash of them.
+	pub options_fingerprint: [u8; 32]
+}
+
+impl SourceDigest {
+	/// The size of the byte representation of a source digest.
+	const SIZE: usize = 64;
+
+	/// Returns the byte representation of this source digest, as stored in ZIP files.
+	fn to_bytes(self) -> [u8; Self::SIZE] {
+		let mut bytes = [0; Self::SIZE];
+		bytes[..32].copy_from_slice(&self.content_hash);
+		bytes[32..].copy_from_slice(&self.options_fingerprint);
+		bytes
+	}
+
+	/// Parses a source digest from its byte representation, as stored in ZIP files.
+	fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+		Self {
+			content_hash: bytes[..32].try_into().unwrap(),
+			options_fingerprint: bytes[32..].try_into().unwrap()
+		}
+	}
+}
+
+/// A file whose processed data was compressed by SquashZip, ready to be added to a ZIP
+/// file with [`SquashZip::add_compressed_file()`].
+pub struct CompressedFile {
+	data: BufferedAsyncSpooledTempFile,
+	info: StoredFileInfo,
+	squash_time: Option<SystemTime>,
+	source_digest: Option<SourceDigest>
+}
+
+impl CompressedFile {
+	/// Returns a description of how this file will be stored in a ZIP file.
+	pub const fn info(&self) -> StoredFileInfo {
+		self.info
+	}
+
+	/// Sets the digest of the source of this file, which will be stored in the ZIP file
+	/// alongside it, so that future runs can check whether the file can be reused.
+	pub fn set_source_digest(&mut self, source_digest: SourceDigest) {
+		self.source_digest = Some(source_digest);
+	}
+
+	/// Reads all the compressed data of this file, appending it to the specified buffer.
+	pub async fn read_data_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, io::Error> {
+		self.data.rewind().await?;
+		self.data.read_to_end(buf).await
+	}
+}
+
 /// Represents an error that may happen during a fallible SquashZip operation.
 #[derive(Error, Debug)]
 #[non_exhaustive]
@@ -171,7 +317,16 @@ pub struct SquashZipSettings {
 	/// them will have a buffer `spool_buffer_size / 2` bytes big.
 	pub spool_buffer_size: usize,
 	/// The comment that will be attached to the output ZIP file.
-	pub zip_comment: ZipArchiveCommentString
+	pub zip_comment: ZipArchiveCommentString,
+	/// If set, SquashZip will generate byte-identical ZIP files for the same added files and
+	/// settings, no matter the order the files were added in. To achieve this, the local file
+	/// header and central directory records are sorted by file path, and the modification time
+	/// of every file is set to this time. This takes precedence over `store_squash_time`, as
+	/// Squash Times can't be reproduced.
+	///
+	/// Obfuscation uses non-deterministic sources of randomness, so it must not be enabled
+	/// alongside this setting.
+	pub reproducible_output_time: Option<SystemTime>
 }
 
 /// A custom, minimalistic ZIP compressor, which exploits its great control
@@ -226,7 +381,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		let mut output_zip = BufferedAsyncSpooledTempFile::new(settings.spool_buffer_size);
 
 		let previous_zip_contents = if let Some(previous_zip) = &mut previous_zip {
-			match read_previous_zip_contents(previous_zip, &obfuscation_engine).await {
+			match read_previous_zip_contents(previous_zip, &obfuscation_engine, true).await {
 				Ok(previous_zip_contents) => previous_zip_contents,
 				Err(err) => return Err((err.into(), settings))
 			}
@@ -277,6 +432,8 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 	/// The result ZIP file may be left in an inconsistent state if this method returns
 	/// an error. The caller probably should discard the ZIP file if this happens, by
 	/// not calling any further methods on this instance.
+	///
+	/// On success, a description of how the file was stored is returned.
 	pub async fn add_file<T: AsRef<[u8]>>(
 		&self,
 		path: &RelativePath<'_>,
@@ -284,16 +441,30 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		skip_compression: bool,
 		file_size_hint: usize,
 		listing_circumstances: FileListingCircumstances
-	) -> Result<(), SquashZipError> {
-		let (mut local_file_header, mut compressed_data_scratch_file) = self
-			.compress_and_generate_local_header(
-				path,
-				processed_data,
-				skip_compression,
-				file_size_hint
-			)
+	) -> Result<StoredFileInfo, SquashZipError> {
+		let compressed_file = self
+			.compress_file(processed_data, skip_compression, file_size_hint)
 			.await?;
 
+		self.add_compressed_file(path, compressed_file, listing_circumstances)
+			.await
+	}
+
+	/// Adds a new file to the result ZIP file from its path and its compressed contents,
+	/// which were returned by [`Self::compress_file()`] or [`Self::precompressed_file()`].
+	///
+	/// The same remarks about adding files with [`Self::add_file()`] apply to this method.
+	pub async fn add_compressed_file(
+		&self,
+		path: &RelativePath<'_>,
+		compressed_file: CompressedFile,
+		listing_circumstances: FileListingCircumstances
+	) -> Result<StoredFileInfo, SquashZipError> {
+		let mut local_file_header = self.generate_local_header(path, &compressed_file)?;
+		let stored_file_info = compressed_file.info;
+		let source_digest = compressed_file.source_digest;
+		let mut compressed_data_scratch_file = compressed_file.data;
+
 		let state = &mut *self.state.lock().await;
 		let output_zip = &mut state.output_zip;
 
@@ -357,6 +528,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 					path,
 					&local_file_header,
 					*matching_header_offset,
+					source_digest,
 					listing_circumstances,
 					&mut state.central_directory_data
 				)?;
@@ -387,28 +559,36 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 				path,
 				&local_file_header,
 				new_local_file_header_offset,
+				source_digest,
 				listing_circumstances,
 				&mut state.central_directory_data
 			)?;
 
-			self.obfuscation_engine
-				.obfuscate_local_file_header(&mut local_file_header);
+			// Reproducible ZIP files get their local headers written in order when finishing
+			// them, so just store the compressed data for now
+			let local_file_header_size = if self.settings.reproducible_output_time.is_none() {
+				self.obfuscation_engine
+					.obfuscate_local_file_header(&mut local_file_header);
+
+				local_file_header.write(output_zip).await?;
+
+				local_file_header.size()
+			} else {
+				0
+			};
 
 			// Avoid allocating memory for the dummy vector
 			if self.settings.enable_deduplication {
-				matching_local_headers.push((new_local_file_header_offset, local_file_header.size()));
+				matching_local_headers.push((new_local_file_header_offset, local_file_header_size));
 			}
 
-			// Write the local header
-			local_file_header.write(output_zip).await?;
-
 			// Write the compressed data
 			compressed_data_scratch_file.rewind().await?;
 
 			tokio::io::copy(&mut compressed_data_scratch_file, output_zip).await?;
 		}
 
-		Ok(())
+		Ok(stored_file_info)
 	}
 
 	/// Returns the time the specified file was added to the ZIP file generated by
@@ -418,11 +598,21 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 	pub fn file_process_time(&self, file_path: &RelativePath<'_>) -> Option<SystemTime> {
 		self.previous_zip_contents
 			.get(file_path)
-			.map(|previous_file| previous_file.squash_time)
+			.and_then(|previous_file| previous_file.squash_time)
+	}
+
+	/// Returns the source digest the specified file was stored with in the ZIP file
+	/// generated by SquashZip in a previous run. `None` may be returned if, for instance,
+	/// the file didn't exist before, or it was stored without a source digest.
+	pub fn file_source_digest(&self, file_path: &RelativePath<'_>) -> Option<SourceDigest> {
+		self.previous_zip_contents
+			.get(file_path)
+			.and_then(|previous_file| previous_file.source_digest)
 	}
 
 	/// Returns the number of files contained in the ZIP file generated in a previous run.
 	/// This will be zero if the file is empty or there is no previous file.
+	#[cfg(test)]
 	pub fn previous_file_count(&self) -> usize {
 		self.previous_zip_contents.len()
 	}
@@ -439,11 +629,13 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 	/// was not present in the previous ZIP file. In this case it is guaranteed that no bad
 	/// state was introduced in the result output ZIP file, and the instance can still be used
 	/// normally.
+	///
+	/// On success, a description of how the file was stored is returned.
 	pub async fn add_previous_file(
 		&self,
 		path: &RelativePath<'_>,
 		listing_circumstances: FileListingCircumstances
-	) -> Result<(), SquashZipError> {
+	) -> Result<StoredFileInfo, SquashZipError> {
 		// For this method we implement a simpler version of the algorithm of add_file. It can be
 		// summarised as follows:
 		// 1. Check if the file is in map 1) (hash, size) -> (LOC offset list).
@@ -461,12 +653,13 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			)));
 		};
 
-		// We can sanitize the Squash Time no matter what because we fail early if there was
-		// no previous file, and any previous file has Squash Time data
+		// Previous files without usable Squash Time data are reused because their source
+		// digest matched, so consider they were processed right now
 		let sanitized_squash_time = SYSTEM_TIME_SANITIZER.sanitize(
-			&previous_file.squash_time,
+			&previous_file.squash_time.unwrap_or_else(SystemTime::now),
 			&previous_file.crc32.to_le_bytes()
 		)?;
+		let source_digest = previous_file.source_digest;
 
 		// Reconstruct the local file header this file would have
 		let mut local_file_header = LocalFileHeader::new(Cow::Borrowed(path));
@@ -475,6 +668,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 		local_file_header.compression_method = previous_file.compression_method;
 		local_file_header.uncompressed_size = previous_file.uncompressed_size;
 		local_file_header.compressed_size = previous_file.compressed_size;
+		let stored_file_info = StoredFileInfo::of_local_file_header(&local_file_header);
 
 		let state = &mut *self.state.lock().await;
 		let output_zip = &mut state.output_zip;
@@ -572,6 +766,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 					path,
 					&local_file_header,
 					*matching_header_offset,
+					source_digest,
 					listing_circumstances,
 					&mut state.central_directory_data
 				)?;
@@ -602,21 +797,29 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 				path,
 				&local_file_header,
 				new_local_file_header_offset,
+				source_digest,
 				listing_circumstances,
 				&mut state.central_directory_data
 			)?;
 
-			self.obfuscation_engine
-				.obfuscate_local_file_header(&mut local_file_header);
+			// Reproducible ZIP files get their local headers written in order when finishing
+			// them, so just store the compressed data for now
+			let local_file_header_size = if self.settings.reproducible_output_time.is_none() {
+				self.obfuscation_engine
+					.obfuscate_local_file_header(&mut local_file_header);
+
+				local_file_header.write(output_zip).await?;
+
+				local_file_header.size()
+			} else {
+				0
+			};
 
 			// Avoid allocating memory for the dummy vector
 			if self.settings.enable_deduplication {
-				matching_local_headers.push((new_local_file_header_offset, local_file_header.size()));
+				matching_local_headers.push((new_local_file_header_offset, local_file_header_size));
 			}
 
-			// Write the local header
-			local_file_header.write(output_zip).await?;
-
 			// Write the compressed data
 			previous_zip
 				.seek(SeekFrom::Start(previous_file.data_offset))
@@ -629,18 +832,84 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			.await?;
 		}
 
-		Ok(())
+		Ok(stored_file_info)
 	}
 
 	/// Finishes this ZIP file, writing any needed remaining data structures and flushing all
 	/// the data to a new file in the specified path.
 	///
 	/// This operation ends the lifecycle of this SquashZip instance, consuming it, so no
-	/// further operations can be done on the ZIP file after this method returns.
-	pub async fn finish(self, path: impl AsRef<Path>) -> Result<(), SquashZipError> {
+	/// further operations can be done on the ZIP file after this method returns. The digests
+	/// of the generated ZIP file are computed while it is written, and returned.
+	pub async fn finish(self, path: impl AsRef<Path>) -> Result<OutputZipDigests, SquashZipError> {
+		let output_zip = self.finish_to_scratch_file().await?;
+
+		Self::copy_to_sink(output_zip, &mut File::create(path).await?).await
+	}
+
+	/// Finishes this ZIP file like [`finish`](Self::finish) does, but writing all the data to
+	/// the specified sink instead of a file, and flushing it afterwards.
+	pub async fn finish_to_writer(
+		self,
+		output_writer: &mut (impl AsyncWrite + Unpin)
+	) -> Result<OutputZipDigests, SquashZipError> {
+		let output_zip = self.finish_to_scratch_file().await?;
+
+		Self::copy_to_sink(output_zip, output_writer).await
+	}
+
+	/// Writes the generated ZIP file to its final sink, which may be a file or not, computing
+	/// its digests on the fly.
+	async fn copy_to_sink(
+		mut output_zip: BufferedAsyncSpooledTempFile,
+		sink: &mut (impl AsyncWrite + Unpin)
+	) -> Result<OutputZipDigests, SquashZipError> {
+		// This also implicitly flushes any buffer, so any error during flushing will be returned
+		output_zip.rewind().await?;
+
+		let mut sha1_hasher = Sha1::new();
+		let mut sha256_hasher = Sha256::new();
+
+		let size = tokio::io::copy(
+			&mut InspectReader::new(output_zip, |data| {
+				sha1_hasher.update(data);
+				sha256_hasher.update(data);
+			}),
+			sink
+		)
+		.await?;
+
+		Ok(OutputZipDigests {
+			sha1: sha1_hasher.finalize().into(),
+			sha256: sha256_hasher.finalize().into(),
+			size
+		})
+	}
+
+	/// Writes any needed remaining data structures to the scratch file that holds this ZIP
+	/// file, and returns that scratch file, consuming this SquashZip instance.
+	async fn finish_to_scratch_file(self) -> Result<BufferedAsyncSpooledTempFile, SquashZipError> {
 		let state = self.state.into_inner();
-		let central_directory_data = state.central_directory_data;
-		let mut output_zip = state.output_zip;
+
+		let mut central_directory_data = state.central_directory_data.into_iter().collect::<Vec<_>>();
+
+		let mut output_zip = if self.settings.reproducible_output_time.is_some() {
+			// Sort the central directory by file path, so that its order does not depend on
+			// the order the files were added in
+			central_directory_data.sort_unstable_by(|(file_name, _), (other_file_name, _)| {
+				file_name.as_str().cmp(other_file_name.as_str())
+			});
+
+			Self::write_local_file_records_in_order(
+				state.output_zip,
+				&mut central_directory_data,
+				&self.obfuscation_engine,
+				self.settings.spool_buffer_size
+			)
+			.await?
+		} else {
+			state.output_zip
+		};
 
 		let central_directory_entry_count = u64::try_from(central_directory_data.len())?;
 		let central_directory_start_offset = output_zip.stream_position().await?;
@@ -655,6 +924,7 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 				uncompressed_size: header_data.uncompressed_size,
 				local_header_disk_number: 0,
 				local_header_offset: header_data.local_header_offset,
+				source_digest: header_data.source_digest.map(SourceDigest::to_bytes),
 				file_name,
 				spoof_version_made_by: false
 			};
@@ -690,31 +960,89 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 
 		end_of_central_directory.write(&mut output_zip).await?;
 
-		// Finally, write the generated ZIP file to its place!
-		// This also implicitly flushes any buffer, so any error during flushing will be returned
-		output_zip.rewind().await?;
+		Ok(output_zip)
+	}
 
-		tokio::io::copy(&mut output_zip, &mut File::create(path).await?).await?;
+	/// Writes the local file records of a reproducible ZIP file to a new scratch file, in the
+	/// order of the specified sorted central directory data, and returns that scratch file. The
+	/// local header offsets of the central directory data are expected to point to the compressed
+	/// data of each file in the specified scratch file, and are updated to point to the newly
+	/// written local file headers.
+	async fn write_local_file_records_in_order(
+		mut compressed_data_file: BufferedAsyncSpooledTempFile,
+		central_directory_data: &mut [(RelativePath<'static>, PartialCentralDirectoryHeader)],
+		obfuscation_engine: &ObfuscationEngine,
+		spool_buffer_size: usize
+	) -> Result<BufferedAsyncSpooledTempFile, SquashZipError> {
+		let mut output_zip = BufferedAsyncSpooledTempFile::new(spool_buffer_size);
+
+		// Keep the obfuscating header, which precedes the compressed data of every file
+		compressed_data_file.rewind().await?;
+		tokio::io::copy(
+			&mut AsyncReadExt::take(
+				&mut compressed_data_file,
+				obfuscation_engine.obfuscating_header_size()
+			),
+			&mut output_zip
+		)
+		.await?;
 
-		Ok(())
+		// Deduplicated files share their compressed data, so they must share the local file
+		// header too. It will be written for the first file in order
+		let mut local_header_offsets = AHashMap::with_capacity(central_directory_data.len());
+		for (file_name, header_data) in central_directory_data {
+			let data_offset = header_data.local_header_offset;
+
+			header_data.local_header_offset = match local_header_offsets.entry(data_offset) {
+				Entry::Occupied(entry) => *entry.get(),
+				Entry::Vacant(entry) => {
+					let local_header_offset = output_zip.stream_position().await?;
+
+					let mut local_file_header = LocalFileHeader::new(Cow::Borrowed(&*file_name));
+					local_file_header.compression_method = header_data.compression_method;
+					local_file_header.squash_time = header_data.squash_time;
+					local_file_header.crc32 = header_data.crc32;
+					local_file_header.compressed_size = header_data.compressed_size;
+					local_file_header.uncompressed_size = header_data.uncompressed_size;
+
+					obfuscation_engine.obfuscate_local_file_header(&mut local_file_header);
+
+					local_file_header.write(&mut output_zip).await?;
+
+					compressed_data_file
+						.seek(SeekFrom::Start(data_offset))
+						.await?;
+					tokio::io::copy(
+						&mut AsyncReadExt::take(
+							&mut compressed_data_file,
+							header_data.compressed_size as u64
+						),
+						&mut output_zip
+					)
+					.await?;
+
+					*entry.insert(local_header_offset)
+				}
+			};
+		}
+
+		Ok(output_zip)
 	}
 
-	/// Compresses a stream of processed data for the given ZIP file path, returning its corresponding
-	/// local file header and a scratch data file that contains its most efficient representation in
-	/// terms of size. The scratch data file stream position is just after the compressed contents, so
-	/// to read the compressed data back client code may need to rewind the file first.
-	async fn compress_and_generate_local_header<'a, T: AsRef<[u8]>>(
+	/// Compresses a stream of processed file data to its most efficient representation in terms
+	/// of size, so that it can be added to the result ZIP file with [`Self::add_compressed_file()`].
+	///
+	/// The Squash Time of the file is taken when this method is called, so the returned compressed
+	/// file should be added to the ZIP file promptly.
+	pub async fn compress_file<T: AsRef<[u8]>>(
 		&self,
-		path: &'a RelativePath<'a>,
 		mut processed_data: impl Stream<Item = T> + Unpin,
 		skip_compression: bool,
 		file_size_hint: usize
-	) -> Result<(LocalFileHeader<'a>, BufferedAsyncSpooledTempFile), SquashZipError> {
+	) -> Result<CompressedFile, SquashZipError> {
 		// Get the Squash Time right now, so it is as close as possible to the time when
-		// we saw whether it was modified or not, which is a good thing. Instantiate the
-		// local file header now, so we validate the path as early as possible
-		let squash_time = self.settings.store_squash_time.then(SystemTime::now);
-		let mut local_file_header = LocalFileHeader::new(Cow::Borrowed(path));
+		// we saw whether it was modified or not, which is a good thing
+		let squash_time = self.squash_time_for_new_file();
 
 		// Set up our scratch data files
 		let mut processed_data_scratch_file = BufferedAsyncSpooledTempFile::with_capacity(
@@ -788,26 +1116,103 @@ impl<F: AsyncRead + AsyncSeek + Unpin> SquashZip<F> {
 			compressed_data_scratch_file = processed_data_scratch_file;
 		}
 
-		// Now populate all the local file header fields
-		local_file_header.compression_method = compression_method;
-		local_file_header.crc32 = processed_data_crc;
-		local_file_header.uncompressed_size = processed_data_size;
-		// The cast is always okay because compressed_data_size <= processed_data_size
-		local_file_header.compressed_size = compressed_data_size as u32;
-		if let Some(squash_time) = squash_time {
+		Ok(CompressedFile {
+			data: compressed_data_scratch_file,
+			info: StoredFileInfo {
+				crc32: processed_data_crc,
+				uncompressed_size: processed_data_size,
+				// The cast is always okay because compressed_data_size <= processed_data_size
+				compressed_size: compressed_data_size as u32,
+				deflated: compression_method == CompressionMethod::Deflate
+			},
+			squash_time,
+			source_digest: None
+		})
+	}
+
+	/// Creates a compressed file from data that was already compressed as described by the
+	/// specified information, such as data that was compressed by [`Self::compress_file()`]
+	/// in a previous run and then stored elsewhere. The returned compressed file can be added
+	/// to the result ZIP file with [`Self::add_compressed_file()`].
+	///
+	/// As with [`Self::compress_file()`], the Squash Time of the file is taken when this method
+	/// is called. The size of the data is checked against the provided information, but it is
+	/// the responsibility of the caller to make sure that the data is otherwise correct.
+	pub async fn precompressed_file(
+		&self,
+		info: StoredFileInfo,
+		data: &[u8]
+	) -> Result<CompressedFile, SquashZipError> {
+		let squash_time = self.squash_time_for_new_file();
+
+		if data.len() != info.compressed_size as usize
+			|| (!info.deflated && info.compressed_size != info.uncompressed_size)
+		{
+			return Err(SquashZipError::Io(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"The size of the compressed data does not match its description"
+			)));
+		}
+
+		let mut compressed_data_scratch_file = BufferedAsyncSpooledTempFile::with_capacity(
+			data.len(),
+			self.settings.spool_buffer_size / 2
+		);
+		compressed_data_scratch_file.write_all(data).await?;
+
+		Ok(CompressedFile {
+			data: compressed_data_scratch_file,
+			info,
+			squash_time,
+			source_digest: None
+		})
+	}
+
+	/// Returns the Squash Time that should be stored for a file that is being added to the
+	/// ZIP file right now, if any.
+	fn squash_time_for_new_file(&self) -> Option<SystemTime> {
+		(self.settings.store_squash_time && self.settings.reproducible_output_time.is_none())
+			.then(SystemTime::now)
+	}
+
+	/// Generates the local file header for the specified compressed file, which will be stored
+	/// at the specified path.
+	fn generate_local_header<'a>(
+		&self,
+		path: &'a RelativePath<'a>,
+		compressed_file: &CompressedFile
+	) -> Result<LocalFileHeader<'a>, SquashZipError> {
+		let info = &compressed_file.info;
+
+		let mut local_file_header = LocalFileHeader::new(Cow::Borrowed(path));
+		local_file_header.compression_method = if info.deflated {
+			CompressionMethod::Deflate
+		} else {
+			CompressionMethod::Store
+		};
+		local_file_header.crc32 = info.crc32;
+		local_file_header.uncompressed_size = info.uncompressed_size;
+		local_file_header.compressed_size = info.compressed_size;
+		if let Some(squash_time) = compressed_file.squash_time {
 			local_file_header.squash_time =
-				SYSTEM_TIME_SANITIZER.sanitize(&squash_time, &processed_data_crc.to_le_bytes())?;
+				SYSTEM_TIME_SANITIZER.sanitize(&squash_time, &info.crc32.to_le_bytes())?;
+		} else if let Some(reproducible_output_time) = self.settings.reproducible_output_time {
+			local_file_header.squash_time = system_time_to_dos_date_time(reproducible_output_time);
 		}
 
-		Ok((local_file_header, compressed_data_scratch_file))
+		Ok(local_file_header)
 	}
 }
 
 /// Reads the relevant previous ZIP contents to a map, ready to be used to efficiently process
 /// files that were already present in the previous version of the ZIP file.
+///
+/// If `read_squash_times` is `false`, Squash Times are not read, so every returned previous
+/// file has no Squash Time. This is useful to read ZIP files that may not contain them.
 async fn read_previous_zip_contents(
 	mut previous_zip: impl AsyncRead + AsyncSeek + Unpin,
-	obfuscation_engine: &ObfuscationEngine
+	obfuscation_engine: &ObfuscationEngine,
+	read_squash_times: bool
 ) -> Result<AHashMap<RelativePath<'static>, PreviousFile>, PreviousZipParseError> {
 	let mut buffer = [0u8; 52];
 	let record_offset = obfuscation_engine.obfuscating_header_size();
@@ -906,19 +1311,21 @@ async fn read_previous_zip_contents(
 			));
 		}
 
-		// SquashZip either generates no extra fields or a single ZIP64 data field
-		// with an extended local header offset (2 + 2 + 8 = 12 bytes)
-		if extra_field_length != 0 && extra_field_length != 12 {
+		// SquashZip generates at most a ZIP64 data field with an extended local header
+		// offset (2 + 2 + 8 = 12 bytes) and a source digest field (2 + 2 + 64 = 68 bytes)
+		if extra_field_length > 12 + 68 {
 			return Err(PreviousZipParseError::Invalid(
 				"Unexpected extra fields size in CDH"
 			));
 		}
 
-		// Read the fields that will be stored in the map
+		// Read the fields that will be stored in the map. Squash Time decoding errors are
+		// handled later, because they are not fatal for files that have a source digest
 		let crc = obfuscation_engine
 			.deobfuscate_crc32(u32::from_le_bytes(buffer[12..16].try_into().unwrap()));
-		let squash_time = SYSTEM_TIME_SANITIZER
-			.desanitize(buffer[8..12].try_into().unwrap(), &crc.to_le_bytes())?;
+		let sanitized_squash_time: [u8; 4] = buffer[8..12].try_into().unwrap();
+		let squash_time_result = read_squash_times
+			.then(|| SYSTEM_TIME_SANITIZER.desanitize(&sanitized_squash_time, &crc.to_le_bytes()));
 		let compression_method = CompressionMethod::from_compression_method_field(
 			u16::from_le_bytes(buffer[6..8].try_into().unwrap())
 		)?;
@@ -950,24 +1357,63 @@ async fn read_previous_zip_contents(
 			RelativePath::from_inner(String::from_utf8(filename_buf)?)
 		};
 
-		if extra_field_length == 12 && local_file_header_offset == 0xFFFFFFFF {
-			// We maybe have a proper local file header offset in a ZIP64 extended information
-			// extra field. Read it. It's just after the file name
-			previous_zip.read_exact(&mut buffer[..12]).await?;
+		// Read the extra fields, which are just after the file name
+		let mut source_digest = None;
+		let extra_fields = &mut buffer[..extra_field_length as usize];
+		previous_zip.read_exact(extra_fields).await?;
+		let mut extra_fields = &*extra_fields;
+		while !extra_fields.is_empty() {
+			let (tag, data_size) = match extra_fields {
+				[tag_low, tag_high, size_low, size_high, ..] => (
+					u16::from_le_bytes([*tag_low, *tag_high]),
+					u16::from_le_bytes([*size_low, *size_high]) as usize
+				),
+				_ => {
+					return Err(PreviousZipParseError::Invalid(
+						"Truncated extra field header in CDH"
+					));
+				}
+			};
 
-			// Check the ZIP64 field tag to make sure it really is the field we're looking for
-			if buffer[..2] == [0x01, 0x00] {
-				local_file_header_offset = u64::from_le_bytes(buffer[4..12].try_into().unwrap());
-			} else {
-				// This wouldn't be a format error, as the extra fields are just a list of blocks.
-				// However, SquashZip doesn't generate ZIP files with extra fields other than this,
-				// so this definitely means that the ZIP file was modified or corrupted
-				return Err(PreviousZipParseError::Invalid(
-					"Found extra field in CDH that is not a ZIP64 extended information field"
-				));
+			let data = extra_fields
+				.get(4..4 + data_size)
+				.ok_or(PreviousZipParseError::Invalid(
+					"Truncated extra field data in CDH"
+				))?;
+
+			match (tag, data_size) {
+				(CentralDirectoryHeader::ZIP64_EXTRA_FIELD_TAG, 8)
+					if local_file_header_offset == 0xFFFFFFFF =>
+				{
+					// We have a proper local file header offset in a ZIP64 extended information
+					// extra field
+					local_file_header_offset = u64::from_le_bytes(data.try_into().unwrap());
+				}
+				(CentralDirectoryHeader::SOURCE_DIGEST_EXTRA_FIELD_TAG, SourceDigest::SIZE) => {
+					source_digest = Some(SourceDigest::from_bytes(data.try_into().unwrap()));
+				}
+				_ => {
+					// This wouldn't be a format error, as the extra fields are just a list of
+					// blocks. However, SquashZip doesn't generate ZIP files with extra fields
+					// other than these, so this definitely means that the ZIP file was modified
+					// or corrupted
+					return Err(PreviousZipParseError::Invalid(
+						"Found unexpected extra field in CDH"
+					));
+				}
 			}
+
+			extra_fields = &extra_fields[4 + data_size..];
 		}
 
+		// Files whose Squash Time was tampered with or encrypted with a different key can
+		// still be reused if their source digest is known, so ignore errors for them
+		let squash_time = match (squash_time_result, source_digest) {
+			(None, _) | (Some(Err(_)), Some(_)) => None,
+			(Some(Ok(squash_time)), _) => Some(squash_time),
+			(Some(Err(err)), None) => return Err(err.into())
+		};
+
 		local_file_header_offset += record_offset;
 
 		// Assume that current offset is where the next central directory header starts.
@@ -1012,6 +1458,8 @@ async fn read_previous_zip_contents(
 			relative_path,
 			PreviousFile {
 				squash_time,
+				sanitized_squash_time,
+				source_digest,
 				data_offset: local_file_header_offset + 30 + local_header_file_name_length,
 				crc32: crc,
 				compression_method,
@@ -1041,6 +1489,7 @@ fn add_partial_central_directory_header(
 	path: &RelativePath<'_>,
 	local_file_header: &LocalFileHeader<'_>,
 	local_file_header_offset: u64,
+	source_digest: Option<SourceDigest>,
 	listing_circumstances: FileListingCircumstances,
 	central_directory_data: &mut AHashMap<RelativePath<'static>, PartialCentralDirectoryHeader>
 ) -> Result<(), SquashZipError> {
@@ -1053,6 +1502,7 @@ fn add_partial_central_directory_header(
 				crc32: local_file_header.crc32,
 				compressed_size: local_file_header.compressed_size,
 				uncompressed_size: local_file_header.uncompressed_size,
+				source_digest,
 				listing_circumstances
 			});
 
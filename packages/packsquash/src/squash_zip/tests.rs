@@ -1,7 +1,9 @@
-use std::{env, path::PathBuf};
+use std::{env, path::PathBuf, time::Duration};
 
 use tempfile::Builder;
 
+use crate::vfs::{VirtualFileSystem, zip_fs::ZipFilesystem};
+
 use pretty_assertions::assert_eq;
 
 use super::*;
@@ -70,7 +72,8 @@ async fn add_files_finish_and_read_back_test(
 				workaround_old_java_obfuscation_quirks: false,
 				spool_buffer_size: spool_buffer_size.unwrap_or(DEFAULT_SPOOL_BUFFER_SIZE),
 				zip_comment: ZipArchiveCommentString::new("Test comment")
-					.expect("The archive comment is assumed to be valid")
+					.expect("The archive comment is assumed to be valid"),
+				reproducible_output_time: None
 			}
 		)
 		.await
@@ -81,7 +84,7 @@ async fn add_files_finish_and_read_back_test(
 	let file_path = create_temporary_output_file(test_name);
 
 	for i in 0..file_count {
-		squash_zip
+		let stored_file_info = squash_zip
 			.add_file(
 				&RelativePath::new(
 					Path::new("./gimme/gimme"),
@@ -101,6 +104,19 @@ async fn add_files_finish_and_read_back_test(
 			)
 			.await
 			.expect(UNEXPECTED_OPERATION_FAILURE);
+
+		assert_eq!(
+			stored_file_info.uncompressed_size as usize, file_size,
+			"Unexpected uncompressed size for an added file"
+		);
+		assert!(
+			stored_file_info.compressed_size as usize <= file_size,
+			"Compressed data should never be bigger than uncompressed data"
+		);
+		assert!(
+			!skip_compression(i) || !stored_file_info.deflated,
+			"Files that skip compression should be stored as-is"
+		);
 	}
 
 	squash_zip
@@ -119,7 +135,8 @@ async fn add_files_finish_and_read_back_test(
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: spool_buffer_size.unwrap_or(DEFAULT_SPOOL_BUFFER_SIZE),
-			zip_comment: ZipArchiveCommentString::default()
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
 		}
 	)
 	.await
@@ -334,7 +351,8 @@ async fn add_several_finish_then_reuse_and_add_works() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
 		}
 	)
 	.await
@@ -383,7 +401,8 @@ async fn add_several_finish_then_reuse_and_add_works() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
 		}
 	)
 	.await
@@ -414,7 +433,8 @@ async fn several_files_with_same_path_are_handled_properly() {
 			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
 			workaround_old_java_obfuscation_quirks: false,
 			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
-			zip_comment: ZipArchiveCommentString::default()
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
 		}
 	)
 	.await
@@ -438,3 +458,496 @@ async fn several_files_with_same_path_are_handled_properly() {
 	add_file().await.expect(UNEXPECTED_OPERATION_FAILURE);
 	add_file().await.expect_err(UNEXPECTED_OPERATION_FAILURE);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn finish_to_writer_works() {
+	let settings = || SquashZipSettings {
+		zopfli_iterations: 20,
+		store_squash_time: true,
+		enable_obfuscation: false,
+		enable_deduplication: false,
+		enable_size_increasing_obfuscation: false,
+		percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+		workaround_old_java_obfuscation_quirks: false,
+		spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+		zip_comment: ZipArchiveCommentString::default(),
+		reproducible_output_time: None
+	};
+
+	let squash_zip = SquashZip::<File>::new(None, settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	squash_zip
+		.add_file(
+			&RelativePath::from_inner("virtual/visions.bin"),
+			&mut tokio_stream::iter(std::iter::repeat_n(&[b'a'][..], FILE_SIZE)),
+			false,
+			FILE_SIZE,
+			FileListingCircumstances {
+				may_be_read_and_provided_by_mods: false,
+				is_force_included: false
+			}
+		)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	let mut output_zip = Vec::new();
+	let output_zip_digests = squash_zip
+		.finish_to_writer(&mut output_zip)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	assert_eq!(output_zip_digests.size(), output_zip.len() as u64);
+	assert_eq!(
+		output_zip_digests.sha1(),
+		&<[u8; 20]>::from(Sha1::digest(&output_zip))
+	);
+	assert_eq!(
+		output_zip_digests.sha256(),
+		&<[u8; 32]>::from(Sha256::digest(&output_zip))
+	);
+
+	let squash_zip = SquashZip::new(Some(io::Cursor::new(output_zip)), settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	assert_eq!(
+		squash_zip.previous_file_count(),
+		1,
+		"Unexpected number of ZIP files read back"
+	);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn reproducible_output_works() {
+	let reproducible_output_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1577934246);
+	let files = [
+		("pack.mcmeta", b'a', false),
+		("assets/minecraft/sounds/b.ogg", b'b', true),
+		("assets/minecraft/lang/en_us.json", b'c', false),
+		// Duplicate of pack.mcmeta
+		("assets/minecraft/lang/es_es.json", b'a', false)
+	];
+
+	let mut output_zips = Vec::with_capacity(2);
+	for reverse_file_order in [false, true] {
+		let squash_zip = SquashZip::<File>::new(
+			None,
+			SquashZipSettings {
+				zopfli_iterations: 20,
+				store_squash_time: true,
+				enable_obfuscation: false,
+				enable_deduplication: true,
+				enable_size_increasing_obfuscation: false,
+				percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+				workaround_old_java_obfuscation_quirks: false,
+				spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+				zip_comment: ZipArchiveCommentString::default(),
+				reproducible_output_time: Some(reproducible_output_time)
+			}
+		)
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+		let mut files = files.to_vec();
+		if reverse_file_order {
+			files.reverse();
+		}
+
+		for (file_path, file_byte, skip_compression) in files {
+			squash_zip
+				.add_file(
+					&RelativePath::from_inner(file_path),
+					&mut tokio_stream::iter(std::iter::repeat_n(&[file_byte][..], FILE_SIZE)),
+					skip_compression,
+					FILE_SIZE,
+					FileListingCircumstances {
+						may_be_read_and_provided_by_mods: false,
+						is_force_included: false
+					}
+				)
+				.await
+				.expect(UNEXPECTED_OPERATION_FAILURE);
+		}
+
+		let mut output_zip = Vec::new();
+		squash_zip
+			.finish_to_writer(&mut output_zip)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+
+		output_zips.push(output_zip);
+	}
+
+	assert_eq!(
+		output_zips[0], output_zips[1],
+		"The generated ZIP files should be identical"
+	);
+
+	// The generated ZIP file should be valid, with the expected modification times
+	let zip_filesystem = ZipFilesystem::new(io::Cursor::new(output_zips.pop().unwrap()))
+		.expect("The generated ZIP file should be valid");
+
+	for (file_path, file_byte, _) in files {
+		let mut file = zip_filesystem
+			.open(file_path)
+			.expect("The generated ZIP file should contain every added file");
+
+		let mut file_data = Vec::new();
+		Read::read_to_end(&mut file.file_read, &mut file_data).expect(UNEXPECTED_IO_FAILURE);
+
+		assert_eq!(file_data, [file_byte].repeat(FILE_SIZE));
+		assert_eq!(
+			file.metadata.modification_time,
+			Some(reproducible_output_time)
+		);
+	}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn add_precompressed_file_works() {
+	let settings = || SquashZipSettings {
+		zopfli_iterations: 20,
+		store_squash_time: true,
+		enable_obfuscation: false,
+		enable_deduplication: true,
+		enable_size_increasing_obfuscation: false,
+		percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+		workaround_old_java_obfuscation_quirks: false,
+		spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+		zip_comment: ZipArchiveCommentString::default(),
+		reproducible_output_time: None
+	};
+
+	// Compress a file with a SquashZip instance, and keep its compressed data around
+	let mut compressed_file = SquashZip::<File>::new(None, settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE)
+		.compress_file(
+			&mut tokio_stream::iter(std::iter::repeat_n(&[b'a'][..], FILE_SIZE)),
+			false,
+			FILE_SIZE
+		)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	let compressed_file_info = compressed_file.info();
+	assert!(
+		compressed_file_info.deflated,
+		"Repetitive data should be compressed"
+	);
+
+	let mut compressed_data = Vec::new();
+	compressed_file
+		.read_data_to_end(&mut compressed_data)
+		.await
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	// Now add that compressed data to a ZIP file generated by another instance
+	let squash_zip = SquashZip::<File>::new(None, settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	assert!(
+		squash_zip
+			.precompressed_file(compressed_file_info, &compressed_data[1..])
+			.await
+			.is_err(),
+		"Data with an unexpected size should be rejected"
+	);
+
+	let precompressed_file = squash_zip
+		.precompressed_file(compressed_file_info, &compressed_data)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	assert_eq!(
+		squash_zip
+			.add_compressed_file(
+				&RelativePath::from_inner("virtual/visions.bin"),
+				precompressed_file,
+				FileListingCircumstances {
+					may_be_read_and_provided_by_mods: false,
+					is_force_included: false
+				}
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE),
+		compressed_file_info
+	);
+
+	let mut output_zip = Vec::new();
+	squash_zip
+		.finish_to_writer(&mut output_zip)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	let mut file = ZipFilesystem::new(io::Cursor::new(output_zip))
+		.expect("The generated ZIP file should be valid")
+		.open("virtual/visions.bin")
+		.expect("The generated ZIP file should contain the added file");
+
+	let mut file_data = Vec::new();
+	Read::read_to_end(&mut file.file_read, &mut file_data).expect(UNEXPECTED_IO_FAILURE);
+
+	assert_eq!(file_data, [b'a'].repeat(FILE_SIZE));
+}
+
+#[tokio::test]
+async fn source_digests_are_read_back() {
+	let settings = || SquashZipSettings {
+		zopfli_iterations: 20,
+		store_squash_time: true,
+		enable_obfuscation: false,
+		enable_deduplication: false,
+		enable_size_increasing_obfuscation: false,
+		percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+		workaround_old_java_obfuscation_quirks: false,
+		spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+		zip_comment: ZipArchiveCommentString::default(),
+		reproducible_output_time: None
+	};
+	let listing_circumstances = FileListingCircumstances {
+		may_be_read_and_provided_by_mods: false,
+		is_force_included: false
+	};
+	let source_digest = SourceDigest {
+		content_hash: [1; 32],
+		options_fingerprint: [2; 32]
+	};
+
+	let squash_zip = SquashZip::<File>::new(None, settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	for (file, source_digest) in [
+		("virtual/digested.bin", Some(source_digest)),
+		("virtual/undigested.bin", None)
+	] {
+		let mut compressed_file = squash_zip
+			.compress_file(
+				&mut tokio_stream::iter(std::iter::repeat_n(&[b'a'][..], FILE_SIZE)),
+				false,
+				FILE_SIZE
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+
+		if let Some(source_digest) = source_digest {
+			compressed_file.set_source_digest(source_digest);
+		}
+
+		squash_zip
+			.add_compressed_file(
+				&RelativePath::from_inner(file),
+				compressed_file,
+				listing_circumstances
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+	}
+
+	let mut output_zip = Vec::new();
+	squash_zip
+		.finish_to_writer(&mut output_zip)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	// Other ZIP file readers should not have any problem with the source digest extra field
+	ZipFilesystem::new(io::Cursor::new(&output_zip))
+		.expect("The generated ZIP file should be valid")
+		.open("virtual/digested.bin")
+		.expect("The generated ZIP file should contain the added file");
+
+	let squash_zip = SquashZip::new(Some(io::Cursor::new(output_zip)), settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	assert_eq!(
+		squash_zip.file_source_digest(&RelativePath::from_inner("virtual/digested.bin")),
+		Some(source_digest)
+	);
+	assert_eq!(
+		squash_zip.file_source_digest(&RelativePath::from_inner("virtual/undigested.bin")),
+		None
+	);
+}
+
+#[tokio::test]
+async fn verification_detects_corrupt_files() {
+	for enable_obfuscation in [false, true] {
+		let settings = || SquashZipSettings {
+			zopfli_iterations: 20,
+			store_squash_time: true,
+			enable_obfuscation,
+			enable_deduplication: false,
+			enable_size_increasing_obfuscation: enable_obfuscation,
+			percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+			workaround_old_java_obfuscation_quirks: false,
+			spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+			zip_comment: ZipArchiveCommentString::default(),
+			reproducible_output_time: None
+		};
+
+		let squash_zip = SquashZip::<File>::new(None, settings())
+			.await
+			.map_err(|(err, _)| err)
+			.expect(INSTANTIATION_FAILURE);
+
+		for (file, file_byte, skip_compression) in [
+			("virtual/deflated.bin", b'a', false),
+			("virtual/stored.bin", b'b', true)
+		] {
+			squash_zip
+				.add_file(
+					&RelativePath::from_inner(file),
+					&mut tokio_stream::iter(std::iter::repeat_n(&[file_byte][..], FILE_SIZE)),
+					skip_compression,
+					FILE_SIZE,
+					FileListingCircumstances {
+						may_be_read_and_provided_by_mods: false,
+						is_force_included: false
+					}
+				)
+				.await
+				.expect(UNEXPECTED_OPERATION_FAILURE);
+		}
+
+		let mut output_zip = Vec::new();
+		squash_zip
+			.finish_to_writer(&mut output_zip)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+
+		let verification_report = verify_zip(io::Cursor::new(&output_zip), &settings())
+			.await
+			.expect("The generated ZIP file should be parseable");
+
+		assert_eq!(verification_report.verified_file_count(), 2);
+		assert!(
+			verification_report.problems().is_empty(),
+			"Unexpected problems found in a valid ZIP file: {:?}",
+			verification_report.problems()
+		);
+
+		// Flip a byte of the stored file data, which can be easily told apart
+		let stored_data_offset = output_zip
+			.windows(FILE_SIZE)
+			.position(|window| window.iter().all(|byte| *byte == b'b'))
+			.expect("The stored file data should be in the ZIP file");
+		output_zip[stored_data_offset + FILE_SIZE / 2] = b'c';
+
+		let verification_report = verify_zip(io::Cursor::new(&output_zip), &settings())
+			.await
+			.expect("The corrupted ZIP file should still be parseable");
+
+		assert_eq!(verification_report.verified_file_count(), 2);
+		assert!(
+			matches!(
+				verification_report.problems(),
+				[ZipVerificationProblem::CrcMismatch { path, .. }] if path.as_str() == "virtual/stored.bin"
+			),
+			"Unexpected problems found in a corrupted ZIP file: {:?}",
+			verification_report.problems()
+		);
+	}
+}
+
+#[tokio::test]
+async fn inspection_lists_entries_and_parse_errors() {
+	let settings = || SquashZipSettings {
+		zopfli_iterations: 20,
+		store_squash_time: true,
+		enable_obfuscation: false,
+		enable_deduplication: true,
+		enable_size_increasing_obfuscation: false,
+		percentage_of_records_tuned_for_obfuscation_discretion: 0.try_into().unwrap(),
+		workaround_old_java_obfuscation_quirks: false,
+		spool_buffer_size: DEFAULT_SPOOL_BUFFER_SIZE,
+		zip_comment: ZipArchiveCommentString::default(),
+		reproducible_output_time: None
+	};
+
+	let squash_zip = SquashZip::<File>::new(None, settings())
+		.await
+		.map_err(|(err, _)| err)
+		.expect(INSTANTIATION_FAILURE);
+
+	for (file, file_byte) in [
+		("virtual/a.bin", b'a'),
+		("virtual/b.bin", b'b'),
+		("virtual/duplicate_a.bin", b'a')
+	] {
+		squash_zip
+			.add_file(
+				&RelativePath::from_inner(file),
+				&mut tokio_stream::iter(std::iter::repeat_n(&[file_byte][..], FILE_SIZE)),
+				false,
+				FILE_SIZE,
+				FileListingCircumstances {
+					may_be_read_and_provided_by_mods: false,
+					is_force_included: false
+				}
+			)
+			.await
+			.expect(UNEXPECTED_OPERATION_FAILURE);
+	}
+
+	let mut output_zip = Vec::new();
+	squash_zip
+		.finish_to_writer(&mut output_zip)
+		.await
+		.expect(UNEXPECTED_OPERATION_FAILURE);
+
+	let inspection_report = inspect_zip(io::Cursor::new(&output_zip), &settings()).await;
+
+	assert!(
+		inspection_report.previous_zip_parse_error().is_none(),
+		"Unexpected previous ZIP parse error: {:?}",
+		inspection_report.previous_zip_parse_error()
+	);
+	assert_eq!(
+		inspection_report
+			.entries()
+			.iter()
+			.map(|entry| (
+				entry.path().as_str(),
+				entry.deduplicated(),
+				entry.squash_time().is_ok()
+			))
+			.collect::<Vec<_>>(),
+		[
+			("virtual/a.bin", true, true),
+			("virtual/duplicate_a.bin", true, true),
+			("virtual/b.bin", false, true)
+		]
+	);
+
+	// Add a file comment to the first central directory header, which SquashZip never
+	// generates. Its length field is 32 bytes after the header signature
+	let central_directory_header_offset = output_zip
+		.windows(4)
+		.position(|window| window == CentralDirectoryHeader::SIGNATURE)
+		.expect("The ZIP file should have a central directory header");
+	output_zip[central_directory_header_offset + 32] = 1;
+
+	let inspection_report = inspect_zip(io::Cursor::new(&output_zip), &settings()).await;
+
+	assert!(
+		matches!(
+			inspection_report.previous_zip_parse_error(),
+			Some(PreviousZipParseError::Invalid(_))
+		),
+		"Unexpected previous ZIP parse error: {:?}",
+		inspection_report.previous_zip_parse_error()
+	);
+	assert!(inspection_report.entries().is_empty());
+}
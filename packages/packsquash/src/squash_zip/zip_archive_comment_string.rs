@@ -1,7 +1,7 @@
 use std::{borrow::Cow, ops::Deref, sync::Arc};
 
 use memchr::memmem;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
 
 use super::zip_file_record::EndOfCentralDirectory;
@@ -91,6 +91,12 @@ impl Deref for ZipArchiveCommentString {
 	}
 }
 
+impl Serialize for ZipArchiveCommentString {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self)
+	}
+}
+
 impl TryFrom<Cow<'_, str>> for ZipArchiveCommentString {
 	type Error = InvalidFileCommentStringError;
 
@@ -3,7 +3,8 @@
 use std::{
 	borrow::Cow,
 	cmp,
-	io::{self, Error, IoSlice, SeekFrom}
+	io::{self, Error, IoSlice, SeekFrom},
+	time::SystemTime
 };
 
 use enumset::{EnumSet, EnumSetType};
@@ -134,6 +135,45 @@ impl CompressionMethod {
 	}
 }
 
+/// Converts a system time to the MS-DOS date and time format used by the last modification time
+/// and date fields of ZIP file records, laid out in a Squash Time compatible way. Times are
+/// converted in UTC, and clamped to the range that can be represented in this format, which
+/// spans from 1980 to 2107 with a precision of two seconds.
+pub(super) fn system_time_to_dos_date_time(time: SystemTime) -> [u8; 4] {
+	// Seconds since the Unix epoch of 1980-01-01 00:00:00 and 2107-12-31 23:59:58
+	let seconds_since_epoch = time
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.map_or(0, |duration| duration.as_secs())
+		.clamp(315532800, 4354819198);
+
+	let days_since_epoch = (seconds_since_epoch / 86400) as u32;
+	let second_of_day = (seconds_since_epoch % 86400) as u32;
+
+	// Proleptic Gregorian calendar date for some days since the Unix epoch, adapted from
+	// http://howardhinnant.github.io/date_algorithms.html#civil_from_days. Dates before
+	// the Unix epoch are not handled, because they were clamped out
+	let days_since_era_start = days_since_epoch + 719468;
+	let era = days_since_era_start / 146097;
+	let day_of_era = days_since_era_start - era * 146097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = era * 400 + year_of_era + (month <= 2) as u32;
+
+	let dos_date = ((year - 1980) << 9) | (month << 5) | day;
+	let dos_time =
+		((second_of_day / 3600) << 11) | ((second_of_day / 60 % 60) << 5) | (second_of_day % 60 / 2);
+
+	((dos_date << 16) | dos_time).to_le_bytes()
+}
+
 /// Computes the general purpose bit flag for this ZIP file record from the file name
 /// it contains, used to specify its UTF-8 encoding.
 fn get_general_purpose_bit_flag(file_name: &str) -> u16 {
@@ -253,6 +293,7 @@ pub(super) struct CentralDirectoryHeader<'a> {
 	pub uncompressed_size: u32,
 	pub local_header_disk_number: u16,
 	pub local_header_offset: u64,
+	pub source_digest: Option<[u8; 64]>,
 	pub file_name: RelativePath<'a>,
 	pub spoof_version_made_by: bool
 }
@@ -262,6 +303,16 @@ impl CentralDirectoryHeader<'_> {
 	/// the beginning of a central directory header record.
 	pub(super) const SIGNATURE: [u8; 4] = 0x02_01_4B_50_u32.to_le_bytes();
 
+	/// The tag of the ZIP64 extended information extra field, defined in section 4.5.3 of
+	/// the ZIP file specification.
+	pub(super) const ZIP64_EXTRA_FIELD_TAG: u16 = 0x0001;
+
+	/// The tag of the extra field that contains the source digest of a file, which is
+	/// specific to SquashZip. Its value spells "PS" when stored in little-endian order, and
+	/// does not clash with any tag reserved or registered in section 4.6 of the ZIP file
+	/// specification.
+	pub(super) const SOURCE_DIGEST_EXTRA_FIELD_TAG: u16 = 0x5350;
+
 	/// Returns whether this central directory header record requires ZIP64 extensions
 	/// to be stored correctly.
 	const fn requires_zip64_extensions(&self) -> bool {
@@ -279,12 +330,14 @@ impl CentralDirectoryHeader<'_> {
 	/// Calculates the total length of the extra fields that should be appended to this
 	/// central directory header. If extra fields are not needed, this returns zero.
 	const fn compute_extra_field_length(&self) -> u16 {
-		// Currently, PackSquash only uses the ZIP64 extended information extra field.
-		// That extra field length is the result of the following formula:
+		// Currently, PackSquash only uses the ZIP64 extended information and source digest
+		// extra fields. The length of every extra field is the result of the following formula:
 		// Header size (2 byte ID/tag + 2 byte data size) + data size
-		// Where data size = local header offset size (8 bytes)
+		// Where data size = local header offset size (8 bytes) for the ZIP64 extended
+		// information extra field, and source digest size (64 bytes) for the other
 		4 * self.requires_zip64_extensions() as u16
 			+ 8 * self.local_header_offset_requires_zip64_extensions() as u16
+			+ (4 + 64) * self.source_digest.is_some() as u16
 	}
 
 	/// Writes this ZIP file record to the specified output ZIP file. For top performance,
@@ -352,9 +405,9 @@ impl CentralDirectoryHeader<'_> {
 
 			output_zip
 				.write_all_vectored(&mut [
-					IoSlice::new(&0x0001_u16.to_le_bytes()), // Extra field tag/ID
+					IoSlice::new(&Self::ZIP64_EXTRA_FIELD_TAG.to_le_bytes()), // Extra field tag/ID
 					// Data size (does not include the 4 byte long header)
-					IoSlice::new(&(extra_field_length - 4).to_le_bytes()),
+					IoSlice::new(&(8 * local_header_offset_requires_zip64 as u16).to_le_bytes()),
 					IoSlice::new(if local_header_offset_requires_zip64 {
 						&local_header_offset_bytes
 					} else {
@@ -364,6 +417,17 @@ impl CentralDirectoryHeader<'_> {
 				.await?;
 		}
 
+		// Source digest extra field
+		if let Some(source_digest) = &self.source_digest {
+			output_zip
+				.write_all_vectored(&mut [
+					IoSlice::new(&Self::SOURCE_DIGEST_EXTRA_FIELD_TAG.to_le_bytes()),
+					IoSlice::new(&(source_digest.len() as u16).to_le_bytes()),
+					IoSlice::new(source_digest)
+				])
+				.await?;
+		}
+
 		Ok(())
 	}
 }
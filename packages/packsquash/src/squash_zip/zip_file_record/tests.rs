@@ -60,6 +60,7 @@ async fn central_directory_works_test(use_zip64_extensions: bool) {
 		uncompressed_size: 3,
 		local_header_disk_number: 4,
 		local_header_offset: local_file_header_offset,
+		source_digest: None,
 		file_name: RelativePath::from_inner(FILE_NAME),
 		spoof_version_made_by: false
 	};
@@ -661,3 +662,30 @@ async fn end_of_central_directory_location_with_partial_reads_works() {
 		Some(145)
 	);
 }
+
+#[test]
+fn system_time_to_dos_date_time_works() {
+	for (seconds_since_epoch, expected_dos_date, expected_dos_time) in [
+		// Times before 1980-01-01 00:00:00 are clamped to that time
+		(0, 1u32 << 5 | 1, 0u32),
+		// 2000-02-29 12:00:00
+		(951825600, 20 << 9 | 2 << 5 | 29, 12 << 11),
+		// 2020-01-02 03:04:06
+		(1577934246, 40 << 9 | 1 << 5 | 2, 3 << 11 | 4 << 5 | 3),
+		// Times after 2107-12-31 23:59:58 are clamped to that time
+		(5000000000, 127 << 9 | 12 << 5 | 31, 23 << 11 | 59 << 5 | 29)
+	] {
+		assert_eq!(
+			system_time_to_dos_date_time(
+				SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds_since_epoch)
+			),
+			((expected_dos_date << 16) | expected_dos_time).to_le_bytes(),
+			"Unexpected MS-DOS date and time for {seconds_since_epoch} seconds since the Unix epoch"
+		);
+	}
+
+	assert_eq!(
+		system_time_to_dos_date_time(SystemTime::UNIX_EPOCH),
+		DUMMY_SQUASH_TIME
+	);
+}
@@ -0,0 +1,165 @@
+//! Contains the logic to list the files of a ZIP file generated by SquashZip, along with the
+//! metadata PackSquash stores about them, for diagnostic purposes.
+
+use std::{io::SeekFrom, time::SystemTime};
+
+use ahash::AHashMap;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt};
+
+use super::{
+	ObfuscationEngine, PreviousZipParseError, SYSTEM_TIME_SANITIZER, SquashZipSettings,
+	read_previous_zip_contents, system_time_sanitizer::SystemTimeSanitizationError,
+	zip_file_record::CompressionMethod
+};
+use crate::{PackFileCompressionMethod, RelativePath};
+
+/// The result of inspecting a ZIP file generated by SquashZip with [`inspect_zip`].
+#[derive(Debug)]
+pub struct ZipInspectionReport {
+	entries: Vec<ZipEntryInspection>,
+	previous_zip_parse_error: Option<PreviousZipParseError>
+}
+
+impl ZipInspectionReport {
+	/// Returns the entries of the ZIP file, sorted by the offset of their data, and then
+	/// by path. This is empty if the ZIP file structure could not be parsed at all.
+	pub fn entries(&self) -> &[ZipEntryInspection] {
+		&self.entries
+	}
+
+	/// Returns the error that would make PackSquash reject this ZIP file when reusing it as
+	/// a previous ZIP file, if any. This is the error reported in
+	/// [`PackSquasherWarning::UnusablePreviousZip`](crate::PackSquasherWarning::UnusablePreviousZip)
+	/// warnings.
+	pub fn previous_zip_parse_error(&self) -> Option<&PreviousZipParseError> {
+		self.previous_zip_parse_error.as_ref()
+	}
+}
+
+/// The metadata of an entry of a ZIP file generated by SquashZip.
+#[derive(Debug)]
+pub struct ZipEntryInspection {
+	path: RelativePath<'static>,
+	compression_method: PackFileCompressionMethod,
+	crc32: u32,
+	compressed_size: u32,
+	uncompressed_size: u32,
+	data_offset: u64,
+	deduplicated: bool,
+	has_source_digest: bool,
+	squash_time: Result<SystemTime, SystemTimeSanitizationError>
+}
+
+impl ZipEntryInspection {
+	/// Returns the path of this entry.
+	pub const fn path(&self) -> &RelativePath<'static> {
+		&self.path
+	}
+
+	/// Returns the compression method used to store the data of this entry.
+	pub const fn compression_method(&self) -> PackFileCompressionMethod {
+		self.compression_method
+	}
+
+	/// Returns the CRC-32 of the data of this entry, after deobfuscating it.
+	pub const fn crc32(&self) -> u32 {
+		self.crc32
+	}
+
+	/// Returns the size of the data of this entry as stored in the ZIP file.
+	pub const fn compressed_size(&self) -> u32 {
+		self.compressed_size
+	}
+
+	/// Returns the uncompressed size of the data of this entry, as stored in the central
+	/// directory. This size is meaningless for obfuscated ZIP files.
+	pub const fn uncompressed_size(&self) -> u32 {
+		self.uncompressed_size
+	}
+
+	/// Returns the offset of the data of this entry from the start of the ZIP file.
+	pub const fn data_offset(&self) -> u64 {
+		self.data_offset
+	}
+
+	/// Returns whether the data of this entry is shared with other entries, due to
+	/// deduplication.
+	pub const fn deduplicated(&self) -> bool {
+		self.deduplicated
+	}
+
+	/// Returns whether a digest of the source of this entry is stored in the ZIP file, which
+	/// allows reusing it by content hash.
+	pub const fn has_source_digest(&self) -> bool {
+		self.has_source_digest
+	}
+
+	/// Returns the Squash Time of this entry, which tells when it was processed, or the error
+	/// that happened while decrypting it. Errors are expected for ZIP files generated without
+	/// storing Squash Times, or in a different system or PackSquash build.
+	pub const fn squash_time(&self) -> Result<&SystemTime, &SystemTimeSanitizationError> {
+		self.squash_time.as_ref()
+	}
+}
+
+/// Inspects the specified ZIP file, which was generated by SquashZip with the specified
+/// settings, listing its entries and pinning down why it would not be reused as a previous
+/// ZIP file, if that is the case.
+///
+/// Unlike when reading a previous ZIP file, errors that happen while decrypting the Squash
+/// Time of an entry do not prevent listing the rest of entries.
+pub async fn inspect_zip(
+	mut zip: impl AsyncRead + AsyncSeek + Unpin,
+	settings: &SquashZipSettings
+) -> ZipInspectionReport {
+	let obfuscation_engine = ObfuscationEngine::from_squash_zip_settings(settings);
+
+	// First, parse the ZIP file in the same way it is parsed when reusing it, to get the
+	// error that would happen in that case. Then, parse it again ignoring Squash Times, to
+	// list as many entries as possible
+	let previous_zip_parse_error = read_previous_zip_contents(&mut zip, &obfuscation_engine, true)
+		.await
+		.err();
+
+	let files = match zip.seek(SeekFrom::Start(0)).await {
+		Ok(_) => read_previous_zip_contents(&mut zip, &obfuscation_engine, false)
+			.await
+			.unwrap_or_default(),
+		Err(_) => AHashMap::new()
+	};
+
+	let mut data_offset_counts = AHashMap::<u64, usize>::with_capacity(files.len());
+	for file in files.values() {
+		*data_offset_counts.entry(file.data_offset).or_default() += 1;
+	}
+
+	let mut entries = files
+		.into_iter()
+		.map(|(path, file)| ZipEntryInspection {
+			compression_method: match file.compression_method {
+				CompressionMethod::Store => PackFileCompressionMethod::Store,
+				CompressionMethod::Deflate => PackFileCompressionMethod::Deflate
+			},
+			crc32: file.crc32,
+			compressed_size: file.compressed_size,
+			uncompressed_size: file.uncompressed_size,
+			data_offset: file.data_offset,
+			deduplicated: data_offset_counts[&file.data_offset] > 1,
+			has_source_digest: file.source_digest.is_some(),
+			squash_time: SYSTEM_TIME_SANITIZER
+				.desanitize(&file.sanitized_squash_time, &file.crc32.to_le_bytes()),
+			path
+		})
+		.collect::<Vec<_>>();
+
+	entries.sort_unstable_by(|a, b| {
+		a.data_offset
+			.cmp(&b.data_offset)
+			.then_with(|| a.path.as_str().cmp(b.path.as_str()))
+	});
+
+	ZipInspectionReport {
+		entries,
+		previous_zip_parse_error
+	}
+}
@@ -0,0 +1,278 @@
+//! Contains the logic to verify that the files of a ZIP file generated by SquashZip can be
+//! read back as expected, despite any obfuscation applied to its records.
+
+use std::io::{self, SeekFrom};
+
+use flate2::{Decompress, FlushDecompress, Status};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use super::{
+	ObfuscationEngine, PreviousFile, SquashZipError, SquashZipSettings, read_previous_zip_contents,
+	zip_file_record::CompressionMethod
+};
+use crate::RelativePath;
+
+/// The size of the buffers used to read and inflate file data.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The result of verifying a ZIP file generated by SquashZip with [`verify_zip`].
+#[derive(Debug)]
+pub struct ZipVerificationReport {
+	verified_file_count: usize,
+	problems: Vec<ZipVerificationProblem>
+}
+
+impl ZipVerificationReport {
+	/// Returns the number of files in the ZIP file that were verified.
+	pub fn verified_file_count(&self) -> usize {
+		self.verified_file_count
+	}
+
+	/// Returns the problems found in the files of the ZIP file, sorted by file path. If
+	/// this is empty, every file can be read back as expected.
+	pub fn problems(&self) -> &[ZipVerificationProblem] {
+		&self.problems
+	}
+}
+
+/// A problem found in a file of a ZIP file generated by SquashZip, which would cause ZIP
+/// file readers, such as Minecraft's, to read its data incorrectly or not at all.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ZipVerificationProblem {
+	/// The CRC-32 of the file data does not match the one stored in the ZIP file.
+	#[error("{}: the CRC-32 of the data is {actual:08x}, but {expected:08x} was expected", .path.as_str())]
+	CrcMismatch {
+		/// The path of the file.
+		path: RelativePath<'static>,
+		/// The CRC-32 stored in the ZIP file, after deobfuscating it.
+		expected: u32,
+		/// The CRC-32 of the file data.
+		actual: u32
+	},
+	/// The size of the file data does not match the one stored in the ZIP file.
+	#[error("{}: the data is {actual} bytes long, but {expected} bytes were expected", .path.as_str())]
+	UncompressedSizeMismatch {
+		/// The path of the file.
+		path: RelativePath<'static>,
+		/// The size stored in the ZIP file.
+		expected: u32,
+		/// The size of the file data.
+		actual: u64
+	},
+	/// The size of the compressed file data does not match the one stored in the ZIP file.
+	#[error(
+		"{}: the compressed data is {actual} bytes long, but {expected} bytes were expected",
+		.path.as_str()
+	)]
+	CompressedSizeMismatch {
+		/// The path of the file.
+		path: RelativePath<'static>,
+		/// The compressed size stored in the ZIP file.
+		expected: u32,
+		/// The size of the compressed file data.
+		actual: u64
+	},
+	/// The compressed file data could not be decompressed.
+	#[error("{}: the compressed data is corrupt: {reason}", .path.as_str())]
+	CorruptData {
+		/// The path of the file.
+		path: RelativePath<'static>,
+		/// A description of why the data could not be decompressed.
+		reason: String
+	}
+}
+
+impl ZipVerificationProblem {
+	/// Returns the path of the file this problem was found in.
+	pub fn path(&self) -> &RelativePath<'static> {
+		match self {
+			Self::CrcMismatch { path, .. }
+			| Self::UncompressedSizeMismatch { path, .. }
+			| Self::CompressedSizeMismatch { path, .. }
+			| Self::CorruptData { path, .. } => path
+		}
+	}
+}
+
+/// Verifies that every file of the specified ZIP file, which was generated by SquashZip with
+/// the specified settings, can be read back as expected.
+///
+/// The ZIP file structure is parsed in the same way previous ZIP files are, and deobfuscated
+/// as needed. Then, the data of every file is read as ZIP file readers that rely on the central
+/// directory do, such as Minecraft's, and inflated if needed. Its CRC-32 and sizes are checked
+/// against the central directory, except for uncompressed sizes of obfuscated ZIP files, because
+/// they are purposefully meaningless.
+///
+/// Errors are returned if the ZIP file structure can't be parsed or an I/O error happens. Problems
+/// with the data of particular files are returned in the verification report instead.
+pub async fn verify_zip(
+	mut zip: impl AsyncRead + AsyncSeek + Unpin,
+	settings: &SquashZipSettings
+) -> Result<ZipVerificationReport, SquashZipError> {
+	let obfuscation_engine = ObfuscationEngine::from_squash_zip_settings(settings);
+
+	let mut files = read_previous_zip_contents(&mut zip, &obfuscation_engine, false)
+		.await?
+		.into_iter()
+		.collect::<Vec<_>>();
+
+	// Read files in the order their data is stored, to avoid seeking back and forth
+	files.sort_unstable_by_key(|(_, file)| file.data_offset);
+
+	let mut input_buffer = vec![0; BUFFER_SIZE];
+	let mut output_buffer = vec![0; BUFFER_SIZE];
+	let mut problems = Vec::new();
+	for (path, file) in &files {
+		zip.seek(SeekFrom::Start(file.data_offset)).await?;
+
+		problems.extend(
+			verify_file(
+				path,
+				file,
+				(&mut zip).take(file.compressed_size as u64),
+				!settings.enable_obfuscation,
+				&mut input_buffer,
+				&mut output_buffer
+			)
+			.await?
+		);
+	}
+
+	problems.sort_by(|a, b| a.path().as_str().cmp(b.path().as_str()));
+
+	Ok(ZipVerificationReport {
+		verified_file_count: files.len(),
+		problems
+	})
+}
+
+/// Verifies the data of the specified file, read from the specified reader, returning the
+/// problem found with it, if any.
+async fn verify_file(
+	path: &RelativePath<'static>,
+	file: &PreviousFile,
+	mut data: impl AsyncRead + Unpin,
+	check_uncompressed_size: bool,
+	input_buffer: &mut [u8],
+	output_buffer: &mut [u8]
+) -> Result<Option<ZipVerificationProblem>, io::Error> {
+	let mut crc32_hasher = crc32fast::Hasher::new();
+	let compressed_size;
+	let uncompressed_size;
+
+	match file.compression_method {
+		CompressionMethod::Store => {
+			let mut read_bytes = 0;
+			loop {
+				let chunk_size = data.read(input_buffer).await?;
+				if chunk_size == 0 {
+					break;
+				}
+
+				crc32_hasher.update(&input_buffer[..chunk_size]);
+				read_bytes += chunk_size as u64;
+			}
+
+			compressed_size = read_bytes;
+			uncompressed_size = read_bytes;
+		}
+		CompressionMethod::Deflate => {
+			let mut decompressor = Decompress::new(false);
+			let mut input_start = 0;
+			let mut input_end = 0;
+			let mut input_exhausted = false;
+
+			loop {
+				if input_start == input_end && !input_exhausted {
+					input_start = 0;
+					input_end = data.read(input_buffer).await?;
+					input_exhausted = input_end == 0;
+				}
+
+				let (total_in, total_out) = (decompressor.total_in(), decompressor.total_out());
+				let status = match decompressor.decompress(
+					&input_buffer[input_start..input_end],
+					output_buffer,
+					FlushDecompress::None
+				) {
+					Ok(status) => status,
+					Err(err) => {
+						return Ok(Some(ZipVerificationProblem::CorruptData {
+							path: path.clone(),
+							reason: err.to_string()
+						}));
+					}
+				};
+
+				let consumed_bytes = (decompressor.total_in() - total_in) as usize;
+				let produced_bytes = (decompressor.total_out() - total_out) as usize;
+				input_start += consumed_bytes;
+				crc32_hasher.update(&output_buffer[..produced_bytes]);
+
+				if status == Status::StreamEnd {
+					break;
+				}
+
+				if input_exhausted && consumed_bytes == 0 && produced_bytes == 0 {
+					return Ok(Some(ZipVerificationProblem::CorruptData {
+						path: path.clone(),
+						reason: "the DEFLATE stream is truncated".into()
+					}));
+				}
+			}
+
+			// Any data after the end of the DEFLATE stream is not part of it, so the stored
+			// compressed size is wrong
+			let mut trailing_bytes = (input_end - input_start) as u64;
+			loop {
+				let chunk_size = data.read(input_buffer).await?;
+				if chunk_size == 0 {
+					break;
+				}
+
+				trailing_bytes += chunk_size as u64;
+			}
+
+			compressed_size = decompressor.total_in() + trailing_bytes;
+			uncompressed_size = decompressor.total_out();
+
+			if trailing_bytes > 0 {
+				return Ok(Some(ZipVerificationProblem::CompressedSizeMismatch {
+					path: path.clone(),
+					expected: file.compressed_size,
+					actual: decompressor.total_in()
+				}));
+			}
+		}
+	}
+
+	// The data reader is limited to the expected compressed size, so it can only be shorter
+	if compressed_size != file.compressed_size as u64 {
+		return Ok(Some(ZipVerificationProblem::CompressedSizeMismatch {
+			path: path.clone(),
+			expected: file.compressed_size,
+			actual: compressed_size
+		}));
+	}
+
+	let crc32 = crc32_hasher.finalize();
+	if crc32 != file.crc32 {
+		return Ok(Some(ZipVerificationProblem::CrcMismatch {
+			path: path.clone(),
+			expected: file.crc32,
+			actual: crc32
+		}));
+	}
+
+	if check_uncompressed_size && uncompressed_size != file.uncompressed_size as u64 {
+		return Ok(Some(ZipVerificationProblem::UncompressedSizeMismatch {
+			path: path.clone(),
+			expected: file.uncompressed_size,
+			actual: uncompressed_size
+		}));
+	}
+
+	Ok(None)
+}
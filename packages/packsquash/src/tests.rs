@@ -0,0 +1,91 @@
+use std::{fs, path::PathBuf, thread};
+
+use indexmap::IndexMap;
+use pretty_assertions::assert_eq;
+use tokio::sync::mpsc;
+
+use crate::config::{GlobalOptions, SquashOptions};
+use crate::vfs::memory_fs::MemoryFilesystem;
+
+use super::*;
+
+static UNEXPECTED_IO_FAILURE: &str = "I/O operations are assumed not to fail during tests";
+
+/// Contents of a previously generated output ZIP file that a squash operation must not touch.
+static PREVIOUS_OUTPUT_ZIP: &[u8] = b"Previous output ZIP file";
+
+fn example_memory_filesystem() -> MemoryFilesystem {
+	let mut vfs = MemoryFilesystem::new();
+
+	vfs.add_file(
+		"pack.mcmeta",
+		&br#"{ "pack": { "pack_format": 7, "description": "Test pack" } }"#[..],
+		None
+	)
+	.expect(UNEXPECTED_IO_FAILURE);
+
+	for i in 0..16 {
+		vfs.add_file(
+			format!("assets/minecraft/models/block/model_{i}.json"),
+			&br#"{ "parent": "block/cube_all" }"#[..],
+			None
+		)
+		.expect(UNEXPECTED_IO_FAILURE);
+	}
+
+	vfs
+}
+
+#[test]
+fn cancelled_squash_operations_leave_the_output_zip_file_untouched() {
+	let output_directory = tempfile::tempdir().expect(UNEXPECTED_IO_FAILURE);
+	let output_file_path = output_directory.path().join("pack.zip");
+	fs::write(&output_file_path, PREVIOUS_OUTPUT_ZIP).expect(UNEXPECTED_IO_FAILURE);
+
+	let mut global_options = GlobalOptions::default();
+	global_options.output_file_path = output_file_path.clone();
+	let squash_options = SquashOptions {
+		pack_directory: PathBuf::new(),
+		global_options,
+		file_options: IndexMap::new()
+	};
+
+	// Cancel the operation as soon as it sends its first status update. The channel only
+	// buffers one status update, so the operation can't get far without that consumer
+	// receiving further status updates, which only happens after the cancellation
+	let cancellation_token = CancellationToken::new();
+	let (status_sender, mut status_receiver) = mpsc::channel(1);
+	let status_consumer = thread::spawn({
+		let cancellation_token = cancellation_token.clone();
+
+		move || {
+			if status_receiver.blocking_recv().is_some() {
+				cancellation_token.cancel();
+			}
+
+			while status_receiver.blocking_recv().is_some() {}
+		}
+	});
+
+	let squash_result = PackSquasher::new()
+		.with_cancellation_token(cancellation_token)
+		.run(
+			example_memory_filesystem(),
+			squash_options,
+			Some(status_sender)
+		);
+
+	status_consumer
+		.join()
+		.expect("The status consumer should not panic");
+
+	assert!(
+		matches!(squash_result, Err(PackSquasherError::Cancelled)),
+		"The squash operation should be cancelled"
+	);
+	assert_eq!(
+		fs::read(&output_file_path).expect(UNEXPECTED_IO_FAILURE),
+		PREVIOUS_OUTPUT_ZIP,
+		"The previous output ZIP file should be left untouched"
+	);
+}
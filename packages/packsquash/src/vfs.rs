@@ -1,13 +1,18 @@
 //! Contains virtual file systems implementations to use with `PackSquasher`.
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::{Component, PathBuf};
 use std::time::SystemTime;
-use std::{fs::FileType, io, path::Path};
+use std::{fs, io, path::Path};
 
 use crate::RelativePath;
 use tokio::io::AsyncRead;
 
+pub mod layered_fs;
+pub mod memory_fs;
 pub mod os_fs;
+pub mod zip_fs;
 
 /// Defines the contract that any virtual file system must implement.
 pub trait VirtualFileSystem: Send + Sync {
@@ -39,7 +44,44 @@ pub trait VirtualFileSystem: Send + Sync {
 	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error>;
 
 	/// Returns the type of the file at the specified virtual filesystem path.
-	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error>;
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<VfsFileType, io::Error>;
+}
+
+/// The type of a file in a virtual filesystem, as returned by [`VirtualFileSystem::file_type`].
+///
+/// Unlike [`std::fs::FileType`], this type can be instantiated by virtual filesystems that
+/// are not backed by the operating system filesystems, such as archives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VfsFileType {
+	/// A file that may hold readable user data.
+	File,
+	/// A directory, which may contain other files and directories.
+	Directory
+}
+
+impl VfsFileType {
+	/// Returns whether this file type is [`VfsFileType::File`].
+	pub fn is_file(&self) -> bool {
+		matches!(self, Self::File)
+	}
+
+	/// Returns whether this file type is [`VfsFileType::Directory`].
+	pub fn is_dir(&self) -> bool {
+		matches!(self, Self::Directory)
+	}
+}
+
+impl From<fs::FileType> for VfsFileType {
+	fn from(file_type: fs::FileType) -> Self {
+		// Special files, such as pipes or devices, can be read from as if they were
+		// regular files, so treat everything that is not a directory as a file
+		if file_type.is_dir() {
+			Self::Directory
+		} else {
+			Self::File
+		}
+	}
 }
 
 /// Contains options that tweak the operation of the [`VirtualFileSystem::file_iterator`]
@@ -80,3 +122,117 @@ pub struct VfsPackFileMetadata {
 	/// time to the creation time.
 	pub modification_time: Option<SystemTime>
 }
+
+/// Checks whether a file with the specified name is a system or hidden file, which can
+/// be ignored when iterating over files with the appropriate [`IteratorTraversalOptions`].
+/// `is_dir` tells whether the file is a directory, as different rules apply to directories.
+/// All the virtual filesystems shipped with PackSquash use this function, so that they
+/// ignore the same files.
+pub(crate) fn is_system_or_hidden_file(file_name: &[u8], is_dir: bool) -> bool {
+	// List based on https://www.toptal.com/developers/gitignore/api/git,windows,linux,macos
+	if !is_dir {
+		(file_name.starts_with(b".") && !file_name.ends_with(b".mcfunction"))
+			|| file_name == b"desktop.ini"
+			|| file_name == b"Desktop.ini"
+			|| file_name == b"Thumbs.db"
+			|| file_name == b"ehthumbs.db"
+			|| file_name == b"ehthumbs_vista.db"
+			|| file_name.ends_with(b".lnk")
+			|| file_name.ends_with(b".orig")
+			|| file_name.ends_with(b".bak")
+			|| file_name.ends_with(b".tmp")
+	} else {
+		file_name.starts_with(b".")
+			|| file_name == b"Network Trash Folder"
+			|| file_name == b"Temporary Items"
+			|| file_name == b"$RECYCLE.BIN"
+			|| file_name == b"__MACOSX"
+	}
+}
+
+/// Converts a virtual filesystem path to a normalized string representation that always uses
+/// the forward slash (/) as a component separator, without leading or trailing slashes. This
+/// is the representation used by virtual filesystems that are not backed by the operating
+/// system filesystems. The empty string represents the root directory.
+fn forward_slash_path(path: &Path) -> Result<String, io::Error> {
+	let mut forward_slash_path = String::new();
+
+	for component in path.components() {
+		match component {
+			Component::Normal(component) => {
+				if !forward_slash_path.is_empty() {
+					forward_slash_path.push('/');
+				}
+
+				forward_slash_path.push_str(component.to_str().ok_or_else(|| {
+					io::Error::new(io::ErrorKind::InvalidInput, "Paths must be valid UTF-8")
+				})?);
+			}
+			Component::CurDir => (),
+			_ => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidInput,
+					"Paths must be relative and not refer to parent directories"
+				));
+			}
+		}
+	}
+
+	Ok(forward_slash_path)
+}
+
+/// Implements [`VirtualFileSystem::file_iterator`] for virtual filesystems that keep their
+/// files in a sorted map, keyed by paths normalized as [`forward_slash_path`] does. Files are
+/// yielded in path order, and filtered in the same way as the OS filesystem does.
+fn sorted_path_map_file_iterator<V>(
+	files: &BTreeMap<String, V>,
+	root_path: &Path,
+	iterator_traversal_options: IteratorTraversalOptions
+) -> std::vec::IntoIter<Result<VfsPackFileIterEntry, io::Error>> {
+	let root_path = match forward_slash_path(root_path) {
+		Ok(root_path) => root_path,
+		Err(err) => return vec![Err(err)].into_iter()
+	};
+
+	let file_path_prefix = if root_path.is_empty() {
+		root_path
+	} else {
+		root_path + "/"
+	};
+
+	files
+		.range::<str, _>((Bound::Included(file_path_prefix.as_str()), Bound::Unbounded))
+		.map(|(file_path, _)| file_path)
+		.take_while(|file_path| file_path.starts_with(&file_path_prefix))
+		.filter_map(|file_path| {
+			let relative_path = &file_path[file_path_prefix.len()..];
+
+			if relative_path
+				.split('/')
+				.any(|component| component.is_empty() || component == "." || component == "..")
+			{
+				return Some(Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("The file path {file_path} is not a normalized relative path")
+				)));
+			}
+
+			// Do not descend into system or hidden directories below the root, and do not
+			// yield system or hidden files, just like the OS filesystem does
+			if iterator_traversal_options.ignore_system_and_hidden_files {
+				let mut components = relative_path.split('/').peekable();
+				while let Some(component) = components.next() {
+					if is_system_or_hidden_file(component.as_bytes(), components.peek().is_some()) {
+						return None;
+					}
+				}
+			}
+
+			Some(Ok(VfsPackFileIterEntry {
+				relative_path: RelativePath::from_inner(relative_path.to_string()),
+				file_path: PathBuf::from(file_path)
+			}))
+		})
+		.collect::<Vec<_>>()
+		.into_iter()
+}
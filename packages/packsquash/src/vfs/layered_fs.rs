@@ -0,0 +1,352 @@
+//! Contains the data types that support a virtual filesystem implementation
+//! that merges the files of several underlying virtual filesystems.
+
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncRead;
+
+use super::{
+	IteratorTraversalOptions, VfsFile, VfsFileType, VfsPackFileIterEntry, VirtualFileSystem
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The file name prefix that denotes a whiteout file, which deletes the file or directory
+/// whose name follows the prefix from lower layers of a [`LayeredFilesystem`], when the
+/// whiteout convention is enabled.
+///
+/// This convention matches the one used by the OverlayFS filesystem, as specified in the
+/// OCI image specification.
+pub const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// A virtual filesystem implementation that merges the files of a stack of layers, each one
+/// being a directory in another virtual filesystem. Files in higher layers shadow files with
+/// the same path relative to the layer root in lower layers, which allows composing packs from
+/// a shared base and several override layers without copying their files together first.
+///
+/// Paths within this virtual filesystem are relative to the root of every layer, use the
+/// forward slash (/) or the platform path separator as a component separator, and may not
+/// contain parent directory (..) components. The root directory, where the roots of every
+/// layer are merged, is denoted by an empty path or a single dot (.).
+///
+/// Optionally, a layer may also delete a file or directory from lower layers by containing a
+/// whiteout file with the same name, prefixed by [`WHITEOUT_PREFIX`]. For example, a
+/// `.wh.pack.png` file deletes `pack.png` from lower layers, and a `.wh.sounds` file deletes
+/// the entire `sounds` directory. Whiteout files themselves are never yielded.
+#[derive(Default)]
+pub struct LayeredFilesystem {
+	layers: Vec<Layer>,
+	whiteouts: bool
+}
+
+/// A layer of a [`LayeredFilesystem`].
+struct Layer {
+	vfs: Box<dyn DynVirtualFileSystem>,
+	root_path: PathBuf
+}
+
+impl LayeredFilesystem {
+	/// Creates a new layered virtual filesystem that contains no layers, and thus no files,
+	/// with the whiteout convention disabled.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Stacks a layer whose root is at `root_path` in `vfs` on top of the current layers, so
+	/// that its files take precedence over the files of the layers added before.
+	pub fn with_layer(
+		mut self,
+		vfs: impl VirtualFileSystem<FileIter: Send> + 'static,
+		root_path: impl Into<PathBuf>
+	) -> Self {
+		self.layers.push(Layer {
+			vfs: Box::new(vfs),
+			root_path: root_path.into()
+		});
+		self
+	}
+
+	/// Sets whether whiteout files, whose names begin with [`WHITEOUT_PREFIX`], delete files
+	/// and directories from lower layers.
+	pub fn with_whiteouts(mut self, whiteouts: bool) -> Self {
+		self.whiteouts = whiteouts;
+		self
+	}
+
+	/// Returns the path in the virtual filesystem of the specified layer that corresponds
+	/// to the specified normalized path in this virtual filesystem.
+	fn layer_path(layer: &Layer, path: &str) -> PathBuf {
+		if path.is_empty() {
+			layer.root_path.clone()
+		} else {
+			layer.root_path.join(path)
+		}
+	}
+
+	/// Checks whether the specified layer contains a whiteout for the specified normalized
+	/// path, or any of its ancestors.
+	fn is_whited_out_by(&self, layer: &Layer, path: &str) -> bool {
+		self.whiteouts
+			&& path
+				.match_indices('/')
+				.map(|(separator_index, _)| &path[..separator_index])
+				.chain([path])
+				.filter(|path| !path.is_empty())
+				.any(|path| {
+					let whiteout_path = match path.rsplit_once('/') {
+						Some((parent, file_name)) => format!("{parent}/{WHITEOUT_PREFIX}{file_name}"),
+						None => format!("{WHITEOUT_PREFIX}{path}")
+					};
+
+					layer
+						.vfs
+						.file_type(&Self::layer_path(layer, &whiteout_path))
+						.is_ok()
+				})
+	}
+
+	/// Returns the type of the file at the specified normalized path in the topmost layer that
+	/// contains it, together with that layer, taking whiteouts into account.
+	fn find(&self, path: &str) -> Result<(&Layer, VfsFileType), io::Error> {
+		for layer in self.layers.iter().rev() {
+			match layer.vfs.file_type(&Self::layer_path(layer, path)) {
+				Ok(file_type) => return Ok((layer, file_type)),
+				Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+				Err(_) => ()
+			}
+
+			if self.is_whited_out_by(layer, path) {
+				break;
+			}
+		}
+
+		Err(io::ErrorKind::NotFound.into())
+	}
+}
+
+impl VirtualFileSystem for LayeredFilesystem {
+	type FileRead = Box<dyn AsyncRead + Unpin + Send>;
+	type FileIter = LayeredFileIter;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		let root_path = match super::forward_slash_path(root_path) {
+			Ok(root_path) => root_path,
+			Err(err) => {
+				return LayeredFileIter {
+					layer_iterators: VecDeque::from([
+						Box::new([Err(err)].into_iter()) as Box<dyn Iterator<Item = _> + Send>
+					]),
+					..LayeredFileIter::default()
+				};
+			}
+		};
+
+		// Whiteout files are usually hidden files, so the layers should not ignore them.
+		// Ignore system and hidden files ourselves instead
+		let ignore_system_and_hidden_files =
+			iterator_traversal_options.ignore_system_and_hidden_files;
+		let filter_system_and_hidden_files = ignore_system_and_hidden_files && self.whiteouts;
+
+		// Layers whose root directory is whited out by a higher layer should not be iterated
+		let mut layer_iterators = VecDeque::with_capacity(self.layers.len());
+		for layer in self.layers.iter().rev() {
+			let layer_root_path = Self::layer_path(layer, &root_path);
+
+			if layer.vfs.file_type(&layer_root_path).is_ok() {
+				layer_iterators.push_back(layer.vfs.file_iterator(
+					&layer_root_path,
+					IteratorTraversalOptions {
+						ignore_system_and_hidden_files: ignore_system_and_hidden_files
+							&& !filter_system_and_hidden_files
+					}
+				));
+			}
+
+			if self.is_whited_out_by(layer, &root_path) {
+				break;
+			}
+		}
+
+		LayeredFileIter {
+			layer_iterators,
+			root_path,
+			whiteouts: self.whiteouts,
+			filter_system_and_hidden_files,
+			..LayeredFileIter::default()
+		}
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		let path = super::forward_slash_path(path.as_ref())?;
+
+		match self.find(&path)? {
+			(layer, VfsFileType::File) => layer.vfs.open(&Self::layer_path(layer, &path)),
+			_ => Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"Directories can't be opened"
+			))
+		}
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<VfsFileType, io::Error> {
+		let path = super::forward_slash_path(path.as_ref())?;
+
+		if path.is_empty() {
+			Ok(VfsFileType::Directory)
+		} else {
+			self.find(&path).map(|(_, file_type)| file_type)
+		}
+	}
+}
+
+/// The iterator over the files of a [`LayeredFilesystem`], which yields the files of every
+/// layer, from the topmost layer to the bottom one, skipping shadowed and deleted files.
+#[derive(Default)]
+pub struct LayeredFileIter {
+	layer_iterators:
+		VecDeque<Box<dyn Iterator<Item = Result<VfsPackFileIterEntry, io::Error>> + Send>>,
+	root_path: String,
+	whiteouts: bool,
+	filter_system_and_hidden_files: bool,
+	yielded_relative_paths: HashSet<String>,
+	whited_out_relative_paths: HashSet<String>,
+	current_layer_whited_out_relative_paths: HashSet<String>
+}
+
+impl LayeredFileIter {
+	/// Checks whether the file at the specified relative path was deleted by a higher layer.
+	fn is_whited_out(&self, relative_path: &str) -> bool {
+		relative_path
+			.match_indices('/')
+			.map(|(separator_index, _)| &relative_path[..separator_index])
+			.chain([relative_path])
+			.any(|path| self.whited_out_relative_paths.contains(path))
+	}
+}
+
+impl Iterator for LayeredFileIter {
+	type Item = Result<VfsPackFileIterEntry, io::Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let entry = match self.layer_iterators.front_mut()?.next() {
+				Some(Ok(entry)) => entry,
+				Some(Err(err)) => return Some(Err(err)),
+				None => {
+					// Whiteouts only delete files from the layers below
+					self.layer_iterators.pop_front();
+					self.whited_out_relative_paths
+						.extend(self.current_layer_whited_out_relative_paths.drain());
+					continue;
+				}
+			};
+
+			let relative_path = entry.relative_path.as_str();
+
+			if self.whiteouts {
+				let (parent, file_name) = relative_path
+					.rsplit_once('/')
+					.map_or(("", relative_path), |(parent, file_name)| {
+						(parent, file_name)
+					});
+
+				if let Some(whited_out_file_name) = file_name.strip_prefix(WHITEOUT_PREFIX) {
+					if !whited_out_file_name.is_empty() {
+						self.current_layer_whited_out_relative_paths
+							.insert(if parent.is_empty() {
+								whited_out_file_name.to_string()
+							} else {
+								format!("{parent}/{whited_out_file_name}")
+							});
+					}
+					continue;
+				}
+
+				if self.is_whited_out(relative_path) {
+					continue;
+				}
+			}
+
+			if self.filter_system_and_hidden_files {
+				let mut components = relative_path.split('/').peekable();
+				let mut is_system_or_hidden_file = false;
+				while let Some(component) = components.next() {
+					is_system_or_hidden_file |= super::is_system_or_hidden_file(
+						component.as_bytes(),
+						components.peek().is_some()
+					);
+				}
+
+				if is_system_or_hidden_file {
+					continue;
+				}
+			}
+
+			if !self
+				.yielded_relative_paths
+				.insert(relative_path.to_string())
+			{
+				// Shadowed by a higher layer
+				continue;
+			}
+
+			let file_path = if self.root_path.is_empty() {
+				PathBuf::from(relative_path)
+			} else {
+				PathBuf::from(format!("{}/{relative_path}", self.root_path))
+			};
+
+			return Some(Ok(VfsPackFileIterEntry {
+				relative_path: entry.relative_path,
+				file_path
+			}));
+		}
+	}
+}
+
+/// An object-safe version of [`VirtualFileSystem`], which allows the layers of a
+/// [`LayeredFilesystem`] to be virtual filesystems of different types.
+trait DynVirtualFileSystem: Send + Sync {
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Box<dyn Iterator<Item = Result<VfsPackFileIterEntry, io::Error>> + Send>;
+
+	fn open(&self, path: &Path) -> Result<VfsFile<Box<dyn AsyncRead + Unpin + Send>>, io::Error>;
+
+	fn file_type(&self, path: &Path) -> Result<VfsFileType, io::Error>;
+}
+
+impl<V: VirtualFileSystem<FileIter: Send> + 'static> DynVirtualFileSystem for V {
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Box<dyn Iterator<Item = Result<VfsPackFileIterEntry, io::Error>> + Send> {
+		Box::new(VirtualFileSystem::file_iterator(
+			self,
+			root_path,
+			iterator_traversal_options
+		))
+	}
+
+	fn open(&self, path: &Path) -> Result<VfsFile<Box<dyn AsyncRead + Unpin + Send>>, io::Error> {
+		VirtualFileSystem::open(self, path).map(|file| VfsFile {
+			file_read: Box::new(file.file_read) as Box<dyn AsyncRead + Unpin + Send>,
+			file_size_hint: file.file_size_hint,
+			metadata: file.metadata
+		})
+	}
+
+	fn file_type(&self, path: &Path) -> Result<VfsFileType, io::Error> {
+		VirtualFileSystem::file_type(self, path)
+	}
+}
@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use pretty_assertions::assert_eq;
+use tokio::io::AsyncReadExt;
+
+use crate::vfs::memory_fs::MemoryFilesystem;
+use crate::vfs::{IteratorTraversalOptions, VfsFileType, VirtualFileSystem};
+
+use super::LayeredFilesystem;
+
+fn memory_filesystem<'a>(
+	files: impl IntoIterator<Item = (&'a str, &'static str)>
+) -> MemoryFilesystem {
+	let mut vfs = MemoryFilesystem::new();
+
+	for (path, data) in files {
+		vfs.add_file(path, data, None)
+			.expect("Adding files to the filesystem should not fail");
+	}
+
+	vfs
+}
+
+/// Creates a layered filesystem with a base layer, and an override layer on top of it
+/// that is rooted at a subdirectory of its filesystem.
+fn example_layered_filesystem(whiteouts: bool) -> LayeredFilesystem {
+	LayeredFilesystem::new()
+		.with_layer(
+			memory_filesystem([
+				("pack.mcmeta", "base"),
+				("pack.png", "base"),
+				("assets/minecraft/sounds/a.ogg", "base"),
+				("assets/minecraft/lang/en_us.json", "base"),
+				("assets/minecraft/.hidden.txt", "base")
+			]),
+			""
+		)
+		.with_layer(
+			memory_filesystem([
+				("server1/pack.mcmeta", "server1"),
+				("server1/assets/minecraft/.wh.sounds", ""),
+				("server1/.wh.pack.png", ""),
+				("server1/assets/minecraft/lang/es_es.json", "server1"),
+				("server2/pack.mcmeta", "server2")
+			]),
+			"server1"
+		)
+		.with_whiteouts(whiteouts)
+}
+
+fn relative_paths(
+	vfs: &LayeredFilesystem,
+	root_path: &str,
+	ignore_system_and_hidden_files: bool
+) -> Vec<String> {
+	let mut relative_paths = vfs
+		.file_iterator(
+			Path::new(root_path),
+			IteratorTraversalOptions {
+				ignore_system_and_hidden_files
+			}
+		)
+		.map(|entry| {
+			entry
+				.expect("I/O operations are assumed not to fail during tests")
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+
+	relative_paths.sort_unstable();
+	relative_paths
+}
+
+async fn read_to_string(vfs: &LayeredFilesystem, path: &str) -> String {
+	let mut data = String::new();
+
+	vfs.open(path)
+		.expect("Files in the filesystem should be readable")
+		.file_read
+		.read_to_string(&mut data)
+		.await
+		.expect("I/O operations are assumed not to fail during tests");
+
+	data
+}
+
+#[test]
+fn file_iterator_works() {
+	assert_eq!(
+		relative_paths(&example_layered_filesystem(false), "", true),
+		[
+			"assets/minecraft/lang/en_us.json",
+			"assets/minecraft/lang/es_es.json",
+			"assets/minecraft/sounds/a.ogg",
+			"pack.mcmeta",
+			"pack.png"
+		]
+	);
+}
+
+#[test]
+fn whiteouts_work() {
+	let vfs = example_layered_filesystem(true);
+
+	assert_eq!(
+		relative_paths(&vfs, "", true),
+		[
+			"assets/minecraft/lang/en_us.json",
+			"assets/minecraft/lang/es_es.json",
+			"pack.mcmeta"
+		]
+	);
+	assert_eq!(
+		relative_paths(&vfs, "assets/minecraft", false),
+		[".hidden.txt", "lang/en_us.json", "lang/es_es.json"]
+	);
+	assert_eq!(
+		relative_paths(&vfs, "assets/minecraft/sounds", false),
+		Vec::<String>::new()
+	);
+
+	assert!(vfs.open("pack.png").is_err());
+	assert!(vfs.file_type("assets/minecraft/sounds/a.ogg").is_err());
+}
+
+#[tokio::test]
+async fn higher_layers_shadow_lower_layers() {
+	for whiteouts in [false, true] {
+		let vfs = example_layered_filesystem(whiteouts);
+
+		assert_eq!(read_to_string(&vfs, "pack.mcmeta").await, "server1");
+		assert_eq!(
+			read_to_string(&vfs, "assets/minecraft/lang/en_us.json").await,
+			"base"
+		);
+		assert_eq!(
+			vfs.file_type("assets/minecraft/lang")
+				.expect("Directories in the filesystem should exist"),
+			VfsFileType::Directory
+		);
+		assert!(vfs.file_type("server2").is_err());
+	}
+}
+
+#[test]
+fn boxed_file_reads_work() {
+	// Readers of layers should be usable as is after type erasure
+	let vfs = LayeredFilesystem::new().with_layer(memory_filesystem([("file.txt", "Hi")]), "");
+
+	let mut file = vfs
+		.open("./file.txt")
+		.expect("Files in the filesystem should be readable");
+	assert_eq!(file.file_size_hint, 2);
+
+	let mut buf = [0; 2];
+	tokio_test::block_on(file.file_read.read_exact(&mut buf))
+		.expect("I/O operations are assumed not to fail during tests");
+	assert_eq!(&buf, b"Hi");
+}
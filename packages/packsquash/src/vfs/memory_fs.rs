@@ -0,0 +1,149 @@
+//! Contains the data types that support a virtual filesystem implementation
+//! that operates with files stored in memory.
+
+use std::collections::BTreeMap;
+use std::io::{self, Cursor};
+use std::ops::Bound;
+use std::path::Path;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+
+use super::{
+	IteratorTraversalOptions, VfsFile, VfsFileType, VfsPackFileIterEntry, VfsPackFileMetadata,
+	VirtualFileSystem
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A virtual filesystem implementation that operates with files stored in memory, which
+/// is useful for applications that receive pack files from sources other than a filesystem,
+/// such as network requests, and for tests.
+///
+/// Paths within this virtual filesystem use the forward slash (/) or the platform path
+/// separator as a component separator, and may not contain parent directory (..)
+/// components. The root directory is denoted by an empty path or a single dot (.).
+/// Directories are inferred from the paths of the files added to the filesystem, so
+/// there are no empty directories.
+#[derive(Default, Clone)]
+pub struct MemoryFilesystem {
+	files: BTreeMap<String, MemoryFile>
+}
+
+/// A file stored in a [`MemoryFilesystem`].
+#[derive(Clone)]
+struct MemoryFile {
+	data: Bytes,
+	modification_time: Option<SystemTime>
+}
+
+impl MemoryFilesystem {
+	/// Creates a new in-memory virtual filesystem that contains no files.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a file with the specified data and modification time to this filesystem,
+	/// replacing any file that previously existed at the same path.
+	///
+	/// This operation fails if the path is not a valid, relative path to a file, or if
+	/// it conflicts with the path of an existing directory or file: a file can't be
+	/// added at the path of a directory, nor inside another file.
+	pub fn add_file(
+		&mut self,
+		path: impl AsRef<Path>,
+		data: impl Into<Bytes>,
+		modification_time: Option<SystemTime>
+	) -> Result<(), io::Error> {
+		let path = super::forward_slash_path(path.as_ref())?;
+
+		if path.is_empty() || self.is_directory(&path) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"Files can't be added at directory paths"
+			));
+		}
+
+		if path
+			.match_indices('/')
+			.any(|(separator_index, _)| self.files.contains_key(&path[..separator_index]))
+		{
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"Files can't be added inside other files"
+			));
+		}
+
+		self.files.insert(
+			path,
+			MemoryFile {
+				data: data.into(),
+				modification_time
+			}
+		);
+
+		Ok(())
+	}
+
+	/// Removes the file at the specified path from this filesystem, returning its data, if
+	/// such a file existed.
+	pub fn remove_file(&mut self, path: impl AsRef<Path>) -> Option<Bytes> {
+		self.files
+			.remove(&super::forward_slash_path(path.as_ref()).ok()?)
+			.map(|file| file.data)
+	}
+
+	/// Checks whether there is a directory at the specified normalized path, which happens
+	/// when some file path starts with it.
+	fn is_directory(&self, path: &str) -> bool {
+		path.is_empty() || {
+			let file_path_prefix = format!("{path}/");
+
+			self.files
+				.range::<str, _>((Bound::Included(file_path_prefix.as_str()), Bound::Unbounded))
+				.next()
+				.is_some_and(|(file_path, _)| file_path.starts_with(&file_path_prefix))
+		}
+	}
+}
+
+impl VirtualFileSystem for MemoryFilesystem {
+	type FileRead = Cursor<Bytes>;
+	type FileIter = std::vec::IntoIter<Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		super::sorted_path_map_file_iterator(&self.files, root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		let file = self
+			.files
+			.get(&super::forward_slash_path(path.as_ref())?)
+			.ok_or(io::ErrorKind::NotFound)?;
+
+		Ok(VfsFile {
+			file_read: Cursor::new(file.data.clone()),
+			file_size_hint: file.data.len() as u64,
+			metadata: VfsPackFileMetadata {
+				modification_time: file.modification_time
+			}
+		})
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<VfsFileType, io::Error> {
+		let path = super::forward_slash_path(path.as_ref())?;
+
+		if self.files.contains_key(&path) {
+			Ok(VfsFileType::File)
+		} else if self.is_directory(&path) {
+			Ok(VfsFileType::Directory)
+		} else {
+			Err(io::ErrorKind::NotFound.into())
+		}
+	}
+}
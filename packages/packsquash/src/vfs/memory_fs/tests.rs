@@ -0,0 +1,129 @@
+use std::io::Read;
+use std::time::Duration;
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+fn example_memory_filesystem() -> MemoryFilesystem {
+	let mut vfs = MemoryFilesystem::new();
+
+	for (path, data) in [
+		("pack.mcmeta", &b"{}"[..]),
+		("assets/minecraft/lang/en_us.json", b"{\"hello\":\"world\"}"),
+		("assets/minecraft/.hidden.txt", b"hidden"),
+		("assets/minecraft/functions/.hi.mcfunction", b"say hi"),
+		("__MACOSX/._pack.mcmeta", b"junk")
+	] {
+		vfs.add_file(path, data, None)
+			.expect("Adding files to the filesystem should not fail");
+	}
+
+	vfs
+}
+
+fn relative_paths(
+	vfs: &MemoryFilesystem,
+	root_path: &str,
+	ignore_system_and_hidden_files: bool
+) -> Vec<String> {
+	vfs.file_iterator(
+		Path::new(root_path),
+		IteratorTraversalOptions {
+			ignore_system_and_hidden_files
+		}
+	)
+	.map(|entry| {
+		entry
+			.expect("I/O operations are assumed not to fail during tests")
+			.relative_path
+			.as_str()
+			.to_string()
+	})
+	.collect()
+}
+
+#[test]
+fn file_iterator_works() {
+	let vfs = example_memory_filesystem();
+
+	assert_eq!(
+		relative_paths(&vfs, "", true),
+		[
+			"assets/minecraft/functions/.hi.mcfunction",
+			"assets/minecraft/lang/en_us.json",
+			"pack.mcmeta"
+		]
+	);
+	assert_eq!(relative_paths(&vfs, ".", false).len(), 5);
+	assert_eq!(
+		relative_paths(&vfs, "assets/minecraft/lang", true),
+		["en_us.json"]
+	);
+	assert_eq!(relative_paths(&vfs, "__MACOSX", false), ["._pack.mcmeta"]);
+}
+
+#[test]
+fn file_type_works() {
+	let vfs = example_memory_filesystem();
+
+	for directory in ["", ".", "assets", "assets/minecraft/lang"] {
+		assert_eq!(
+			vfs.file_type(directory)
+				.expect("Directories in the filesystem should exist"),
+			VfsFileType::Directory,
+			"Unexpected file type for {directory}"
+		);
+	}
+
+	assert_eq!(
+		vfs.file_type("pack.mcmeta")
+			.expect("Files in the filesystem should exist"),
+		VfsFileType::File
+	);
+
+	assert_eq!(
+		vfs.file_type("asset")
+			.expect_err("Partial directory names should not exist")
+			.kind(),
+		io::ErrorKind::NotFound
+	);
+}
+
+#[test]
+fn open_works() {
+	let mut vfs = MemoryFilesystem::new();
+	let modification_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1577934246);
+
+	vfs.add_file("assets/file.txt", "Hello", Some(modification_time))
+		.expect("Adding files to the filesystem should not fail");
+
+	let mut file = vfs
+		.open("assets/file.txt")
+		.expect("Files in the filesystem should be readable");
+
+	let mut data = String::new();
+	file.file_read
+		.read_to_string(&mut data)
+		.expect("I/O operations are assumed not to fail during tests");
+
+	assert_eq!(data, "Hello");
+	assert_eq!(file.file_size_hint, 5);
+	assert_eq!(file.metadata.modification_time, Some(modification_time));
+
+	assert_eq!(
+		vfs.remove_file("assets/file.txt"),
+		Some(Bytes::from("Hello"))
+	);
+	assert!(vfs.open("assets/file.txt").is_err());
+}
+
+#[test]
+fn conflicting_paths_are_rejected() {
+	let mut vfs = example_memory_filesystem();
+
+	assert!(vfs.add_file("assets/minecraft", "", None).is_err());
+	assert!(vfs.add_file("pack.mcmeta/file.txt", "", None).is_err());
+	assert!(vfs.add_file("", "", None).is_err());
+	assert!(vfs.add_file("../file.txt", "", None).is_err());
+}
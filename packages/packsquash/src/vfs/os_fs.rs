@@ -3,7 +3,7 @@
 
 use std::borrow::Cow;
 use std::{
-	fs::{self, File, FileType},
+	fs::{self, File},
 	io,
 	path::Path
 };
@@ -14,7 +14,8 @@ use walkdir::{DirEntry, WalkDir};
 use crate::RelativePath;
 
 use super::{
-	IteratorTraversalOptions, VfsFile, VfsPackFileIterEntry, VfsPackFileMetadata, VirtualFileSystem
+	IteratorTraversalOptions, VfsFile, VfsFileType, VfsPackFileIterEntry, VfsPackFileMetadata,
+	VirtualFileSystem
 };
 
 /// A virtual filesystem implementation that operates with files in the mounted
@@ -75,8 +76,8 @@ impl VirtualFileSystem for OsFilesystem {
 		})
 	}
 
-	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<FileType, io::Error> {
-		fs::metadata(path).map(|metadata| metadata.file_type())
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<VfsFileType, io::Error> {
+		fs::metadata(path).map(|metadata| metadata.file_type().into())
 	}
 }
 
@@ -95,25 +96,7 @@ fn is_system_or_hidden_file(entry: &DirEntry) -> bool {
 		file_name = _file_name_str.as_bytes()
 	}
 
-	// List based on https://www.toptal.com/developers/gitignore/api/git,windows,linux,macos
-	if entry.file_type().is_file() {
-		(file_name.starts_with(b".") && !file_name.ends_with(b".mcfunction"))
-			|| file_name == b"desktop.ini"
-			|| file_name == b"Desktop.ini"
-			|| file_name == b"Thumbs.db"
-			|| file_name == b"ehthumbs.db"
-			|| file_name == b"ehthumbs_vista.db"
-			|| file_name.ends_with(b".lnk")
-			|| file_name.ends_with(b".orig")
-			|| file_name.ends_with(b".bak")
-			|| file_name.ends_with(b".tmp")
-	} else {
-		file_name.starts_with(b".")
-			|| file_name == b"Network Trash Folder"
-			|| file_name == b"Temporary Items"
-			|| file_name == b"$RECYCLE.BIN"
-			|| file_name == b"__MACOSX"
-	}
+	super::is_system_or_hidden_file(file_name, entry.file_type().is_dir())
 }
 
 #[cfg(test)]
@@ -0,0 +1,492 @@
+//! Contains the data types that support a virtual filesystem implementation
+//! that operates with files stored in a ZIP archive.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use flate2::read::DeflateDecoder;
+use memchr::memmem;
+
+use super::{
+	IteratorTraversalOptions, VfsFile, VfsFileType, VfsPackFileIterEntry, VfsPackFileMetadata,
+	VirtualFileSystem
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The signature of a ZIP local file header record.
+const LOCAL_FILE_HEADER_SIGNATURE: [u8; 4] = 0x04_03_4B_50_u32.to_le_bytes();
+/// The signature of a ZIP central directory header record.
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: [u8; 4] = 0x02_01_4B_50_u32.to_le_bytes();
+/// The signature of a ZIP end of central directory record.
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = 0x06_05_4B_50_u32.to_le_bytes();
+/// The signature of a ZIP64 end of central directory record.
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = 0x06_06_4B_50_u32.to_le_bytes();
+/// The signature of a ZIP64 end of central directory locator record.
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE: [u8; 4] = 0x07_06_4B_50_u32.to_le_bytes();
+
+/// The size of an end of central directory record without an archive comment.
+const END_OF_CENTRAL_DIRECTORY_MIN_SIZE: u64 = 22;
+/// The size of a ZIP64 end of central directory record without extensible data.
+const ZIP64_END_OF_CENTRAL_DIRECTORY_MIN_SIZE: u64 = 56;
+/// The size of a ZIP64 end of central directory locator record.
+const ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE: u64 = 20;
+
+/// The maximum number of bytes to allocate in advance for buffers whose size is read from
+/// the archive, which protects against memory exhaustion due to malicious size fields.
+const MAX_PREALLOCATED_BUFFER_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The header ID of the ZIP64 extended information extra field.
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+/// The header ID of the extended timestamp extra field, defined by Info-ZIP.
+const EXTENDED_TIMESTAMP_EXTRA_FIELD_ID: u16 = 0x5455;
+
+/// A virtual filesystem implementation that operates with the files stored in a ZIP
+/// archive, which is read from any seekable byte source, such as a file or an in-memory
+/// buffer. This allows optimizing packs distributed as ZIP files without extracting them
+/// to a directory first.
+///
+/// Paths within this virtual filesystem are relative to the root of the archive, use the
+/// forward slash (/) or the platform path separator as a component separator, and may
+/// not contain parent directory (..) components. The root of the archive is denoted by
+/// an empty path or a single dot (.). Directories are inferred from the paths of the
+/// archive entries, so archives that lack explicit directory entries are supported.
+///
+/// Like Minecraft does, this filesystem reads the ZIP central directory to find out
+/// what files are in the archive, and tolerates data prepended to the archive. Only the
+/// STORE and DEFLATE compression methods are supported, and encrypted entries can't be
+/// opened. For simplicity, the data of an entry is read to memory in its entirety when
+/// the entry is opened, which is fine for the sizes of the files usually found in packs.
+pub struct ZipFilesystem<R: Read + Seek + Send> {
+	reader: Mutex<R>,
+	entries: BTreeMap<String, ZipEntry>,
+	directories: BTreeSet<String>
+}
+
+/// Metadata of a file stored in a ZIP archive, gathered from its central directory header.
+struct ZipEntry {
+	compression_method: u16,
+	encrypted: bool,
+	compressed_size: u64,
+	uncompressed_size: u64,
+	local_header_offset: u64,
+	modification_time: Option<SystemTime>
+}
+
+impl<R: Read + Seek + Send> ZipFilesystem<R> {
+	/// Creates a new ZIP virtual filesystem that reads the ZIP archive provided by the
+	/// specified reader. The central directory of the archive is read and validated
+	/// before returning, so this operation fails if the byte source is not a ZIP file.
+	///
+	/// Readers that do small reads are expensive to use with this filesystem, so it
+	/// is recommended to wrap files in a [`std::io::BufReader`].
+	pub fn new(mut reader: R) -> Result<Self, io::Error> {
+		let (entries, directories) = read_central_directory(&mut reader)?;
+
+		Ok(Self {
+			reader: Mutex::new(reader),
+			entries,
+			directories
+		})
+	}
+
+	/// Reads the compressed data of the specified entry, returning it as is.
+	fn read_compressed_data(&self, entry: &ZipEntry) -> Result<Vec<u8>, io::Error> {
+		let mut reader = self
+			.reader
+			.lock()
+			.map_err(|_| io::Error::other("A previous ZIP archive read panicked"))?;
+
+		let mut local_header = [0; 30];
+		reader.seek(SeekFrom::Start(entry.local_header_offset))?;
+		reader.read_exact(&mut local_header)?;
+
+		if local_header[..4] != LOCAL_FILE_HEADER_SIGNATURE {
+			return Err(invalid_data(
+				"Local file header signature expected, but not found"
+			));
+		}
+
+		// The lengths of the file name and extra fields in the local header may differ from
+		// those in the central directory, so they have to be read to locate the entry data
+		let file_name_length = read_u16(&local_header, 26);
+		let extra_field_length = read_u16(&local_header, 28);
+		reader.seek(SeekFrom::Current(
+			file_name_length as i64 + extra_field_length as i64
+		))?;
+
+		let mut compressed_data = Vec::with_capacity(preallocated_buffer_size(entry.compressed_size));
+		(&mut *reader)
+			.take(entry.compressed_size)
+			.read_to_end(&mut compressed_data)?;
+
+		if compressed_data.len() as u64 != entry.compressed_size {
+			return Err(io::ErrorKind::UnexpectedEof.into());
+		}
+
+		Ok(compressed_data)
+	}
+}
+
+impl<R: Read + Seek + Send> VirtualFileSystem for ZipFilesystem<R> {
+	type FileRead = Cursor<Vec<u8>>;
+	type FileIter = std::vec::IntoIter<Result<VfsPackFileIterEntry, io::Error>>;
+
+	fn file_iterator(
+		&self,
+		root_path: &Path,
+		iterator_traversal_options: IteratorTraversalOptions
+	) -> Self::FileIter {
+		super::sorted_path_map_file_iterator(&self.entries, root_path, iterator_traversal_options)
+	}
+
+	fn open<P: AsRef<Path>>(&self, path: P) -> Result<VfsFile<Self::FileRead>, io::Error> {
+		let entry = self
+			.entries
+			.get(&super::forward_slash_path(path.as_ref())?)
+			.ok_or(io::ErrorKind::NotFound)?;
+
+		if entry.encrypted {
+			return Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"Encrypted ZIP entries are not supported"
+			));
+		}
+
+		let compressed_data = self.read_compressed_data(entry)?;
+		let data = match entry.compression_method {
+			// STORE
+			0 => compressed_data,
+			// DEFLATE
+			8 => {
+				// Do not inflate more data than declared, plus a byte to detect that the declared
+				// size was exceeded, so that ZIP bombs can't exhaust memory
+				let mut data = Vec::with_capacity(preallocated_buffer_size(entry.uncompressed_size));
+				DeflateDecoder::new(&*compressed_data)
+					.take(entry.uncompressed_size.saturating_add(1))
+					.read_to_end(&mut data)?;
+				data
+			}
+			compression_method => {
+				return Err(io::Error::new(
+					io::ErrorKind::Unsupported,
+					format!("Unsupported ZIP compression method: {compression_method}")
+				));
+			}
+		};
+
+		if data.len() as u64 != entry.uncompressed_size {
+			return Err(invalid_data(
+				"The size of the ZIP entry data does not match its expected size"
+			));
+		}
+
+		Ok(VfsFile {
+			file_read: Cursor::new(data),
+			file_size_hint: entry.uncompressed_size,
+			metadata: VfsPackFileMetadata {
+				modification_time: entry.modification_time
+			}
+		})
+	}
+
+	fn file_type<P: AsRef<Path>>(&self, path: P) -> Result<VfsFileType, io::Error> {
+		let path = super::forward_slash_path(path.as_ref())?;
+
+		if self.entries.contains_key(&path) {
+			Ok(VfsFileType::File)
+		} else if path.is_empty() || self.directories.contains(&path) {
+			Ok(VfsFileType::Directory)
+		} else {
+			Err(io::ErrorKind::NotFound.into())
+		}
+	}
+}
+
+/// Reads the central directory of the ZIP archive provided by `reader`, returning the
+/// file entries it contains, keyed by name, and the set of directories that contain them.
+#[allow(clippy::type_complexity)]
+fn read_central_directory(
+	reader: &mut (impl Read + Seek)
+) -> Result<(BTreeMap<String, ZipEntry>, BTreeSet<String>), io::Error> {
+	let end_of_central_directory_offset = locate_end_of_central_directory(reader)?
+		.ok_or_else(|| invalid_data("End of central directory record not found"))?;
+
+	let mut buffer = [0; ZIP64_END_OF_CENTRAL_DIRECTORY_MIN_SIZE as usize];
+	reader.seek(SeekFrom::Start(end_of_central_directory_offset))?;
+	reader.read_exact(&mut buffer[..END_OF_CENTRAL_DIRECTORY_MIN_SIZE as usize])?;
+
+	let mut entry_count = read_u16(&buffer, 10) as u64;
+	let mut central_directory_size = read_u32(&buffer, 12) as u64;
+	let mut central_directory_offset = read_u32(&buffer, 16) as u64;
+	let mut central_directory_end_offset = end_of_central_directory_offset;
+
+	if entry_count == u16::MAX as u64
+		|| central_directory_size == u32::MAX as u64
+		|| central_directory_offset == u32::MAX as u64
+	{
+		let locator_offset = end_of_central_directory_offset
+			.checked_sub(ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE)
+			.ok_or_else(|| invalid_data("ZIP64 end of central directory locator not found"))?;
+
+		reader.seek(SeekFrom::Start(locator_offset))?;
+		reader.read_exact(&mut buffer[..ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIZE as usize])?;
+		if buffer[..4] != ZIP64_END_OF_CENTRAL_DIRECTORY_LOCATOR_SIGNATURE {
+			return Err(invalid_data(
+				"ZIP64 end of central directory locator not found"
+			));
+		}
+
+		// Data may be prepended to the archive, so the ZIP64 end of central directory record
+		// may not be where the locator says. In that case, look for it right before the locator
+		let mut zip64_end_of_central_directory_offset = read_u64(&buffer, 8);
+		let mut found = false;
+		for candidate_offset in [
+			Some(zip64_end_of_central_directory_offset),
+			locator_offset.checked_sub(ZIP64_END_OF_CENTRAL_DIRECTORY_MIN_SIZE)
+		]
+		.into_iter()
+		.flatten()
+		{
+			reader.seek(SeekFrom::Start(candidate_offset))?;
+			if reader.read_exact(&mut buffer).is_ok()
+				&& buffer[..4] == ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE
+			{
+				zip64_end_of_central_directory_offset = candidate_offset;
+				found = true;
+				break;
+			}
+		}
+
+		if !found {
+			return Err(invalid_data(
+				"ZIP64 end of central directory record not found"
+			));
+		}
+
+		entry_count = read_u64(&buffer, 32);
+		central_directory_size = read_u64(&buffer, 40);
+		central_directory_offset = read_u64(&buffer, 48);
+		central_directory_end_offset = zip64_end_of_central_directory_offset;
+	}
+
+	// Compute the offset of any data prepended to the archive from the actual position
+	// of the central directory, which should end right before the end records
+	let prepended_data_size = central_directory_end_offset
+		.checked_sub(central_directory_size)
+		.and_then(|central_directory_start| {
+			central_directory_start.checked_sub(central_directory_offset)
+		})
+		.ok_or_else(|| invalid_data("Inconsistent central directory size or offset"))?;
+
+	reader.seek(SeekFrom::Start(
+		central_directory_offset + prepended_data_size
+	))?;
+
+	let mut central_directory = Vec::with_capacity(preallocated_buffer_size(central_directory_size));
+	(&mut *reader)
+		.take(central_directory_size)
+		.read_to_end(&mut central_directory)?;
+
+	let mut entries = BTreeMap::new();
+	let mut directories = BTreeSet::new();
+	let mut record = &central_directory[..];
+
+	for _ in 0..entry_count {
+		if record.len() < 46 || record[..4] != CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+			return Err(invalid_data(
+				"Central directory header signature expected, but not found"
+			));
+		}
+
+		let general_purpose_bit_flag = read_u16(record, 8);
+		let compression_method = read_u16(record, 10);
+		let dos_time = read_u16(record, 12);
+		let dos_date = read_u16(record, 14);
+		let mut compressed_size = read_u32(record, 20) as u64;
+		let mut uncompressed_size = read_u32(record, 24) as u64;
+		let file_name_length = read_u16(record, 28) as usize;
+		let extra_field_length = read_u16(record, 30) as usize;
+		let file_comment_length = read_u16(record, 32) as usize;
+		let mut local_header_offset = read_u32(record, 42) as u64;
+
+		let record_size = 46 + file_name_length + extra_field_length + file_comment_length;
+		if record.len() < record_size {
+			return Err(invalid_data("Truncated central directory header"));
+		}
+
+		let file_name = std::str::from_utf8(&record[46..46 + file_name_length])
+			.map_err(|_| invalid_data("ZIP entry names must be valid UTF-8"))?;
+		let mut extra_fields =
+			&record[46 + file_name_length..46 + file_name_length + extra_field_length];
+
+		let mut modification_time = dos_date_time_to_system_time(dos_date, dos_time);
+		while extra_fields.len() >= 4 {
+			let header_id = read_u16(extra_fields, 0);
+			let data_size = read_u16(extra_fields, 2) as usize;
+			let data = extra_fields
+				.get(4..4 + data_size)
+				.ok_or_else(|| invalid_data("Truncated extra field"))?;
+
+			match header_id {
+				ZIP64_EXTRA_FIELD_ID => {
+					// Only the fields that overflowed in the header are present, in this order
+					let mut data = data;
+					for field in [
+						&mut uncompressed_size,
+						&mut compressed_size,
+						&mut local_header_offset
+					] {
+						if *field == u32::MAX as u64 {
+							if data.len() < 8 {
+								return Err(invalid_data("Truncated ZIP64 extra field"));
+							}
+
+							*field = read_u64(data, 0);
+							data = &data[8..];
+						}
+					}
+				}
+				EXTENDED_TIMESTAMP_EXTRA_FIELD_ID if data.len() >= 5 && data[0] & 1 != 0 => {
+					// This field has a precision of one second, unlike DOS times
+					let unix_time = i32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+					modification_time = Some(if unix_time >= 0 {
+						SystemTime::UNIX_EPOCH + Duration::from_secs(unix_time as u64)
+					} else {
+						SystemTime::UNIX_EPOCH - Duration::from_secs(unix_time.unsigned_abs() as u64)
+					});
+				}
+				_ => ()
+			}
+
+			extra_fields = &extra_fields[4 + data_size..];
+		}
+
+		// Register the ancestors of this entry as directories, and the entry itself as
+		// a directory or a file, depending on whether its name ends with a slash
+		let (file_name, is_directory) = file_name
+			.strip_suffix('/')
+			.map_or((file_name, false), |directory_name| (directory_name, true));
+
+		let mut ancestor = file_name;
+		while let Some((parent, _)) = ancestor.rsplit_once('/') {
+			if !directories.insert(parent.to_string()) {
+				// The rest of the ancestors were already registered
+				break;
+			}
+			ancestor = parent;
+		}
+
+		if is_directory {
+			directories.insert(file_name.to_string());
+		} else {
+			let local_header_offset = local_header_offset
+				.checked_add(prepended_data_size)
+				.ok_or_else(|| invalid_data("Inconsistent local header offset"))?;
+
+			entries.insert(
+				file_name.to_string(),
+				ZipEntry {
+					compression_method,
+					encrypted: general_purpose_bit_flag & 1 != 0,
+					compressed_size,
+					uncompressed_size,
+					local_header_offset,
+					modification_time
+				}
+			);
+		}
+
+		record = &record[record_size..];
+	}
+
+	Ok((entries, directories))
+}
+
+/// Locates the offset of the end of central directory record of a ZIP archive by looking
+/// for its signature, starting from the end of the archive, where it should be.
+fn locate_end_of_central_directory(
+	reader: &mut (impl Read + Seek)
+) -> Result<Option<u64>, io::Error> {
+	const MAX_END_OF_CENTRAL_DIRECTORY_SIZE: u64 =
+		END_OF_CENTRAL_DIRECTORY_MIN_SIZE + u16::MAX as u64;
+
+	let archive_size = reader.seek(SeekFrom::End(0))?;
+	let search_region_start = archive_size.saturating_sub(MAX_END_OF_CENTRAL_DIRECTORY_SIZE);
+
+	let mut search_region = Vec::with_capacity((archive_size - search_region_start) as usize);
+	reader.seek(SeekFrom::Start(search_region_start))?;
+	reader.read_to_end(&mut search_region)?;
+
+	// The last signature that leaves room for a complete record is the most likely to
+	// belong to the actual record, as the archive comment is at the end of the file
+	Ok(
+		memmem::rfind_iter(&search_region, &END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+			.find(|&offset| {
+				search_region.len() - offset >= END_OF_CENTRAL_DIRECTORY_MIN_SIZE as usize
+			})
+			.map(|offset| search_region_start + offset as u64)
+	)
+}
+
+/// Converts a MS-DOS date and time, as stored in ZIP headers, to a system time. As MS-DOS
+/// times have no time zone information, they are assumed to be in UTC.
+fn dos_date_time_to_system_time(dos_date: u16, dos_time: u16) -> Option<SystemTime> {
+	let year = 1980 + (dos_date >> 9) as i64;
+	let month = ((dos_date >> 5) & 0xF) as i64;
+	let day = (dos_date & 0x1F) as i64;
+	let hour = (dos_time >> 11) as u64;
+	let minute = ((dos_time >> 5) & 0x3F) as u64;
+	let second = ((dos_time & 0x1F) * 2) as u64;
+
+	if !(1..=12).contains(&month) || day == 0 || hour > 23 || minute > 59 || second > 59 {
+		return None;
+	}
+
+	// Days since the Unix epoch for a proleptic Gregorian calendar date, adapted from
+	// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+	let (year, month) = if month <= 2 {
+		(year - 1, month + 9)
+	} else {
+		(year, month - 3)
+	};
+	let era = year / 400;
+	let year_of_era = year - era * 400;
+	let day_of_year = (153 * month + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	let days_since_epoch = (era * 146097 + day_of_era - 719468) as u64;
+
+	Some(
+		SystemTime::UNIX_EPOCH
+			+ Duration::from_secs(days_since_epoch * 86400 + hour * 3600 + minute * 60 + second)
+	)
+}
+
+/// Returns the capacity to preallocate for a buffer that is expected to hold `size` bytes.
+fn preallocated_buffer_size(size: u64) -> usize {
+	size.min(MAX_PREALLOCATED_BUFFER_SIZE) as usize
+}
+
+/// Creates an I/O error that signals invalid ZIP archive data.
+fn invalid_data(message: impl Into<String>) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Reads a little-endian 16-bit unsigned integer at the specified buffer offset.
+fn read_u16(buffer: &[u8], offset: usize) -> u16 {
+	u16::from_le_bytes([buffer[offset], buffer[offset + 1]])
+}
+
+/// Reads a little-endian 32-bit unsigned integer at the specified buffer offset.
+fn read_u32(buffer: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes(buffer[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads a little-endian 64-bit unsigned integer at the specified buffer offset.
+fn read_u64(buffer: &[u8], offset: usize) -> u64 {
+	u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap())
+}
@@ -0,0 +1,200 @@
+use std::time::{Duration, SystemTime};
+
+use pretty_assertions::assert_eq;
+
+use super::*;
+
+/// Data for a ZIP file that contains a small pack, with a DEFLATE-compressed `pack.mcmeta`
+/// file, an explicit directory entry, and some hidden and system files. Every entry was
+/// last modified on 2020-01-02 03:04:06.
+static EXAMPLE_ZIP: &[u8] = include_bytes!("example.zip");
+
+/// The modification time of every entry in [`EXAMPLE_ZIP`].
+const EXAMPLE_ZIP_MODIFICATION_TIME: u64 = 1577934246;
+
+fn example_zip_filesystem(prepended_data: &[u8]) -> ZipFilesystem<Cursor<Vec<u8>>> {
+	ZipFilesystem::new(Cursor::new([prepended_data, EXAMPLE_ZIP].concat()))
+		.expect("The example ZIP file is assumed to be valid")
+}
+
+fn relative_paths(
+	vfs: &ZipFilesystem<Cursor<Vec<u8>>>,
+	root_path: &str,
+	ignore_system_and_hidden_files: bool
+) -> Vec<String> {
+	vfs.file_iterator(
+		Path::new(root_path),
+		IteratorTraversalOptions {
+			ignore_system_and_hidden_files
+		}
+	)
+	.map(|entry| {
+		entry
+			.expect("I/O operations are assumed not to fail during tests")
+			.relative_path
+			.as_str()
+			.to_string()
+	})
+	.collect()
+}
+
+#[test]
+fn file_iterator_works() {
+	let vfs = example_zip_filesystem(&[]);
+
+	assert_eq!(
+		relative_paths(&vfs, "", true),
+		["assets/minecraft/lang/en_us.json", "pack.mcmeta"]
+	);
+	assert_eq!(
+		relative_paths(&vfs, ".", false),
+		[
+			"__MACOSX/._pack.mcmeta",
+			"assets/minecraft/.hidden.txt",
+			"assets/minecraft/lang/en_us.json",
+			"pack.mcmeta"
+		]
+	);
+	assert_eq!(
+		relative_paths(&vfs, "assets/minecraft", true),
+		["lang/en_us.json"]
+	);
+	assert!(relative_paths(&vfs, "pack.mcmeta", false).is_empty());
+}
+
+#[test]
+fn hidden_root_directory_is_descended_into() {
+	let vfs = example_zip_filesystem(&[]);
+
+	assert_eq!(
+		relative_paths(&vfs, "__MACOSX", true),
+		Vec::<String>::new(),
+		"Hidden files inside a hidden root directory should still be ignored"
+	);
+	assert_eq!(relative_paths(&vfs, "__MACOSX", false), ["._pack.mcmeta"]);
+}
+
+#[test]
+fn file_type_works() {
+	let vfs = example_zip_filesystem(&[]);
+
+	for directory in [
+		"",
+		".",
+		"assets",
+		"assets/minecraft",
+		"assets/minecraft/lang"
+	] {
+		assert_eq!(
+			vfs.file_type(directory)
+				.expect("Directories in the ZIP file should exist"),
+			VfsFileType::Directory,
+			"Unexpected file type for {directory}"
+		);
+	}
+
+	assert_eq!(
+		vfs.file_type("assets/minecraft/lang/en_us.json")
+			.expect("Files in the ZIP file should exist"),
+		VfsFileType::File
+	);
+
+	assert_eq!(
+		vfs.file_type("data")
+			.expect_err("Directories not in the ZIP file should not exist")
+			.kind(),
+		io::ErrorKind::NotFound
+	);
+}
+
+#[test]
+fn open_works() {
+	// Prepended data should not prevent the ZIP file from being read
+	for prepended_data in [&[][..], b"PK\x03\x04 not really a local file header"] {
+		let vfs = example_zip_filesystem(prepended_data);
+
+		for (path, expected_data) in [
+			(
+				"pack.mcmeta",
+				r#"{"pack":{"pack_format":7,"description":"ZIP pack"}}"#.repeat(4)
+			),
+			(
+				"assets/minecraft/lang/en_us.json",
+				r#"{"hello":"world"}"#.to_string()
+			)
+		] {
+			let mut file = vfs
+				.open(path)
+				.expect("Files in the ZIP file should be readable");
+
+			let mut data = String::new();
+			file.file_read
+				.read_to_string(&mut data)
+				.expect("I/O operations are assumed not to fail during tests");
+
+			assert_eq!(data, expected_data);
+			assert_eq!(file.file_size_hint, expected_data.len() as u64);
+			assert_eq!(
+				file.metadata.modification_time,
+				Some(SystemTime::UNIX_EPOCH + Duration::from_secs(EXAMPLE_ZIP_MODIFICATION_TIME))
+			);
+		}
+	}
+}
+
+#[test]
+fn invalid_zip_files_are_rejected() {
+	assert!(ZipFilesystem::new(Cursor::new(b"This is not a ZIP file")).is_err());
+	assert!(ZipFilesystem::new(Cursor::new(&EXAMPLE_ZIP[..EXAMPLE_ZIP.len() - 30])).is_err());
+}
+
+#[test]
+fn overflowing_local_header_offsets_are_rejected() {
+	// A central directory entry whose ZIP64 local header offset overflows once the size of
+	// the data prepended to the archive is added to it
+	let central_directory = [
+		&[0x50, 0x4B, 0x01, 0x02][..],
+		&[0; 24],
+		&1u16.to_le_bytes(),  // File name length
+		&12u16.to_le_bytes(), // Extra field length
+		&[0; 10],
+		&u32::MAX.to_le_bytes(), // Local header offset
+		b"a",
+		&1u16.to_le_bytes(), // ZIP64 extra field ID
+		&8u16.to_le_bytes(),
+		&u64::MAX.to_le_bytes()
+	]
+	.concat();
+	let end_of_central_directory = [
+		&[0x50, 0x4B, 0x05, 0x06][..],
+		&[0; 4],
+		&1u16.to_le_bytes(),
+		&1u16.to_le_bytes(),
+		&(central_directory.len() as u32).to_le_bytes(),
+		&0u32.to_le_bytes(), // Central directory offset
+		&[0; 2]
+	]
+	.concat();
+
+	assert_eq!(
+		ZipFilesystem::new(Cursor::new(
+			[&[0][..], &central_directory, &end_of_central_directory].concat()
+		))
+		.err()
+		.expect("Overflowing local header offsets should be rejected")
+		.kind(),
+		io::ErrorKind::InvalidData
+	);
+}
+
+#[test]
+fn parent_directory_paths_are_rejected() {
+	let vfs = example_zip_filesystem(&[]);
+
+	assert_eq!(
+		vfs.file_type("assets/../pack.mcmeta")
+			.expect_err("Parent directory components should be rejected")
+			.kind(),
+		io::ErrorKind::InvalidInput
+	);
+}
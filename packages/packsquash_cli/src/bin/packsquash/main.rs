@@ -3,28 +3,45 @@
 use anstyle::{AnsiColor, Color, Effects};
 use std::{
 	borrow::Cow,
-	env, fs,
-	io::{self, IsTerminal, Read, Stderr},
+	env,
+	fs::{self, File},
+	io::{self, BufReader, IsTerminal, Read, Stderr},
+	path::{Path, PathBuf},
 	process,
-	time::{Duration, Instant}
+	time::{Duration, Instant, UNIX_EPOCH}
 };
 
 use env_logger::{Builder, Target, WriteStyle};
 use getopts::{Options, ParsingStyle};
 use log::{Level, LevelFilter, debug, error, info, trace, warn};
+use pack_file_report::write_pack_file_report;
+use pack_server::PackServer;
+use pack_watcher::PackWatcher;
 use packsquash::{
-	PackSquasher, PackSquasherError, PackSquasherStatus, PackSquasherWarning, config::SquashOptions,
-	vfs::os_fs::OsFilesystem
+	OutputZipDigests, PackFileDecision, PackFileStatus, PackSquasher, PackSquasherError,
+	PackSquasherStatus, PackSquasherWarning,
+	config::SquashOptions,
+	vfs::{VirtualFileSystem, os_fs::OsFilesystem, zip_fs::ZipFilesystem}
 };
 use terminal_style::{environment_allows_color, environment_allows_emoji};
 use terminal_title_controller::TerminalTitleController;
-use tokio::{runtime, select, sync::mpsc::channel, time::sleep};
+use tokio::{
+	runtime, select,
+	sync::mpsc::{Sender, channel},
+	time::sleep
+};
 use tz::UtcDateTime;
 
+mod pack_file_report;
+mod pack_server;
+mod pack_watcher;
 mod terminal_style;
 mod terminal_title_controller;
 mod terminal_title_setter;
 
+/// The loopback interface port the `serve` subcommand serves the output ZIP file on by default.
+const DEFAULT_SERVE_PORT: u16 = 8080;
+
 /// The log target where status messages will be sent to.
 const LOG_TARGET: Target = Target::Stderr;
 /// A producer of the [`IsTerminal`] implementation that matches the [`LOG_TARGET`] constant.
@@ -83,23 +100,71 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 			"Always disable color in messages. \
 			This is equivalent to defining the NO_COLOR environment variable, or setting PACKSQUASH_COLOR or COLOR to something else than \"show\""
 		)
+		.optopt(
+			"",
+			"report",
+			"Writes a machine-readable report with a record for every pack file to the specified path after processing the pack. \
+			The report is written in CSV format if the path has a .csv extension, and in JSON format otherwise",
+			"PATH"
+		)
+		.optflag(
+			"",
+			"watch",
+			"After processing the pack, keep watching its files for changes and process it again as a whole whenever they \
+			change. Unchanged files are copied from the previously generated ZIP file instead of being optimized again"
+		)
+		.optflag(
+			"",
+			"print-options",
+			"Prints the options that would be used to process the pack in TOML format and exits, without processing it. \
+			The printed options include the default value of every option that was not set, and automatically detected values"
+		)
+		.optopt(
+			"",
+			"port",
+			&format!(
+				"The loopback interface port to serve the generated ZIP file on, when using the serve subcommand. \
+				Defaults to {DEFAULT_SERVE_PORT}"
+			),
+			"PORT"
+		)
 		.parsing_style(ParsingStyle::StopAtFirstFree);
 
 	match options.parse(env::args().skip(1)) {
 		Ok(option_matches) => {
 			if option_matches.opt_present("h") {
-				print_version_information(true);
+				print!("{}", version_information(true));
 				println!();
 				println!("Usage:");
-				print!(
+				println!(
 					"    {} [OPTION]... [options file path]",
 					env!("CARGO_BIN_NAME")
 				);
+				println!(
+					"    {} [OPTION]... verify [options file path]",
+					env!("CARGO_BIN_NAME")
+				);
+				println!(
+					"    {} [OPTION]... inspect [options file path]",
+					env!("CARGO_BIN_NAME")
+				);
+				println!(
+					"    {} [OPTION]... plan [options file path]",
+					env!("CARGO_BIN_NAME")
+				);
+				println!(
+					"    {} [OPTION]... explain <relative path> [options file path]",
+					env!("CARGO_BIN_NAME")
+				);
+				print!(
+					"    {} [OPTION]... serve [options file path]",
+					env!("CARGO_BIN_NAME")
+				);
 				println!("{}", options.usage(""));
 
 				0
 			} else if option_matches.opt_present("v") {
-				print_version_information(true);
+				print!("{}", version_information(true));
 
 				0
 			} else {
@@ -117,15 +182,70 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 
 				init_logger(enable_emoji, enable_color);
 
-				print_version_information(false);
-				println!();
-				read_options_file_and_squash(
-					option_matches.free.first().filter(|path| {
-						// Let "-" behave as if no path was provided
-						path != &"-"
-					}),
-					title_controller
-				)
+				let mut free_args = option_matches.free.iter().peekable();
+				let subcommand = free_args
+					.next_if(|arg| {
+						matches!(
+							arg.as_str(),
+							"verify" | "inspect" | "plan" | "explain" | "serve"
+						)
+					})
+					.map(String::as_str);
+
+				// Squashing a pack may write the output ZIP file to the standard output stream,
+				// which must not carry anything else then. The output file path is not known
+				// until the options file is read, so show the banner next to the log messages
+				if subcommand.is_none() && !option_matches.opt_present("print-options") {
+					eprintln!("{}", version_information(false));
+				} else {
+					println!("{}", version_information(false));
+				}
+				let explained_path = subcommand
+					.filter(|subcommand| *subcommand == "explain")
+					.and_then(|_| free_args.next());
+				let options_file_path = free_args.next().filter(|path| {
+					// Let "-" behave as if no path was provided
+					path != &"-"
+				});
+
+				match subcommand {
+					_ if option_matches.opt_present("print-options") => {
+						read_options_file_and_print_resolved_options(options_file_path)
+					}
+					Some("verify") => read_options_file_and_verify(options_file_path),
+					Some("inspect") => read_options_file_and_inspect(options_file_path),
+					Some("plan") => read_options_file_and_plan(options_file_path),
+					Some("explain") => match explained_path {
+						Some(explained_path) => {
+							read_options_file_and_explain(explained_path, options_file_path)
+						}
+						None => {
+							error!("The explain subcommand requires a relative pack file path");
+
+							1
+						}
+					},
+					Some("serve") => match option_matches.opt_get_default("port", DEFAULT_SERVE_PORT)
+					{
+						Ok(port) => read_options_file_and_serve(
+							options_file_path,
+							option_matches.opt_str("report").as_deref().map(Path::new),
+							port,
+							title_controller
+						),
+						Err(err) => {
+							error!("Invalid port: {err}");
+
+							1
+						}
+					},
+					_ => read_options_file_and_squash(
+						options_file_path,
+						option_matches.opt_str("report").as_deref().map(Path::new),
+						option_matches.opt_present("watch"),
+						title_controller
+					)
+				}
 			}
 		}
 		Err(parse_err) => {
@@ -143,64 +263,169 @@ fn run(title_controller: Option<TerminalTitleController>) -> i32 {
 }
 
 /// Reads an options file and launches a squash operation to optimize it with the
-/// read options.
+/// read options. If `watch` is `true`, the pack is optimized again whenever it changes.
 fn read_options_file_and_squash(
 	options_file_path: Option<&String>,
+	report_file_path: Option<&Path>,
+	watch: bool,
+	mut title_controller: Option<TerminalTitleController>
+) -> i32 {
+	let squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	if watch {
+		watch_and_squash(squash_options, report_file_path, title_controller, |_| ())
+	} else {
+		info!("Options read. Processing pack...");
+
+		squash_and_report(squash_options, report_file_path, title_controller.as_mut())
+			.map_or_else(|exit_code| exit_code, |_| 0)
+	}
+}
+
+/// Reads an options file and serves the output ZIP file generated with those options over
+/// HTTP on the loopback interface, generating it again whenever the pack changes.
+fn read_options_file_and_serve(
+	options_file_path: Option<&String>,
+	report_file_path: Option<&Path>,
+	port: u16,
 	title_controller: Option<TerminalTitleController>
 ) -> i32 {
-	let user_friendly_options_path =
-		options_file_path.map_or("standard input (keyboard input or pipe)", |path| path);
+	let squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
 
-	// Tell the user where are we reading the configuration from
-	info!(
-		"Reading options from {}...{}",
-		user_friendly_options_path,
-		if options_file_path.is_none() {
-			// Newbies are often confused by terms such as "standard input", so try
-			// to point them in the direction of what they probably want to do
-			"\nIf you are not sure what this means, try using an external options file.\
-			 \nPlease check out <https://packsquash.aylas.org/links/Options-files> for examples and more information."
-		} else {
-			""
+	let output_file_path = squash_options.global_options.output_file_path.clone();
+	if output_file_path.as_os_str() == "-" {
+		error!("The output ZIP file must be written to a file to be served");
+
+		return 8;
+	}
+
+	let pack_server = match PackServer::bind(port) {
+		Ok(pack_server) => pack_server,
+		Err(err) => {
+			error!("Couldn't start the HTTP server on port {port}: {err}");
+
+			return 8;
 		}
-	);
+	};
 
-	// Read the TOML configuration data from the specified source
-	let options_string = match if let Some(path) = options_file_path {
-		fs::read_to_string(path)
-	} else {
-		let mut buf = String::new();
-		match io::stdin().read_to_string(&mut buf) {
-			Ok(_) => Ok(buf),
-			Err(err) => Err(err)
+	watch_and_squash(
+		squash_options,
+		report_file_path,
+		title_controller,
+		|output_zip_digests| {
+			// The digests are always sent when the output ZIP file is written successfully
+			let Some(output_zip_digests) = output_zip_digests else {
+				return;
+			};
+
+			match fs::read(&output_file_path) {
+				Ok(output_zip) => {
+					let sha1 = output_zip_digests.sha1_hex();
+					info!(
+						"Serving the generated ZIP file at {} (SHA-1 {sha1})",
+						pack_server.url()
+					);
+
+					pack_server.serve(output_zip, sha1);
+				}
+				Err(err) => error!("Couldn't read the generated ZIP file to serve it: {err}")
+			}
 		}
-	} {
-		Ok(options_string) => options_string,
+	)
+}
+
+/// Watches the pack directory for changes, launching a squash operation to optimize the pack
+/// first, and then after every burst of changes. The specified function is called with the
+/// digests of the output ZIP file after every successful squash operation. This only returns
+/// if an error occurs while watching for changes.
+fn watch_and_squash(
+	squash_options: SquashOptions,
+	report_file_path: Option<&Path>,
+	mut title_controller: Option<TerminalTitleController>,
+	mut on_squash_success: impl FnMut(Option<OutputZipDigests>)
+) -> i32 {
+	/// The time without changes after which a burst of changes is considered to be over.
+	/// Editors and image manipulation programs usually save files in several steps.
+	const DEBOUNCE_TIME: Duration = Duration::from_millis(500);
+
+	let global_options = &squash_options.global_options;
+	if global_options.never_store_squash_times
+		|| global_options.reproducible_output
+		|| global_options.output_file_path.as_os_str() == "-"
+	{
+		warn!(
+			"The previously generated ZIP file will not be reused with the current options, \
+			so every pack file will be processed again after every change"
+		);
+	}
+
+	let pack_watcher = match PackWatcher::new(
+		&squash_options.pack_directory,
+		[
+			Some(&*global_options.output_file_path),
+			global_options.output_manifest_file_path.as_deref(),
+			global_options.optimization_cache_directory.as_deref(),
+			report_file_path
+		]
+		.into_iter()
+		.flatten(),
+		DEBOUNCE_TIME
+	) {
+		Ok(pack_watcher) => pack_watcher,
 		Err(err) => {
-			error!("Couldn't read the options file from {user_friendly_options_path}: {err}");
+			error!("Couldn't watch the pack for changes: {err}");
 
-			return 2;
+			return 7;
 		}
 	};
 
-	// Deserialize the options struct contained in the string
-	let squash_options = match toml::from_str::<SquashOptions>(&options_string) {
-		Ok(squash_options) => squash_options,
-		Err(deserialize_error) => {
-			error!(
-				"An error occurred while parsing the options file from {user_friendly_options_path}: {deserialize_error}"
-			);
+	info!("Options read. Processing pack and watching it for changes...");
 
-			return 3;
+	loop {
+		// Errors were already shown to the user, and they may be fixed by further changes
+		if let Ok(output_zip_digests) = squash_and_report(
+			squash_options.clone(),
+			report_file_path,
+			title_controller.as_mut()
+		) {
+			on_squash_success(output_zip_digests);
 		}
-	};
 
-	info!("Options read. Processing pack...");
+		if let Some(title_controller) = &mut title_controller {
+			title_controller.return_to_idle();
+		}
+
+		info!("Waiting for changes to the pack. Press Ctrl+C to exit");
+
+		match pack_watcher.wait_for_changes() {
+			Ok(()) => info!("Changes detected. Processing pack again..."),
+			Err(err) => {
+				error!("Couldn't watch the pack for changes: {err}");
 
+				return 7;
+			}
+		}
+	}
+}
+
+/// Launches a squash operation to optimize a pack with the specified options, writing a
+/// report file afterwards if requested. On success, the digests of the output ZIP file are
+/// returned, if known. On error, the exit code to return is returned.
+fn squash_and_report(
+	squash_options: SquashOptions,
+	report_file_path: Option<&Path>,
+	title_controller: Option<&mut TerminalTitleController>
+) -> Result<Option<OutputZipDigests>, i32> {
 	let output_file_path = squash_options.global_options.output_file_path.clone();
 	let start_instant = Instant::now();
 
-	squash(squash_options, title_controller).map_or_else(
+	squash(squash_options, report_file_path.is_some(), title_controller).map_or_else(
 		|err| {
 			error!(
 				"Pack processing error: {}{}\n\
@@ -218,10 +443,11 @@ fn read_options_file_and_squash(
 				}
 			);
 
-			128
+			Err(128)
 		},
-		|file_counts| {
+		|mut squash_summary| {
 			let process_time = start_instant.elapsed();
+			let output_zip_digests = squash_summary.output_zip_digests;
 
 			debug!(
 				"{} ({} pack files, {} pack files stored, {}.{:03} s)",
@@ -233,27 +459,457 @@ fn read_options_file_and_squash(
 						metadata.len() as f64 / (1024.0 * 1024.0)
 					))
 				),
-				file_counts.map_or_else(
-					|| Cow::Borrowed("unknown"),
-					|(total_file_count, _)| Cow::Owned(format!("{total_file_count}"))
-				),
-				file_counts.map_or_else(
-					|| Cow::Borrowed("unknown"),
-					|(_, processed_file_count)| Cow::Owned(format!("{processed_file_count}"))
-				),
+				squash_summary.total_file_count,
+				squash_summary.processed_file_count,
 				process_time.as_secs(),
 				process_time.subsec_millis()
 			);
 
-			0
+			if let Some(report_file_path) = report_file_path {
+				if let Err(err) =
+					write_pack_file_report(report_file_path, &mut squash_summary.pack_file_statuses)
+				{
+					error!(
+						"Couldn't write the report file to {}: {err}",
+						report_file_path.display()
+					);
+
+					return Err(4);
+				}
+
+				info!("Report written to {}", report_file_path.display());
+			}
+
+			Ok(output_zip_digests)
 		}
 	)
 }
 
+/// Reads an options file and verifies that the output ZIP file previously generated with
+/// those options can be read back as expected.
+fn read_options_file_and_verify(options_file_path: Option<&String>) -> i32 {
+	let squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	let output_file_path = squash_options.global_options.output_file_path.clone();
+
+	info!(
+		"Options read. Verifying {}...",
+		output_file_path.as_os_str().to_string_lossy()
+	);
+
+	match PackSquasher::new().verify(squash_options) {
+		Ok(verification_report) => {
+			for problem in verification_report.problems() {
+				error!("{problem}");
+			}
+
+			if verification_report.problems().is_empty() {
+				info!(
+					"{} files verified. Every file can be read back as expected",
+					verification_report.verified_file_count()
+				);
+
+				0
+			} else {
+				error!(
+					"{} files verified. {} of them can't be read back as expected",
+					verification_report.verified_file_count(),
+					verification_report.problems().len()
+				);
+
+				5
+			}
+		}
+		Err(err) => {
+			error!("Verification error: {err}");
+
+			128
+		}
+	}
+}
+
+/// Reads an options file and lists the entries of the output ZIP file previously generated
+/// with those options, telling why it would not be reused as a previous ZIP file, if that
+/// is the case.
+fn read_options_file_and_inspect(options_file_path: Option<&String>) -> i32 {
+	let squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	let output_file_path = squash_options.global_options.output_file_path.clone();
+
+	info!(
+		"Options read. Inspecting {}...",
+		output_file_path.as_os_str().to_string_lossy()
+	);
+
+	let inspection_report = match PackSquasher::new().inspect(squash_options) {
+		Ok(inspection_report) => inspection_report,
+		Err(err) => {
+			error!("Inspection error: {err}");
+
+			return 128;
+		}
+	};
+
+	for entry in inspection_report.entries() {
+		println!(
+			"{}: {}, {} bytes ({} bytes uncompressed), CRC-32 {:08x}, data at offset {}{}{}, squash time: {}",
+			entry.path().as_str(),
+			entry.compression_method().as_str(),
+			entry.compressed_size(),
+			entry.uncompressed_size(),
+			entry.crc32(),
+			entry.data_offset(),
+			if entry.deduplicated() {
+				" (deduplicated)"
+			} else {
+				""
+			},
+			if entry.has_source_digest() {
+				", source digest stored"
+			} else {
+				""
+			},
+			entry.squash_time().map_or_else(
+				|err| Cow::Owned(format!("unknown ({err})")),
+				|squash_time| {
+					squash_time
+						.duration_since(UNIX_EPOCH)
+						.ok()
+						.and_then(|squash_time| {
+							UtcDateTime::from_timespec(
+								squash_time.as_secs().try_into().ok()?,
+								squash_time.subsec_nanos()
+							)
+							.ok()
+						})
+						.map_or(Cow::Borrowed("unknown"), |squash_time| {
+							Cow::Owned(format!(
+								"{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+								squash_time.year(),
+								squash_time.month(),
+								squash_time.month_day(),
+								squash_time.hour(),
+								squash_time.minute(),
+								squash_time.second()
+							))
+						})
+				}
+			)
+		);
+	}
+
+	if let Some(err) = inspection_report.previous_zip_parse_error() {
+		error!(
+			"{} entries listed. This ZIP file would not be reused as a previous ZIP file: {err}",
+			inspection_report.entries().len()
+		);
+
+		6
+	} else {
+		info!(
+			"{} entries listed. This ZIP file can be reused as a previous ZIP file",
+			inspection_report.entries().len()
+		);
+
+		0
+	}
+}
+
+/// Reads an options file and shows what a squash operation with those options would do with
+/// every pack file, without processing any of them or writing an output ZIP file.
+fn read_options_file_and_plan(options_file_path: Option<&String>) -> i32 {
+	let mut squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	info!(
+		"Options read. Planning the optimization of {}...",
+		squash_options.pack_directory.as_os_str().to_string_lossy()
+	);
+
+	// Packs distributed as ZIP files are read directly from the archive, like when squashing them
+	let plan =
+		if fs::metadata(&squash_options.pack_directory).is_ok_and(|metadata| metadata.is_file()) {
+			File::open(&squash_options.pack_directory)
+				.and_then(|zip_file| ZipFilesystem::new(BufReader::new(zip_file)))
+				.map_err(PackSquasherError::from)
+				.and_then(|zip_vfs| {
+					squash_options.pack_directory = PathBuf::new();
+					PackSquasher::new().plan(zip_vfs, squash_options)
+				})
+		} else {
+			PackSquasher::new().plan(OsFilesystem, squash_options)
+		};
+
+	let plan = match plan {
+		Ok(plan) => plan,
+		Err(err) => {
+			error!("Planning error: {err}");
+
+			return 128;
+		}
+	};
+
+	let minecraft_quirks = plan.minecraft_quirks();
+	println!(
+		"Minecraft quirks to work around: {}",
+		if minecraft_quirks.is_empty() {
+			Cow::Borrowed("none")
+		} else {
+			Cow::Owned(
+				minecraft_quirks
+					.iter()
+					.map(|quirk| quirk.as_str())
+					.collect::<Vec<_>>()
+					.join(", ")
+			)
+		}
+	);
+	println!("Asset types to match: {}", plan.asset_types().join(", "));
+
+	let mut included_file_count = 0;
+	for pack_file in plan.pack_files() {
+		included_file_count += u64::from(matches!(
+			pack_file.decision(),
+			PackFileDecision::Include | PackFileDecision::ForceInclude
+		));
+
+		println!(
+			"{}: {}{}{}",
+			pack_file.decision().as_str(),
+			pack_file.path().as_str(),
+			pack_file
+				.asset_type()
+				.map_or(Cow::Borrowed(""), |asset_type| Cow::Owned(format!(
+					", asset type: {asset_type}"
+				))),
+			pack_file
+				.file_options_glob()
+				.map_or(Cow::Borrowed(""), |file_options_glob| Cow::Owned(format!(
+					", file options: {file_options_glob}"
+				)))
+		);
+	}
+
+	info!(
+		"{} pack files listed. {included_file_count} of them would be added to the ZIP file",
+		plan.pack_files().len()
+	);
+
+	0
+}
+
+/// Reads an options file and shows how a squash operation with those options would match and
+/// process the pack file at the specified path, relative to the pack directory.
+fn read_options_file_and_explain(relative_path: &str, options_file_path: Option<&String>) -> i32 {
+	let mut squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	info!("Options read. Explaining the processing of {relative_path}...");
+
+	// Packs distributed as ZIP files are read directly from the archive, like when squashing them
+	let explanation =
+		if fs::metadata(&squash_options.pack_directory).is_ok_and(|metadata| metadata.is_file()) {
+			File::open(&squash_options.pack_directory)
+				.and_then(|zip_file| ZipFilesystem::new(BufReader::new(zip_file)))
+				.map_err(PackSquasherError::from)
+				.and_then(|zip_vfs| {
+					squash_options.pack_directory = PathBuf::new();
+					PackSquasher::new().explain(zip_vfs, squash_options, relative_path)
+				})
+		} else {
+			PackSquasher::new().explain(OsFilesystem, squash_options, relative_path)
+		};
+
+	let explanation = match explanation {
+		Ok(explanation) => explanation,
+		Err(err) => {
+			error!("Explanation error: {err}");
+
+			return 128;
+		}
+	};
+
+	let list_or_none = |items: &[&str]| {
+		if items.is_empty() {
+			Cow::Borrowed("none")
+		} else {
+			Cow::Owned(items.join(", "))
+		}
+	};
+
+	let plan = explanation.plan();
+	println!("Path: {}", plan.path().as_str());
+	println!(
+		"Matched asset types: {}",
+		list_or_none(explanation.matched_asset_types())
+	);
+	println!(
+		"Asset types filtered out by the asset type mask: {}",
+		list_or_none(explanation.masked_asset_types())
+	);
+	println!(
+		"Custom pack file processor matched: {}",
+		if explanation.custom_pack_file_processor_matched() {
+			"yes"
+		} else {
+			"no"
+		}
+	);
+	println!(
+		"Matched file options: {}",
+		list_or_none(
+			&explanation
+				.matched_file_options_globs()
+				.iter()
+				.map(String::as_str)
+				.collect::<Vec<_>>()
+		)
+	);
+	println!(
+		"Used file options: {}",
+		plan.file_options_glob().unwrap_or("none (defaults)")
+	);
+	println!("Decision: {}", plan.decision().as_str());
+	println!("Asset type: {}", plan.asset_type().unwrap_or("none"));
+	println!("Processor: {}", explanation.processor().unwrap_or("none"));
+	match explanation
+		.effective_file_options()
+		.map(|file_options| file_options.to_toml_string())
+	{
+		Some(Ok(file_options)) => print!("Effective settings:\n{file_options}"),
+		Some(Err(err)) => {
+			error!("Effective settings serialization error: {err}");
+
+			return 128;
+		}
+		None => println!("Effective settings: none")
+	}
+
+	0
+}
+
+/// Reads an options file and prints the options that a squash operation with those options
+/// would actually use, after automatic detections, in TOML format.
+fn read_options_file_and_print_resolved_options(options_file_path: Option<&String>) -> i32 {
+	let mut squash_options = match read_options_file(options_file_path) {
+		Ok(squash_options) => squash_options,
+		Err(exit_code) => return exit_code
+	};
+
+	info!(
+		"Options read. Resolving the options for {}...",
+		squash_options.pack_directory.as_os_str().to_string_lossy()
+	);
+
+	// Packs distributed as ZIP files are read directly from the archive, like when squashing them
+	let resolved_options =
+		if fs::metadata(&squash_options.pack_directory).is_ok_and(|metadata| metadata.is_file()) {
+			File::open(&squash_options.pack_directory)
+				.and_then(|zip_file| ZipFilesystem::new(BufReader::new(zip_file)))
+				.map_err(PackSquasherError::from)
+				.and_then(|zip_vfs| {
+					squash_options.pack_directory = PathBuf::new();
+					PackSquasher::new().resolve_options(zip_vfs, squash_options)
+				})
+		} else {
+			PackSquasher::new().resolve_options(OsFilesystem, squash_options)
+		};
+
+	match resolved_options.and_then(|resolved_options| {
+		resolved_options
+			.to_toml_string()
+			.map_err(PackSquasherError::from)
+	}) {
+		Ok(resolved_options) => {
+			print!("{resolved_options}");
+
+			0
+		}
+		Err(err) => {
+			error!("Options resolution error: {err}");
+
+			128
+		}
+	}
+}
+
+/// Reads the options file at the specified path, or from the standard input if no path is
+/// specified. If something goes wrong, the error is logged and the exit code to return is
+/// returned.
+fn read_options_file(options_file_path: Option<&String>) -> Result<SquashOptions, i32> {
+	let user_friendly_options_path =
+		options_file_path.map_or("standard input (keyboard input or pipe)", |path| path);
+
+	// Tell the user where are we reading the configuration from
+	info!(
+		"Reading options from {}...{}",
+		user_friendly_options_path,
+		if options_file_path.is_none() {
+			// Newbies are often confused by terms such as "standard input", so try
+			// to point them in the direction of what they probably want to do
+			"\nIf you are not sure what this means, try using an external options file.\
+			 \nPlease check out <https://packsquash.aylas.org/links/Options-files> for examples and more information."
+		} else {
+			""
+		}
+	);
+
+	// Read the TOML configuration data from the specified source
+	let options_string = match if let Some(path) = options_file_path {
+		fs::read_to_string(path)
+	} else {
+		let mut buf = String::new();
+		match io::stdin().read_to_string(&mut buf) {
+			Ok(_) => Ok(buf),
+			Err(err) => Err(err)
+		}
+	} {
+		Ok(options_string) => options_string,
+		Err(err) => {
+			error!("Couldn't read the options file from {user_friendly_options_path}: {err}");
+
+			return Err(2);
+		}
+	};
+
+	// Deserialize the options struct contained in the string
+	let squash_options = match toml::from_str::<SquashOptions>(&options_string) {
+		Ok(squash_options) => squash_options,
+		Err(deserialize_error) => {
+			error!(
+				"An error occurred while parsing the options file from {user_friendly_options_path}: {deserialize_error}"
+			);
+
+			return Err(3);
+		}
+	};
+
+	Ok(squash_options)
+}
+
+/// A summary of a finished squash operation, gathered from its status updates.
+struct SquashSummary {
+	total_file_count: u64,
+	processed_file_count: u64,
+	pack_file_statuses: Vec<PackFileStatus>,
+	output_zip_digests: Option<OutputZipDigests>
+}
+
 fn squash(
-	squash_options: SquashOptions,
-	mut title_controller: Option<TerminalTitleController>
-) -> Result<Option<(u64, u64)>, PackSquasherError> {
+	mut squash_options: SquashOptions,
+	collect_pack_file_statuses: bool,
+	mut title_controller: Option<&mut TerminalTitleController>
+) -> Result<SquashSummary, PackSquasherError> {
 	let (sender, mut receiver) = channel(64);
 
 	// Move on to the "processing" title phase
@@ -268,13 +924,15 @@ fn squash(
 		.enable_time()
 		.build()?;
 
-	let cli_update_task = runtime.spawn(async move {
+	let cli_update_task = async move {
 		/// The maximum interval of time between two progress ticks of the title. Used to assure
 		/// the user that progress is being made even when something takes a while to optimize.
 		const PROGRESS_TICK_INTERVAL: Duration = Duration::from_secs(1);
 
 		let mut total_file_count = 0;
 		let mut processed_file_count = 0;
+		let mut pack_file_statuses = vec![];
+		let mut output_zip_digests = None;
 		let progress_tick_timer = sleep(PROGRESS_TICK_INTERVAL);
 
 		tokio::pin!(progress_tick_timer);
@@ -313,6 +971,10 @@ fn squash(
 								}
 							};
 
+							if collect_pack_file_statuses {
+								pack_file_statuses.push(pack_file_status);
+							}
+
 							if let Some(title_controller) = &mut title_controller {
 								title_controller.advance_and_show();
 
@@ -331,6 +993,15 @@ fn squash(
 								title_controller.show();
 							}
 						}
+						PackSquasherStatus::ZipDigests(digests) => {
+							info!(
+								"Output ZIP file digests: SHA-1 {}, SHA-256 {}",
+								digests.sha1_hex(),
+								digests.sha256_hex()
+							);
+
+							output_zip_digests = Some(digests);
+						}
 						PackSquasherStatus::Notice(notice) => info!("{notice}"),
 						PackSquasherStatus::Warning(warning) => match warning {
 							PackSquasherWarning::UnusablePreviousZip(err) => warn!(
@@ -352,6 +1023,28 @@ fn squash(
 								"The number of pack files that will be processed in parallel was reduced to avoid \
 								exceeding open file descriptor limits. Please increase the open file descriptor \
 								limit for optimum performance, or decrease the number of threads"),
+							PackSquasherWarning::OptimizationCacheWriteError(err) => warn!(
+								"A processed pack file could not be stored in the optimization cache: {err}. \
+								It will have to be processed again in future runs"),
+							PackSquasherWarning::UnreferencedAsset(path) => warn!(
+								"{}: No other pack file refers to this asset, so it may be unused. \
+								If it is referred to by code or mods, add it to the unreferenced assets allowlist",
+								path.as_str()
+							),
+							PackSquasherWarning::DanglingReference { referring_file, referred_path } => warn!(
+								"{}: Refers to {referred_path}, which does not exist in the pack",
+								referring_file.as_str()
+							),
+							PackSquasherWarning::InvalidPackFilePath { path, problem } => {
+								warn!("{}: {problem}", path.as_str())
+							}
+							PackSquasherWarning::MissingLanguageKeys { path, missing_keys } => {
+								warn!(
+									"{}: Does not define the keys {}, which will be shown untranslated",
+									path.as_str(),
+									missing_keys.join(", ")
+								)
+							}
 							_ => unimplemented!()
 						},
 						_ => unimplemented!()
@@ -376,30 +1069,61 @@ fn squash(
 			}
 		}
 
-		(total_file_count, processed_file_count)
-	});
+		SquashSummary {
+			total_file_count,
+			processed_file_count,
+			pack_file_statuses,
+			output_zip_digests
+		}
+	};
 
 	// Squash the pack! This blocks until the operation is complete, so we can't run it in this thread
-	let packsquasher = runtime
-		.spawn_blocking(|| PackSquasher::new().run(OsFilesystem, squash_options, Some(sender)));
+	let packsquasher = runtime.spawn_blocking(|| {
+		// Packs distributed as ZIP files are read directly from the archive, without extracting them
+		if fs::metadata(&squash_options.pack_directory).is_ok_and(|metadata| metadata.is_file()) {
+			let zip_vfs =
+				ZipFilesystem::new(BufReader::new(File::open(&squash_options.pack_directory)?))?;
+			squash_options.pack_directory = PathBuf::new();
+
+			run_pack_squasher(zip_vfs, squash_options, sender)
+		} else {
+			run_pack_squasher(OsFilesystem, squash_options, sender)
+		}
+	});
 
 	runtime.block_on(async {
+		// Update the display until the PackSquasher finishes, which closes the status channel.
+		// The title controller is borrowed, so this must run on this thread instead of being
+		// spawned as a task
+		let squash_summary = cli_update_task.await;
+
 		// Wait for completion. Unwrap the handle because any panic in the thread is fatal anyway,
 		// and we should propagate it
-		match packsquasher.await.unwrap() {
-			Ok(()) => Ok(cli_update_task.await.ok()),
-			Err(err) => Err(err)
-		}
+		packsquasher.await.unwrap().map(|()| squash_summary)
 	})
 }
 
-/// Prints `PackSquash` version information to the standard output stream.
-fn print_version_information(verbose: bool) {
+/// Runs a [`PackSquasher`] on the specified virtual filesystem, writing the output ZIP file
+/// to the standard output stream if the output file path is `-`.
+fn run_pack_squasher(
+	vfs: impl VirtualFileSystem + 'static,
+	squash_options: SquashOptions,
+	sender: Sender<PackSquasherStatus>
+) -> Result<(), PackSquasherError> {
+	if squash_options.global_options.output_file_path.as_os_str() == "-" {
+		PackSquasher::new().run_with_output(vfs, squash_options, tokio::io::stdout(), Some(sender))
+	} else {
+		PackSquasher::new().run(vfs, squash_options, Some(sender))
+	}
+}
+
+/// Returns `PackSquash` version information, ready to be printed.
+fn version_information(verbose: bool) -> String {
 	let build_date_time =
 		UtcDateTime::from_timespec(env!("PACKSQUASH_BUILD_TIMESTAMP").parse().unwrap(), 0).unwrap();
 
-	println!(
-		"PackSquash {} ({}, {}) for {}",
+	let mut version_information = format!(
+		"PackSquash {} ({}, {}) for {}\n{}\n\n",
 		env!("PACKSQUASH_BUILD_VERSION"),
 		env!("CARGO_PROFILE"),
 		format_args!(
@@ -408,32 +1132,36 @@ fn print_version_information(verbose: bool) {
 			build_date_time.month(),
 			build_date_time.month_day()
 		),
-		env!("CARGO_TARGET_TRIPLE")
+		env!("CARGO_TARGET_TRIPLE"),
+		env!("CARGO_PKG_DESCRIPTION")
 	);
-	println!("{}", env!("CARGO_PKG_DESCRIPTION"));
-	println!();
-
-	if verbose {
-		println!("Copyright (C) {}", env!("CARGO_PKG_AUTHORS"));
-		println!();
-		println!("This program is free software: you can redistribute it and/or modify");
-		println!("it under the terms of the GNU Affero General Public License as");
-		println!("published by the Free Software Foundation, either version 3 of the");
-		println!("License, or (at your option) any later version.");
-		println!();
-		println!("This program is distributed free of charge in the hope that it will");
-		println!("be useful, but WITHOUT ANY WARRANTY; without even the implied warranty");
-		println!("of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the");
-		println!("GNU Affero General Public License for more details.");
-		println!();
-		println!("You should have received a copy of the GNU Affero General Public License");
-		println!("along with this program. If not, see <https://www.gnu.org/licenses/>.");
+
+	version_information.push_str(if verbose {
+		concat!(
+			"Copyright (C) ",
+			env!("CARGO_PKG_AUTHORS"),
+			"\n\n",
+			"This program is free software: you can redistribute it and/or modify\n",
+			"it under the terms of the GNU Affero General Public License as\n",
+			"published by the Free Software Foundation, either version 3 of the\n",
+			"License, or (at your option) any later version.\n\n",
+			"This program is distributed free of charge in the hope that it will\n",
+			"be useful, but WITHOUT ANY WARRANTY; without even the implied warranty\n",
+			"of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the\n",
+			"GNU Affero General Public License for more details.\n\n",
+			"You should have received a copy of the GNU Affero General Public License\n",
+			"along with this program. If not, see <https://www.gnu.org/licenses/>.\n"
+		)
 	} else {
-		println!("This program comes with ABSOLUTELY NO WARRANTY.");
-		println!("This is free software, and you are welcome to redistribute it");
-		println!("under certain conditions. Use the -v command line switch for");
-		println!("more details about these conditions.");
-	}
+		concat!(
+			"This program comes with ABSOLUTELY NO WARRANTY.\n",
+			"This is free software, and you are welcome to redistribute it\n",
+			"under certain conditions. Use the -v command line switch for\n",
+			"more details about these conditions.\n"
+		)
+	});
+
+	version_information
 }
 
 /// Initializes the logging of the application, responsible for showing to the user relevant
@@ -0,0 +1,136 @@
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::Path
+};
+
+use packsquash::PackFileStatus;
+use serde_json::{Map, Value};
+
+/// Writes a machine-readable report with a record for every specified pack file status to
+/// the specified path. The report is written in CSV format if the path has a `csv` extension,
+/// and in JSON format otherwise.
+///
+/// Records are sorted by pack file path, so that reports of different runs over the same
+/// pack can be easily compared.
+pub fn write_pack_file_report(
+	path: &Path,
+	pack_file_statuses: &mut [PackFileStatus]
+) -> Result<(), io::Error> {
+	pack_file_statuses.sort_unstable_by(|a, b| a.path().as_str().cmp(b.path().as_str()));
+
+	let mut report_file = BufWriter::new(File::create(path)?);
+
+	if path
+		.extension()
+		.is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+	{
+		write_csv(&mut report_file, pack_file_statuses)?;
+	} else {
+		serde_json::to_writer_pretty(
+			&mut report_file,
+			&pack_file_statuses
+				.iter()
+				.map(|pack_file_status| {
+					FIELD_NAMES
+						.into_iter()
+						.map(str::to_string)
+						.zip(record_fields(pack_file_status))
+						.collect::<Map<_, _>>()
+				})
+				.collect::<Vec<_>>()
+		)?;
+		writeln!(report_file)?;
+	}
+
+	report_file.flush()
+}
+
+/// Writes the records of the specified pack file statuses as a CSV table, preceded by a
+/// header row with the field names.
+fn write_csv(sink: &mut impl Write, pack_file_statuses: &[PackFileStatus]) -> Result<(), io::Error> {
+	let mut write_row = |fields: &mut dyn Iterator<Item = String>| {
+		let mut first_field = true;
+
+		for field in fields {
+			if !first_field {
+				sink.write_all(b",")?;
+			}
+			first_field = false;
+
+			if field.contains([',', '"', '\n', '\r']) {
+				write!(sink, "\"{}\"", field.replace('"', "\"\""))?;
+			} else {
+				sink.write_all(field.as_bytes())?;
+			}
+		}
+
+		// RFC 4180 mandates CRLF line terminators
+		sink.write_all(b"\r\n")
+	};
+
+	write_row(&mut FIELD_NAMES.into_iter().map(str::to_string))?;
+
+	for pack_file_status in pack_file_statuses {
+		write_row(
+			&mut record_fields(pack_file_status)
+				.into_iter()
+				.map(|value| match value {
+					Value::Null => String::new(),
+					Value::String(value) => value,
+					value => value.to_string()
+				})
+		)?;
+	}
+
+	Ok(())
+}
+
+/// The names of the fields of every report record, in the order they are returned by
+/// [`record_fields`].
+const FIELD_NAMES: [&str; 13] = [
+	"path",
+	"skipped",
+	"optimization_strategy",
+	"optimization_error",
+	"asset_type",
+	"file_options_glob",
+	"copied_from_previous_zip",
+	"copied_from_optimization_cache",
+	"input_size",
+	"processed_size",
+	"compressed_size",
+	"compression_method",
+	"processing_time_seconds"
+];
+
+/// Returns the fields of the report record for the specified pack file status. Fields that
+/// only make sense for pack files that were added to the output ZIP file are null for the
+/// rest.
+fn record_fields(pack_file_status: &PackFileStatus) -> [Value; 13] {
+	let report = pack_file_status.report();
+
+	[
+		pack_file_status.path().as_str().into(),
+		pack_file_status.skipped().into(),
+		pack_file_status.optimization_strategy().into(),
+		pack_file_status.optimization_error().into(),
+		report.map(|report| report.asset_type()).into(),
+		report.and_then(|report| report.file_options_glob()).into(),
+		report
+			.map(|report| report.copied_from_previous_zip())
+			.into(),
+		report
+			.map(|report| report.copied_from_optimization_cache())
+			.into(),
+		report.map(|report| report.input_size()).into(),
+		report.map(|report| report.processed_size()).into(),
+		report.map(|report| report.compressed_size()).into(),
+		report
+			.map(|report| report.compression_method().as_str())
+			.into(),
+		report
+			.map(|report| report.processing_time().as_secs_f64())
+			.into()
+	]
+}
@@ -0,0 +1,197 @@
+//! A minimal HTTP server that serves the most recently generated output ZIP file on the
+//! loopback interface, meant for playtesting packs with local Minecraft clients and servers.
+
+use std::{
+	io::{self, BufRead, BufReader, Write},
+	net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+	sync::{
+		Arc, RwLock,
+		atomic::{AtomicUsize, Ordering}
+	},
+	thread,
+	time::Duration
+};
+
+use log::debug;
+
+/// The path of the stable URL the output ZIP file is served at.
+const PACK_URL_PATH: &str = "/pack.zip";
+
+/// The maximum size of the request line and headers of a request. This server ignores
+/// request headers, so this is only meant to bound the resources a client can use.
+const MAX_REQUEST_HEAD_SIZE: u64 = 16 * 1024;
+
+/// The maximum number of connections that are handled at the same time. Further connections
+/// are answered with a temporary error, so that clients can not exhaust system threads.
+const MAX_CONCURRENT_CONNECTIONS: usize = 16;
+
+/// The maximum time a read or write operation on a connection may take before the connection
+/// is closed, so that stalled clients do not hold on to a connection slot forever.
+const CONNECTION_IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An output ZIP file that is being served, along with its metadata.
+struct ServedPack {
+	data: Vec<u8>,
+	sha1: String
+}
+
+/// A minimal HTTP server that serves the most recently generated output ZIP file on the
+/// loopback interface, in a background thread.
+pub struct PackServer {
+	address: SocketAddr,
+	served_pack: Arc<RwLock<Option<Arc<ServedPack>>>>
+}
+
+impl PackServer {
+	/// Starts serving on the specified loopback interface port. Until a ZIP file to serve is
+	/// set with [`serve`](Self::serve), requests are answered with a temporary error.
+	pub fn bind(port: u16) -> io::Result<Self> {
+		let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+		let address = listener.local_addr()?;
+		let served_pack = Arc::new(RwLock::new(None));
+
+		let listener_served_pack = Arc::clone(&served_pack);
+		thread::Builder::new()
+			.name("packsquash-server".into())
+			.spawn(move || {
+				let active_connections = Arc::new(AtomicUsize::new(0));
+
+				for stream in listener.incoming().flatten() {
+					if let Err(err) = stream
+						.set_read_timeout(Some(CONNECTION_IO_TIMEOUT))
+						.and_then(|()| stream.set_write_timeout(Some(CONNECTION_IO_TIMEOUT)))
+					{
+						debug!("Error while setting up a connection: {err}");
+						continue;
+					}
+
+					let Some(connection_slot) = ConnectionSlot::acquire(&active_connections) else {
+						// The response is small enough to fit in the socket send buffer, so
+						// this does not block the listener thread
+						if let Err(err) = write_error_response(&stream, "503 Service Unavailable") {
+							debug!("Error while rejecting a connection: {err}");
+						}
+						continue;
+					};
+
+					let served_pack = listener_served_pack.read().unwrap().clone();
+
+					// Handle every connection in its own thread, so that slow clients do not
+					// block others
+					thread::spawn(move || {
+						let _connection_slot = connection_slot;
+
+						if let Err(err) = handle_connection(stream, served_pack.as_deref()) {
+							debug!("Error while serving a request: {err}");
+						}
+					});
+				}
+			})?;
+
+		Ok(Self {
+			address,
+			served_pack
+		})
+	}
+
+	/// Returns the stable URL the served ZIP file can be downloaded from.
+	pub fn url(&self) -> String {
+		format!("http://{}{PACK_URL_PATH}", self.address)
+	}
+
+	/// Sets the ZIP file to serve from now on, which has the specified SHA-1 digest, in
+	/// lowercase hexadecimal representation. Requests that are being answered keep getting
+	/// the previous ZIP file.
+	pub fn serve(&self, data: Vec<u8>, sha1: String) {
+		*self.served_pack.write().unwrap() = Some(Arc::new(ServedPack { data, sha1 }));
+	}
+}
+
+/// One of the [`MAX_CONCURRENT_CONNECTIONS`] connections that may be handled at the same
+/// time. The slot is released when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+	/// Takes a free connection slot out of the specified count of active connections, if
+	/// there is any.
+	fn acquire(active_connections: &Arc<AtomicUsize>) -> Option<Self> {
+		active_connections
+			.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+				(count < MAX_CONCURRENT_CONNECTIONS).then_some(count + 1)
+			})
+			.ok()
+			.map(|_| Self(Arc::clone(active_connections)))
+	}
+}
+
+impl Drop for ConnectionSlot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::AcqRel);
+	}
+}
+
+/// Reads a HTTP request from the specified stream, and answers it with the specified served
+/// output ZIP file, if any. The connection is closed after the response is sent.
+fn handle_connection(stream: TcpStream, served_pack: Option<&ServedPack>) -> io::Result<()> {
+	let mut request_reader = BufReader::new(io::Read::take(&stream, MAX_REQUEST_HEAD_SIZE));
+
+	let mut request_line = String::new();
+	request_reader.read_line(&mut request_line)?;
+
+	// Skip the request headers, which are not relevant for us. Do not bother reading the body
+	// of requests that may have one: they are answered with an error anyway
+	let mut header_line = String::new();
+	while request_reader.read_line(&mut header_line)? > 2 {
+		header_line.clear();
+	}
+
+	let mut request_line_parts = request_line.split_ascii_whitespace();
+	let (method, target) = (request_line_parts.next(), request_line_parts.next());
+	let path = target.map(|target| target.split_once('?').map_or(target, |(path, _)| path));
+
+	let mut stream = &stream;
+	match (method, path, served_pack) {
+		(Some(method @ ("GET" | "HEAD")), Some(PACK_URL_PATH), Some(served_pack)) => {
+			write!(
+				stream,
+				"HTTP/1.1 200 OK\r\n\
+				Content-Type: application/zip\r\n\
+				Content-Length: {}\r\n\
+				ETag: \"{sha1}\"\r\n\
+				X-Checksum-SHA1: {sha1}\r\n\
+				Cache-Control: no-cache\r\n\
+				Connection: close\r\n\r\n",
+				served_pack.data.len(),
+				sha1 = served_pack.sha1
+			)?;
+
+			if method == "GET" {
+				stream.write_all(&served_pack.data)?;
+			}
+		}
+		(Some("GET" | "HEAD"), Some(PACK_URL_PATH), None) => {
+			write_error_response(stream, "503 Service Unavailable")?;
+		}
+		(Some("GET" | "HEAD"), _, _) => {
+			write_error_response(stream, "404 Not Found")?;
+		}
+		(Some(_), _, _) => {
+			write_error_response(stream, "405 Method Not Allowed")?;
+		}
+		(None, _, _) => {
+			write_error_response(stream, "400 Bad Request")?;
+		}
+	}
+
+	stream.flush()
+}
+
+/// Writes a response with the specified status and no body to the specified stream.
+fn write_error_response(mut stream: &TcpStream, status: &str) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {status}\r\n\
+		Content-Length: 0\r\n\
+		Connection: close\r\n\r\n"
+	)
+}
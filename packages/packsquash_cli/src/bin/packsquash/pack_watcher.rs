@@ -0,0 +1,104 @@
+//! Watches a pack for changes, so that it can be squashed again as soon as it is modified.
+
+use std::{
+	path::{self, Path, PathBuf},
+	sync::mpsc::{self, Receiver, RecvTimeoutError},
+	time::Duration
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Watches the files of a pack for changes, using the most efficient mechanism available in
+/// the current platform (for example, inotify on Linux), and groups bursts of changes together.
+pub struct PackWatcher {
+	/// The underlying watcher. It must be kept alive to keep receiving change events.
+	_watcher: RecommendedWatcher,
+	event_receiver: Receiver<notify::Result<Event>>,
+	ignored_paths: Vec<PathBuf>,
+	debounce_time: Duration
+}
+
+impl PackWatcher {
+	/// Starts watching the specified pack path, which may be a directory or a ZIP file, for
+	/// changes. Changes to the specified ignored paths, such as the output ZIP file, and to
+	/// anything below them if they are directories, are not taken into account. Changes that
+	/// happen less than the specified debounce time apart are considered to belong to the same
+	/// burst.
+	pub fn new<P: AsRef<Path>>(
+		pack_path: &Path,
+		ignored_paths: impl IntoIterator<Item = P>,
+		debounce_time: Duration
+	) -> notify::Result<Self> {
+		// Make paths absolute, so that they can be compared with the paths of events
+		let watched_path = path::absolute(pack_path)?;
+		let ignored_paths = ignored_paths
+			.into_iter()
+			.map(path::absolute)
+			.collect::<Result<_, _>>()?;
+
+		let (event_sender, event_receiver) = mpsc::channel();
+		let mut watcher = notify::recommended_watcher(event_sender)?;
+		watcher.watch(&watched_path, RecursiveMode::Recursive)?;
+
+		Ok(Self {
+			_watcher: watcher,
+			event_receiver,
+			ignored_paths,
+			debounce_time
+		})
+	}
+
+	/// Blocks the current thread until a burst of changes to the pack ends. Changes that
+	/// happened since the last call to this method end this wait as soon as possible.
+	///
+	/// The changed paths are not returned, as a pack is always processed again as a whole:
+	/// squash operations rely on the previously generated ZIP file to skip unchanged files.
+	pub fn wait_for_changes(&self) -> notify::Result<()> {
+		// Wait for the first relevant change to begin a burst
+		loop {
+			let event = self
+				.event_receiver
+				.recv()
+				.map_err(|_| watcher_stopped_error())?;
+			if self.is_relevant_change(&event?) {
+				break;
+			}
+		}
+
+		// Keep waiting until no changes happen for the debounce time
+		loop {
+			match self.event_receiver.recv_timeout(self.debounce_time) {
+				Ok(event) => {
+					event?;
+				}
+				Err(RecvTimeoutError::Timeout) => break Ok(()),
+				Err(RecvTimeoutError::Disconnected) => break Err(watcher_stopped_error())
+			}
+		}
+	}
+
+	/// Checks whether the specified event signals a change to a path that is not ignored.
+	fn is_relevant_change(&self, event: &Event) -> bool {
+		// Some events may have been lost, so assume that anything may have changed
+		if event.need_rescan() {
+			return true;
+		}
+
+		// Reading files, which PackSquash itself does, does not change them
+		if matches!(event.kind, EventKind::Access(_)) {
+			return false;
+		}
+
+		event.paths.iter().any(|path| {
+			!self
+				.ignored_paths
+				.iter()
+				.any(|ignored_path| path.starts_with(ignored_path))
+		})
+	}
+}
+
+/// Returns the error that signals that the underlying watcher stopped sending events.
+fn watcher_stopped_error() -> notify::Error {
+	notify::Error::generic("The pack watcher stopped unexpectedly")
+}
@@ -70,6 +70,13 @@ impl TerminalTitleController {
 		self.show();
 	}
 
+	/// Goes back to the title of the idle title phase, and then shows that title.
+	pub fn return_to_idle(&mut self) {
+		self.current_title = TerminalTitle::Idle;
+
+		self.show();
+	}
+
 	/// Moves on to the first title of the next title phase. The new title is not
 	/// shown; call [`TerminalTitleController::show`] to show it.
 	pub fn next_title_phase(&mut self) {
@@ -0,0 +1,74 @@
+//! Checks that the output ZIP file can be written to the standard output stream of the CLI.
+
+use std::{
+	fs,
+	io::{Cursor, Write},
+	path::Path,
+	process::{Command, Stdio}
+};
+
+use packsquash::vfs::{IteratorTraversalOptions, VirtualFileSystem, zip_fs::ZipFilesystem};
+
+static UNEXPECTED_IO_FAILURE: &str = "I/O operations are assumed not to fail during tests";
+
+#[test]
+fn standard_output_only_carries_the_output_zip_file() {
+	let pack_directory = tempfile::tempdir().expect(UNEXPECTED_IO_FAILURE);
+	fs::write(
+		pack_directory.path().join("pack.mcmeta"),
+		r#"{ "pack": { "pack_format": 7, "description": "Test pack" } }"#
+	)
+	.expect(UNEXPECTED_IO_FAILURE);
+
+	let mut packsquash = Command::new(env!("CARGO_BIN_EXE_packsquash"))
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.expect("The PackSquash CLI should be launched successfully");
+
+	packsquash
+		.stdin
+		.take()
+		.unwrap()
+		.write_all(
+			format!(
+				"pack_directory = '{}'\noutput_file_path = '-'\n",
+				pack_directory.path().display()
+			)
+			.as_bytes()
+		)
+		.expect(UNEXPECTED_IO_FAILURE);
+
+	let output = packsquash.wait_with_output().expect(UNEXPECTED_IO_FAILURE);
+
+	assert!(
+		output.status.success(),
+		"The pack should be squashed successfully. Standard error stream contents:\n{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+	assert!(
+		output.stdout.starts_with(b"PK\x03\x04"),
+		"The standard output stream should begin with the first ZIP file entry"
+	);
+
+	let output_zip = ZipFilesystem::new(Cursor::new(output.stdout))
+		.expect("The standard output stream should contain a valid ZIP file");
+	let output_zip_files = output_zip
+		.file_iterator(
+			Path::new(""),
+			IteratorTraversalOptions {
+				ignore_system_and_hidden_files: false
+			}
+		)
+		.map(|entry| {
+			entry
+				.expect(UNEXPECTED_IO_FAILURE)
+				.relative_path
+				.as_str()
+				.to_string()
+		})
+		.collect::<Vec<_>>();
+
+	assert_eq!(output_zip_files, ["pack.mcmeta"]);
+}